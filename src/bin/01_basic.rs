@@ -11,15 +11,18 @@ cargo run --bin 01_basic
 */
 
 use serde_json::{json, Value};
+use utils::rpc_url;
 
 #[tokio::main]
 async fn main() {
     let client = reqwest::Client::new();
+    // The node to talk to (localhost, unless the POLKADOT_RPC_URL environment variable is set):
+    let url = rpc_url();
 
     // See https://www.jsonrpc.org/specification for more information on
     // the JSON RPC 2.0 format that we use here to talk to nodes.
     let res = client
-        .post("http://localhost:9933")
+        .post(&url)
         .json(&json! {{
             "id": 1,
             "jsonrpc": "2.0",
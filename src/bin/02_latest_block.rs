@@ -7,14 +7,16 @@ cargo run --bin 02_latest_block
 */
 
 use serde_json::{json, Value};
+use utils::rpc_url;
 
 #[tokio::main]
 async fn main() {
     // find the hash of the latest block, so that we can query for
     // details about the block using it.
     let client = reqwest::Client::new();
+    let url = rpc_url();
     let res = client
-        .post("http://localhost:9933")
+        .post(&url)
         .json(&json! {{
             "id": 1,
             "jsonrpc": "2.0",
@@ -32,7 +34,7 @@ async fn main() {
     // Get some details, passing the hash we obtained above as a parameter
     // to the JSON RPC call.
     let res = client
-        .post("http://localhost:9933")
+        .post(&url)
         .json(&json! {{
             "id": 1,
             "jsonrpc": "2.0",
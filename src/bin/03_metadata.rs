@@ -5,6 +5,7 @@ We can get the metadata for a chain, which describes, for each module (pallet) t
   a call maye exists to transfer balance between accounts),
 - Consts that are relevant to the module (for the balances module, we may have an
   ExistentialDeposit value for instance; the minimum deposit that can exist in an account).
+  The `constants` command of `polkadot-interact` decodes and lists these.
 - Events that can be emitted (eg, "a transfer has happened from X to Y with balance B"),
 - Storage used by the module (eg the balances module keeps a hash of account ID to balance info).
 
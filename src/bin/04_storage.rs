@@ -10,14 +10,24 @@ cargo run --bin 04_storage
 */
 
 use parity_scale_codec::Decode;
-use sp_core::crypto::{Ss58AddressFormatRegistry, Ss58Codec};
 use sp_core::{crypto::AccountId32, hashing};
 use sp_keyring::AccountKeyring;
 use std::convert::TryInto;
-use utils::rpc_to_localhost;
+use utils::{
+    format_address, get_account_balances, get_metadata, get_ss58_prefix, get_token_info, polkadot,
+    rpc_to_localhost, rpc_url,
+};
 
 #[tokio::main]
 async fn main() {
+    let url = rpc_url();
+
+    // Addresses are displayed differently on each chain (see below). The runtime tells us which
+    // format to use via the `System.SS58Prefix` constant in the metadata (and failing that, the
+    // node hands it back from the "system_properties" RPC call):
+    let metadata = get_metadata(&url).await.unwrap();
+    let ss58_prefix = get_ss58_prefix(&url, &metadata).await.unwrap();
+
     {
         // We can look in metadata to see what's been stored. We note the "storage prefix"
         // and then "name" of the item we're interested in. First, we'll find out the total
@@ -60,6 +70,12 @@ async fn main() {
 
         println!("Total issued encoded response: {}", result_hex_str);
         println!("Total issued: {}", total_issued);
+
+        // That number is in the smallest unit of the token (on Polkadot, a "planck"). The node
+        // can tell us how many decimal places the token has, and its symbol, so that we can
+        // show the amount in the same way as a UI would (eg "1.5 DOT"):
+        let token = get_token_info(&url).await.unwrap();
+        println!("Total issued: {}", token.format_amount(total_issued));
     }
 
     {
@@ -101,11 +117,7 @@ async fn main() {
 
             // The address you see is basically the account ID + a version (ie "this is a polkadot address")
             // encoded into SS58 format (see https://github.com/paritytech/substrate/wiki/External-Address-Format-(SS58)):
-            println!(
-                "{}",
-                address
-                    .to_ss58check_with_version(Ss58AddressFormatRegistry::PolkadotAccount.into())
-            );
+            println!("{}", format_address(&address, ss58_prefix));
         }
     }
 
@@ -134,7 +146,9 @@ async fn main() {
         // this is what a `T::AccountId` is in Polkadot, so we're golden.
         //
         // FYI, if we see an address in the UI, we can convert from that SS568 encoding
-        // into an AccountId by running `AccountId32::from_ss58check("the-address").unwrap();`.
+        // into an AccountId by running `AccountId32::from_ss58check("the-address").unwrap();`,
+        // or `utils::parse_address("the-address")`, which also accepts hex account IDs and tells
+        // us which prefix the address was encoded with.
         let bobs_account_id = AccountKeyring::Bob.to_account_id();
 
         // if we like, we can print out Bobs address. This is basically the public address
@@ -142,8 +156,7 @@ async fn main() {
         // https://github.com/paritytech/substrate/wiki/External-Address-Format-(SS58)):
         println!(
             "\nBobs address: {}",
-            bobs_account_id
-                .to_ss58check_with_version(Ss58AddressFormatRegistry::PolkadotAccount.into())
+            format_address(&bobs_account_id, ss58_prefix)
         );
 
         // Hash things:
@@ -181,11 +194,60 @@ async fn main() {
         // type AccountData = pallet_balances::AccountData<Balance>;
         // ```
         //
-        // Well, `Nonce` is just an alias for u32, and Balance is just an alias for `u128`, so we end up
-        // wanting to decode our result into this type to read it:
-        type PolkadotAccountInfo =
-            pallet_system::AccountInfo<u32, pallet_balances::AccountData<u128>>;
-        let account_info = PolkadotAccountInfo::decode(&mut result_scaled.as_ref());
+        // Well, `Nonce` is just an alias for u32, and Balance is just an alias for `u128`. Rather than
+        // depending on the `frame-system` and `pallet-balances` crates for those types, we use the ones
+        // that `build.rs` generates from Polkadot's metadata (see `utils::generate_code`), which decode
+        // in the same way. The generated code also knows how to build the storage key, which had better
+        // match ours:
+        let generated_key = polkadot::system::storage::account(&polkadot::types::AccountId32(
+            bobs_account_id.into(),
+        ));
+        assert_eq!(generated_key, storage_key);
+
+        let account_info = polkadot::system::storage::Account::decode(&mut result_scaled.as_ref());
         println!("{:?}", account_info);
     }
+
+    {
+        // Bob's free balance isn't necessarily all spendable. Various pallets can lock some of it
+        // (for instance, staking or vesting), and reserved funds are held for some reason or other.
+        // These live in separate storage entries in the Balances pallet (`Locks`, `Reserves`, and on
+        // newer runtimes `Holds` and `Freezes`), keyed by account ID in the same way as above.
+        //
+        // The shape of `AccountData` has also changed between runtime versions (older ones have
+        // `misc_frozen` and `fee_frozen` where newer ones have `frozen`), so rather than decoding into
+        // the types from the pallet crates, `get_account_balances` decodes all of this using the
+        // type information in the metadata (see example 03):
+        let bobs_account_id = AccountKeyring::Bob.to_account_id();
+        let token = get_token_info(&url).await.unwrap();
+        let balances = get_account_balances(&url, &metadata, &bobs_account_id, None)
+            .await
+            .unwrap();
+
+        println!(
+            "\nBob's free balance: {}",
+            token.format_amount(balances.free)
+        );
+        for lock in &balances.locks {
+            println!(
+                "  locked by {}: {}",
+                lock.id,
+                token.format_amount(lock.amount)
+            );
+        }
+        for freeze in &balances.freezes {
+            println!(
+                "  frozen by {}: {}",
+                freeze.id,
+                token.format_amount(freeze.amount)
+            );
+        }
+
+        // Locks and freezes overlap rather than adding up, so the most that's locked is what
+        // can't be spent:
+        println!(
+            "Bob's spendable balance: {}",
+            token.format_amount(balances.spendable())
+        );
+    }
 }
@@ -92,7 +92,7 @@ use sp_core::{blake2_256, H256};
 use sp_keyring::AccountKeyring;
 use sp_runtime::{generic::Era, MultiAddress, MultiSignature};
 use sp_version::RuntimeVersion;
-use utils::rpc_to_localhost;
+use utils::{get_token_info, rpc_to_localhost, rpc_url};
 
 #[tokio::main]
 async fn main() {
@@ -109,7 +109,8 @@ async fn main() {
     // The "transfer" call takes 2 arguments, which are as follows (if we wanted, we could
     // avoid using `MultiAddress` and encode a 0 u8 and then the account ID, but for simplicity..)
     let address = MultiAddress::Id::<_, u32>(AccountKeyring::Bob.to_account_id());
-    let balance = Compact::from(123456789012345u128);
+    let amount = 123456789012345u128;
+    let balance = Compact::from(amount);
 
     // We're transferring the money from Alice. How many transfers has she made already? we need
     // to include this number below; it has to be correct for the transfer to succeed.
@@ -128,6 +129,7 @@ async fn main() {
     // Many "ty" props there will resolve to nothing, so can be ignored. The ones that don't
     // resolve to nothing are the ones that encode to a non-zero number of bytes and need to
     // therefore be included.
+    let tip = 500000000000000u128;
     let extra = (
         // How long should this call "last" in the transaction pool before
         // being deemed "out of date" and discarded?
@@ -137,7 +139,8 @@ async fn main() {
         Compact(alice_nonce),
         // This is a tip, paid to the block producer (and in part the treasury)
         // to help incentive it to include this transaction in the block. Can be 0.
-        Compact(500000000000000u128),
+        // (see example 16 for working out how much is worth tipping).
+        Compact(tip),
     );
 
     // Grab a little more info that we'll need for below:
@@ -197,6 +200,15 @@ async fn main() {
     let payload_scale_encoded = encode_extrinsic(signature_to_encode, call);
     let payload_hex = format!("0x{}", hex::encode(&payload_scale_encoded));
 
+    // Amounts are given in the smallest unit of the token (a "planck" on Polkadot). The node
+    // can tell us how to display them in the same way that a UI would (eg "1.5 DOT"):
+    let token = get_token_info(&rpc_url()).await.unwrap();
+    println!(
+        "Transferring {} to Bob, with a tip of {}",
+        token.format_amount(amount),
+        token.format_amount(tip)
+    );
+
     // Submit it!
     println!("Submitting this payload: {}", payload_hex);
     let res = rpc_to_localhost("author_submitExtrinsic", [payload_hex])
@@ -13,17 +13,17 @@ cargo run --bin 06_storage_iter
 use jsonrpsee::{core::client::ClientT, rpc_params};
 use parity_scale_codec::Decode;
 use sp_core::{hashing, storage::StorageKey};
-use utils::ws_client;
+use utils::{polkadot, ws_client, ws_url};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // The WebSocket client can be utilized for submitting request to the
-    // production nodes. This example is submitting the request to the localhost.
-    // If you want to make requests to a production node use:
+    // production nodes. This example is submitting the request to the localhost,
+    // unless the POLKADOT_WS_URL environment variable points somewhere else, like:
     // ```
-    let client = ws_client("ws://localhost:9944/polkadot").await?;
+    // POLKADOT_WS_URL=wss://rpc.polkadot.io:443 cargo run --bin 06_storage_iter
     // ```
-    // let client = ws_client("ws://localhost:9944").await?;
+    let client = ws_client(&ws_url()).await?;
 
     // The VersionNotifiers type of the XcmPallet is defined as:
     //
@@ -40,6 +40,10 @@ async fn main() -> anyhow::Result<()> {
     // 		OptionQuery,
     // 	>;
     // ```
+    //
+    // Rather than depending on the `xcm` crate for the types of the keys and value, we use the
+    // ones that `build.rs` generates from Polkadot's metadata (see `utils::generate_code`),
+    // which decode in the same way.
 
     // Construct the XcmPallet VersionNotifiers key represented by:
     // twox_128(XcmPallet) ++ twox_128(VersionNotifiers)
@@ -89,9 +93,9 @@ async fn main() -> anyhow::Result<()> {
             format!("0x{}", hex::encode(&inspected_bytes))
         );
 
-        // The next 4 bytes represent the first key - `xcm::Version` which is an u32.
+        // The next 4 bytes represent the first key - `XcmVersion` which is an u32.
         let inspected_bytes = &key_bytes[40..44];
-        let xcm_version = xcm::Version::decode(&mut inspected_bytes.clone())?;
+        let xcm_version = u32::decode(&mut inspected_bytes.clone())?;
         println!(
             "     bytes[40..44]: {}                         == first key - XcmVersion: {}",
             format!("0x{}", hex::encode(&inspected_bytes)),
@@ -108,18 +112,26 @@ async fn main() -> anyhow::Result<()> {
         // The remaining bytes are represented by the second key.
         let inspected_bytes = &key_bytes[60..];
         let versioned_multilocation =
-            xcm::VersionedMultiLocation::decode(&mut inspected_bytes.clone())?;
+            polkadot::types::VersionedMultiLocation::decode(&mut inspected_bytes.clone())?;
         println!(
             "     bytes[60..  ]: {}                     == second key - VersionedMultiLocation: {:?}",
             format!("0x{}", hex::encode(&inspected_bytes)),
             versioned_multilocation
         );
 
+        // Having decoded both keys, the generated code can build the same storage key from them:
+        let generated_key = polkadot::xcm_pallet::storage::version_notifiers(
+            &xcm_version,
+            &versioned_multilocation,
+        );
+        assert_eq!(generated_key, key_bytes.as_slice());
+
         // Get the value of the storage key.
         let params = rpc_params![key];
         let result_hex: String = client.request("state_getStorage", params).await?;
         let result_bytes = hex::decode(result_hex.trim_start_matches("0x"))?;
-        let query_id = xcm::v2::QueryId::decode(&mut result_bytes.as_slice())?;
+        let query_id =
+            polkadot::xcm_pallet::storage::VersionNotifiers::decode(&mut result_bytes.as_slice())?;
         println!("  Value: {}\n", query_id);
     }
 
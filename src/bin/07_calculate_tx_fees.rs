@@ -4,7 +4,7 @@ An example of obtaining and calculating the partial fee that a tx will cost.
 This example takes 1-3 args;
 - an encoded signed extrinsic
 - a block number (if not a number, use latest block)
-- a URL to query (if not provided, point to localhost)
+- a URL to query (if not provided, use $POLKADOT_WS_URL, or else point to localhost)
 
 Note that URLs must be suffixed with a port number. For most public instances if the URL
 is WSS (eg those used in polkadot.js) the port will be 443.
@@ -20,12 +20,10 @@ cargo run --bin 07_calculate_tx_fees -- \
 
 */
 
-use utils::ws_client;
-use std::{env, process};
 use jsonrpsee::{core::client::ClientT, rpc_params};
 use serde_json::Value;
-
-const LOCAL_URL: &str = "ws://localhost:9944";
+use std::{env, process};
+use utils::{number_from_json, weight_fees, ws_client, ws_url, Weight};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -37,13 +35,15 @@ async fn main() -> anyhow::Result<()> {
     let extrinsic_hex: String = match args.next() {
         Some(hex) => hex,
         None => {
-            eprintln!("cargo run --bin 07_calculate_tx_fees -- EXTRINSIC_HEX [BLOCK_NUMBER] [RPC_URL]");
+            eprintln!(
+                "cargo run --bin 07_calculate_tx_fees -- EXTRINSIC_HEX [BLOCK_NUMBER] [RPC_URL]"
+            );
             process::exit(1);
         }
     };
 
     let block_number: Option<u64> = args.next().and_then(|n| n.parse().ok());
-    let rpc_url: String = args.next().unwrap_or(LOCAL_URL.to_string());
+    let rpc_url: String = args.next().unwrap_or_else(ws_url);
 
     println!();
     println!("Extrinsix hex: {extrinsic_hex}");
@@ -62,23 +62,31 @@ async fn main() -> anyhow::Result<()> {
 
     // Now, pass this into payment_queryFeeDetails:
     let fee_details_value = client
-        .request::<Value>("payment_queryFeeDetails", rpc_params![extrinsic_hex.clone(), block_hash.clone()])
+        .request::<Value>(
+            "payment_queryFeeDetails",
+            rpc_params![extrinsic_hex.clone(), block_hash.clone()],
+        )
         .await
         .expect("cannot get queryFeeDetails back for extrinsic");
 
     let inclusion_fee = &fee_details_value["inclusionFee"];
-    let weight_fee = to_number(&inclusion_fee["adjustedWeightFee"]);
-    let base_fee = to_number(&inclusion_fee["baseFee"]);
-    let len_fee = to_number(&inclusion_fee["lenFee"]);
+    let weight_fee = number_from_json(&inclusion_fee["adjustedWeightFee"], "adjustedWeightFee")?;
+    let base_fee = number_from_json(&inclusion_fee["baseFee"], "baseFee")?;
+    let len_fee = number_from_json(&inclusion_fee["lenFee"], "lenFee")?;
 
     // We can also fetch the underlying estimated weight/partialFee:
     let fee_info_value = client
-        .request::<Value>("payment_queryInfo", rpc_params![extrinsic_hex, block_hash])
+        .request::<Value>(
+            "payment_queryInfo",
+            rpc_params![extrinsic_hex, block_hash.clone()],
+        )
         .await
         .expect("cannot get queryInfo back for extrinsic");
 
-    let weight = fee_info_value["weight"].as_i64().expect("weight should exist");
-    let partial_fee = fee_info_value["partialFee"].as_str().expect("partialFee should exist");
+    // Older runtimes give back the weight as a single number, and newer ones as
+    // `{ refTime, proofSize }`; `Weight::from_json` understands both:
+    let weight = Weight::from_json(&fee_info_value["weight"])?;
+    let partial_fee = number_from_json(&fee_info_value["partialFee"], "partialFee")?;
 
     println!();
     // The cost to include the extrinsic in a block. Takes into account the cost
@@ -95,14 +103,29 @@ async fn main() -> anyhow::Result<()> {
     println!("Adjusted weight fee: {weight_fee}");
     // The underlying weight of the transaction, I think, before it's adjusted.
     // This represents the cost to process that particular transaction, but doesn't
-    // take into account how busy the network is.
-    println!("Weight:              {weight}");
+    // take into account how busy the network is. Newer runtimes measure weight in
+    // two dimensions: the time taken to execute ("ref time"), and the size of the
+    // proof that validators need in order to check the block ("proof size").
+    println!("Weight (ref time):   {}", weight.ref_time);
+    println!("Weight (proof size): {}", weight.proof_size);
+
+    // Runtimes with two dimensional weights can tell us how much each dimension
+    // costs (before the adjustment for network load). How these are combined into
+    // the weight fee is up to the runtime; some charge for ref time alone, and some
+    // charge for whichever dimension is more expensive.
+    match weight_fees(&client, weight, Some(&block_hash)).await {
+        Ok(fees) => {
+            println!("  ref time fee:      {}", fees.ref_time_fee);
+            println!("  proof size fee:    {}", fees.proof_size_fee);
+        }
+        Err(_) => println!("  (this runtime doesn't expose a fee per weight dimension)"),
+    }
 
     // The partial fee is the total fee paid minus a tip. It's basically the sum
     // of the base fee, length fee and adjusted weight fee.
     println!();
     println!("Partial fee:         {partial_fee}");
-    assert_eq!((base_fee + len_fee + weight_fee).to_string(), partial_fee);
+    assert_eq!(base_fee + len_fee + weight_fee, partial_fee);
 
     // NOTE: When an extrinsic is submitted, it's actual weight ends up in
     // ExtrinsicSuccess, as does a `paysFee` parameter. The node is free to
@@ -114,14 +137,12 @@ async fn main() -> anyhow::Result<()> {
     //
     // `fee = len_fee + base_fee + (weight_fee / wight * new_weight_from_ext_success)`
     //
+    // (With two dimensional weights, the ratio should be taken between the fees for
+    // each weight, since the runtime decides how to combine the dimensions.) See
+    // `14_actual_fee` for this calculation in practice.
+    //
     // Also note; this is only applicable to Polkadot and chains which copy the
     // way that Polkadot does fees. Chains can do whatever they like, really.
 
     Ok(())
 }
-
-fn to_number(value: &Value) -> u128 {
-    let s = value.as_str().expect("value should be a hex string");
-    let s = &s[2..]; // trim 0x
-    u128::from_str_radix(s, 16).expect("value should be valid u128")
-}
@@ -0,0 +1,100 @@
+/*!
+Submitting several transactions from the same account in quick succession.
+
+In example 05, we ask the node for Alice's nonce (via "system_accountNextIndex") right before
+building the transfer. If we did that for several transfers at once, each request would
+come back with the same nonce (the node hasn't seen any of our transactions yet), and all
+but one of them would be rejected by the transaction pool.
+
+Here, we use `utils::NonceManager`, which asks the node once and then hands out nonces
+locally, to pay a handful of accounts concurrently. If a submission fails, we give the
+nonce back (or re-sync with the node if it tells us that our nonce is outdated) so that
+later transactions aren't left waiting on a gap.
+
+As with example 05, this expects a fresh dev node.
+
+```
+cargo run --bin 08_nonce_manager
+```
+*/
+
+use parity_scale_codec::Compact;
+use sp_keyring::AccountKeyring;
+use sp_runtime::MultiAddress;
+use std::sync::Arc;
+use utils::{get_genesis_hash, get_runtime_version, rpc, rpc_url, signed_extrinsic, NonceManager};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let url = rpc_url();
+
+    // As in example 05, these are the hard coded Balances pallet and transfer call indexes:
+    let pallet_index: u8 = 5;
+    let call_index: u8 = 0;
+
+    let runtime_version = get_runtime_version(&url).await?;
+    let genesis_hash = get_genesis_hash(&url).await?;
+
+    // One nonce manager, shared between every task submitting transactions:
+    let nonces = Arc::new(NonceManager::new(url.clone()));
+
+    let payees = [
+        AccountKeyring::Bob,
+        AccountKeyring::Charlie,
+        AccountKeyring::Dave,
+        AccountKeyring::Eve,
+        AccountKeyring::Ferdie,
+    ];
+
+    let mut tasks = Vec::new();
+    for payee in payees {
+        let url = url.clone();
+        let nonces = nonces.clone();
+        let runtime_version = runtime_version.clone();
+        tasks.push(tokio::spawn(async move {
+            let signer = AccountKeyring::Alice.pair();
+            let from = AccountKeyring::Alice.to_account_id();
+            let call = (
+                pallet_index,
+                call_index,
+                MultiAddress::Id::<_, u32>(payee.to_account_id()),
+                Compact(1_000_000_000_000u128),
+            );
+
+            // Try a couple of times; if the node says our nonce is outdated, something else
+            // has been submitting from this account too, so we re-sync and go again.
+            for _attempt in 0..2 {
+                let nonce = nonces.next(&from).await?;
+                let payload =
+                    signed_extrinsic(&signer, &call, nonce, 0, &runtime_version, genesis_hash);
+                let payload_hex = format!("0x{}", hex::encode(&payload));
+
+                match rpc(&url, "author_submitExtrinsic", [payload_hex]).await {
+                    Ok(hash) => {
+                        println!("Paid {:?} with nonce {}: {}", payee, nonce, hash);
+                        return Ok(());
+                    }
+                    Err(e) if e.to_string().contains("outdated") => {
+                        println!("Nonce {} was stale; re-syncing with the node", nonce);
+                        nonces.resync(&from).await?;
+                    }
+                    Err(e) => {
+                        // The transaction never made it into the pool, so nothing will
+                        // ever use this nonce unless we hand it out again:
+                        nonces.release(&from, nonce).await;
+                        return Err(e);
+                    }
+                }
+            }
+            anyhow::bail!("Could not pay {:?}: nonce kept going stale", payee)
+        }));
+    }
+
+    for task in tasks {
+        if let Err(e) = task.await? {
+            println!("Error: {}", e);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,122 @@
+/*!
+Paying several accounts in one transaction using the Utility pallet.
+
+The Utility pallet has three calls which take a list of other calls and dispatch them
+one after the other, differing in what happens when one of them fails:
+
+- `batch`: stop at the first failing call; the calls before it still take effect.
+- `batch_all`: if any call fails, revert all of them (the whole extrinsic fails).
+- `force_batch`: carry on past failing calls, dispatching every one of them.
+
+Each takes a single argument, a `Vec` of calls. Calls are encoded just like the call data
+that we built by hand in example 05 (pallet index, call index, arguments), so building a
+batch is a case of encoding a few of those and wrapping them in the Utility call. This time,
+rather than hard coding the indexes, we look them up by name in the metadata.
+
+Once the batch is in a block, we decode the events it emitted to find out which of
+the calls succeeded.
+
+The mode can optionally be given as an argument; one of "batch", "batch_all" (the
+default) or "force_batch":
+
+```
+cargo run --bin 09_batch_calls -- force_batch
+```
+*/
+
+use parity_scale_codec::Compact;
+use sp_keyring::AccountKeyring;
+use sp_runtime::MultiAddress;
+use std::env;
+use utils::{
+    batch_call, batch_outcome, find_extrinsic_index, get_events, get_genesis_hash,
+    get_runtime_version, rpc, rpc_url, signed_extrinsic, submit_and_watch, ws_client, ws_url,
+    BatchMode, EncodedCall, MetadataCache,
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let url = rpc_url();
+    let mode = match env::args().nth(1).as_deref() {
+        Some("batch") => BatchMode::Batch,
+        Some("force_batch") => BatchMode::ForceBatch,
+        _ => BatchMode::BatchAll,
+    };
+
+    // The metadata is kept on disk between runs (see `utils::MetadataCache`), so it's only
+    // downloaded again after a runtime upgrade:
+    let metadata = MetadataCache::new(url.as_str()).metadata().await?;
+
+    // Build a transfer to each of our payees. The last one tries to transfer more than
+    // Alice has, so we can see how each batch mode handles a failing call. Like
+    // `polkadot-interact transfer`, we use `transfer_keep_alive`, since `transfer` no longer
+    // exists in newer runtimes:
+    let payments = [
+        (AccountKeyring::Bob, 1_000_000_000_000u128),
+        (AccountKeyring::Charlie, 2_000_000_000_000),
+        (AccountKeyring::Dave, u128::MAX / 2),
+    ];
+    let calls = payments
+        .iter()
+        .map(|(payee, amount)| {
+            EncodedCall::by_name(
+                &metadata,
+                "Balances",
+                "transfer_keep_alive",
+                (
+                    MultiAddress::Id::<_, u32>(payee.to_account_id()),
+                    Compact(*amount),
+                ),
+            )
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let num_calls = calls.len();
+
+    // Wrap them up into a single Utility call:
+    let call = batch_call(&metadata, mode, calls)?;
+    println!("{} call data: 0x{}", mode.call_name(), hex::encode(&call.0));
+
+    // Sign and submit it in the same way as example 05:
+    let nonce: u32 = serde_json::from_value(
+        rpc(
+            &url,
+            "system_accountNextIndex",
+            (AccountKeyring::Alice.to_account_id(),),
+        )
+        .await?,
+    )?;
+    let runtime_version = get_runtime_version(&url).await?;
+    let genesis_hash = get_genesis_hash(&url).await?;
+    let payload = signed_extrinsic(
+        &AccountKeyring::Alice.pair(),
+        &call,
+        nonce,
+        0,
+        &runtime_version,
+        genesis_hash,
+    );
+    let payload_hex = format!("0x{}", hex::encode(&payload));
+
+    // We want to know which block the batch ends up in, so that we can look at the
+    // events in it. We subscribe to the status of the transaction (over WS) to find out:
+    let client = ws_client(&ws_url()).await?;
+    let block_hash = submit_and_watch(&client, &payload_hex).await?;
+    println!("Included in block {}", block_hash);
+
+    // Events are tagged with the index of the extrinsic that emitted them, so find ours:
+    let extrinsic_index = find_extrinsic_index(&url, &block_hash, &payload_hex)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("extrinsic not found in block {}", block_hash))?;
+
+    let events = get_events(&url, &metadata, Some(&block_hash)).await?;
+    let outcome = batch_outcome(&events, extrinsic_index, num_calls);
+
+    for ((payee, _), result) in payments.iter().zip(&outcome.items) {
+        println!("Payment to {:?}: {:?}", payee, result);
+    }
+    if let Some(error) = &outcome.extrinsic_failed {
+        println!("The batch failed as a whole: {}", error);
+    }
+
+    Ok(())
+}
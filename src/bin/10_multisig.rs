@@ -0,0 +1,122 @@
+/*!
+Coordinating a multisig transfer between several signatories.
+
+A multisig account is controlled by a set of signatories, some number of whom (the
+threshold) need to approve an operation before it happens. Here, Alice, Bob and Charlie
+share a 2-of-3 multisig account, and want to send some of its funds to Dave:
+
+1. We derive the multisig account ID from the signatories and threshold (it doesn't
+   have a key of its own), and Alice sends it some funds to play with.
+2. Alice approves the transfer with `Multisig.approve_as_multi`. This only needs the hash
+   of the call that we want to make, and records a "timepoint" (the block number and
+   extrinsic index of her approval) in `Multisig.Multisigs` storage.
+3. We read that storage to find the timepoint, and the approvals so far.
+4. Bob approves with `Multisig.as_multi`, providing the timepoint and the full call. Since
+   his is the second of two approvals, the transfer is then dispatched from the multisig
+   account, and a `Multisig.MultisigExecuted` event is emitted telling us how it went.
+
+Each of these is signed and submitted in the same way as the transfer in example 05; the
+only difference is the call data. As with example 05, this expects a fresh dev node.
+
+```
+cargo run --bin 10_multisig
+```
+*/
+
+use parity_scale_codec::Compact;
+use sp_keyring::AccountKeyring;
+use sp_runtime::MultiAddress;
+use utils::{
+    call_hash, get_events, rpc_url, sign_and_watch, ws_client, ws_url, EncodedCall, MetadataCache,
+    Multisig, NonceManager, Weight,
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let url = rpc_url();
+    let metadata = MetadataCache::new(url.as_str()).metadata().await?;
+    let client = ws_client(&ws_url()).await?;
+    let nonces = NonceManager::new(&url);
+
+    let alice = AccountKeyring::Alice;
+    let bob = AccountKeyring::Bob;
+
+    // 1. Work out the multisig account, and give it some funds:
+    let multisig = Multisig::new(
+        vec![
+            alice.to_account_id(),
+            bob.to_account_id(),
+            AccountKeyring::Charlie.to_account_id(),
+        ],
+        2,
+    )?;
+    let multisig_account = multisig.account_id();
+    println!("Multisig account: {}", multisig_account);
+
+    let fund_call = EncodedCall::by_name(
+        &metadata,
+        "Balances",
+        "transfer_keep_alive",
+        (
+            MultiAddress::Id::<_, u32>(multisig_account.clone()),
+            Compact(10_000_000_000_000u128),
+        ),
+    )?;
+    sign_and_watch(&client, &url, &nonces, &alice.pair(), &fund_call).await?;
+    println!("Funded the multisig account");
+
+    // This is the call that we want the multisig account to make:
+    let transfer_call = EncodedCall::by_name(
+        &metadata,
+        "Balances",
+        "transfer_keep_alive",
+        (
+            MultiAddress::Id::<_, u32>(AccountKeyring::Dave.to_account_id()),
+            Compact(1_000_000_000_000u128),
+        ),
+    )?;
+    let transfer_call_hash = call_hash(&transfer_call);
+    println!("Call hash: 0x{}", hex::encode(transfer_call_hash));
+
+    // The most weight we're willing to let the transfer use when it's dispatched. This needs
+    // to be at least the weight of the call (a transfer is far less than this):
+    let max_weight = Weight::new(1_000_000_000, 100_000);
+
+    // 2. Alice makes the first approval. There's no timepoint yet:
+    let approve_call = multisig.approve_as_multi(
+        &metadata,
+        &alice.to_account_id(),
+        None,
+        transfer_call_hash,
+        max_weight,
+    )?;
+    sign_and_watch(&client, &url, &nonces, &alice.pair(), &approve_call).await?;
+
+    // 3. See what's pending for this call:
+    let pending = multisig
+        .pending(&url, &metadata, transfer_call_hash)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Alice's approval should be pending"))?;
+    println!("Pending multisig: {:?}", pending);
+
+    // 4. Bob makes the second and final approval, providing the whole call:
+    let as_multi_call = multisig.as_multi(
+        &metadata,
+        &bob.to_account_id(),
+        Some(pending.when),
+        &transfer_call,
+        max_weight,
+    )?;
+    let in_block = sign_and_watch(&client, &url, &nonces, &bob.pair(), &as_multi_call).await?;
+
+    // The result of dispatching the transfer can be found in the events:
+    let events = get_events(&url, &metadata, Some(&in_block.block_hash)).await?;
+    for event in events
+        .iter()
+        .filter(|e| e.is_from_extrinsic(in_block.extrinsic_index) && e.pallet == "Multisig")
+    {
+        println!("{}.{}: {}", event.pallet, event.variant, event.fields);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,104 @@
+/*!
+Making calls on behalf of another account via the Proxy pallet.
+
+An account can register other accounts (delegates) as its proxies. A delegate can then
+wrap a call in `Proxy.proxy` and submit it, signed with its own key, and the call will
+be dispatched as though it came from the proxied account. Each proxy has a type which
+limits which calls it can make (for instance, "Any", "NonTransfer" or "Staking"), and
+optionally a delay, in which case calls must be announced that many blocks in advance.
+
+Here, we:
+
+1. Have Alice register Bob as an "Any" proxy, and read `Proxy.Proxies` to check it's there.
+2. Have Bob transfer some of Alice's funds to Charlie, using `Proxy.proxy`.
+3. Have Alice create a "pure" proxy; a brand new account with no key which can only be
+   controlled by proxy. We derive its account ID ourselves and check it against the one
+   in the `Proxy.PureCreated` event.
+
+As with example 05, this expects a fresh dev node.
+
+```
+cargo run --bin 11_proxy
+```
+*/
+
+use parity_scale_codec::Compact;
+use sp_keyring::AccountKeyring;
+use sp_runtime::MultiAddress;
+use std::convert::TryInto;
+use utils::{
+    add_proxy_call, create_pure_call, get_events, get_proxies, number_from_json, proxy_call,
+    pure_proxy_account_id, rpc, rpc_url, sign_and_watch, ws_client, ws_url, EncodedCall,
+    MetadataCache, NonceManager,
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let url = rpc_url();
+    let metadata = MetadataCache::new(url.as_str()).metadata().await?;
+    let client = ws_client(&ws_url()).await?;
+    let nonces = NonceManager::new(&url);
+
+    let alice = AccountKeyring::Alice;
+    let bob = AccountKeyring::Bob;
+
+    // 1. Alice makes Bob her proxy, with no delay:
+    let call = add_proxy_call(&metadata, &bob.to_account_id(), "Any", 0)?;
+    sign_and_watch(&client, &url, &nonces, &alice.pair(), &call).await?;
+
+    let proxies = get_proxies(&url, &metadata, &alice.to_account_id()).await?;
+    println!("Alice's proxies: {:#?}", proxies);
+
+    // 2. Bob wraps a transfer in `Proxy.proxy`, so that it comes from Alice:
+    let transfer = EncodedCall::by_name(
+        &metadata,
+        "Balances",
+        "transfer_keep_alive",
+        (
+            MultiAddress::Id::<_, u32>(AccountKeyring::Charlie.to_account_id()),
+            Compact(1_000_000_000_000u128),
+        ),
+    )?;
+    let call = proxy_call(&metadata, &alice.to_account_id(), None, &transfer)?;
+    let in_block = sign_and_watch(&client, &url, &nonces, &bob.pair(), &call).await?;
+
+    // The result of the proxied call is given in a `ProxyExecuted` event:
+    let events = get_events(&url, &metadata, Some(&in_block.block_hash)).await?;
+    for event in events
+        .iter()
+        .filter(|e| e.is_from_extrinsic(in_block.extrinsic_index))
+    {
+        println!("{}.{}: {}", event.pallet, event.variant, event.fields);
+    }
+
+    // 3. Alice creates a pure proxy. Its account ID depends on the block number and
+    // extrinsic index that it's created at, so we need to know where the call ended up:
+    let call = create_pure_call(&metadata, "Any", 0, 0)?;
+    let in_block = sign_and_watch(&client, &url, &nonces, &alice.pair(), &call).await?;
+
+    let header = rpc(&url, "chain_getHeader", [&in_block.block_hash]).await?;
+    let block_number: u32 = number_from_json(&header["number"], "number")?.try_into()?;
+
+    let pure_account = pure_proxy_account_id(
+        &metadata,
+        &alice.to_account_id(),
+        "Any",
+        0,
+        block_number,
+        in_block.extrinsic_index,
+    )?;
+    println!(
+        "Derived pure proxy account: 0x{}",
+        hex::encode(&pure_account)
+    );
+
+    let events = get_events(&url, &metadata, Some(&in_block.block_hash)).await?;
+    if let Some(event) = events
+        .iter()
+        .find(|e| e.is_from_extrinsic(in_block.extrinsic_index) && e.is("Proxy", "PureCreated"))
+    {
+        println!("PureCreated event says:     {}", event.fields["pure"]);
+    }
+
+    Ok(())
+}
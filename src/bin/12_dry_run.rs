@@ -0,0 +1,111 @@
+/*!
+Finding out whether a transaction would succeed before submitting it.
+
+In example 05 we submit a transfer and hope for the best; if it's going to fail, we only find
+out afterwards (and if the transaction is valid but the transfer itself fails, we still pay the
+fees). Nodes can "dry run" an extrinsic instead: apply it on top of the latest block, tell us
+what happened, and then throw the result away.
+
+The result is an `ApplyExtrinsicResult`, which has two levels of failure:
+
+- The transaction might be invalid (a `TransactionValidityError`), meaning that it would be
+  rejected by the transaction pool and never make it into a block; for instance, because the
+  nonce has already been used ("Stale"), or the signature is wrong ("BadProof").
+- The transaction might be valid, but the call in it fails (a `DispatchError`), for instance
+  because Alice doesn't have enough funds. The transaction would be included in a block and
+  fees paid. Errors from pallets are given as a pallet index and error index, which we look up
+  in the metadata to find a name like "Balances.InsufficientBalance".
+
+Here we build a few transfers in the same way as example 05 and dry run each of them.
+
+Newer runtimes also provide a `DryRunApi`, which can dispatch a call from some account without
+it being in a signed extrinsic at all. This skips the nonce, signature and fee checks, so it
+only tells us whether the call itself would succeed, but it means we can try a call out on
+behalf of any account. We finish by using it to try the failing transfer again as Bob.
+
+```
+cargo run --bin 12_dry_run
+```
+*/
+
+use parity_scale_codec::Compact;
+use sp_keyring::AccountKeyring;
+use sp_runtime::MultiAddress;
+use utils::{
+    dry_run, dry_run_call, get_genesis_hash, get_runtime_version, rpc, rpc_url, signed_extrinsic,
+    ApplyExtrinsicResult, EncodedCall, MetadataCache,
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let url = rpc_url();
+    let metadata = MetadataCache::new(url.as_str()).metadata().await?;
+    let runtime_version = get_runtime_version(&url).await?;
+    let genesis_hash = get_genesis_hash(&url).await?;
+
+    let alice = AccountKeyring::Alice;
+    let nonce: u32 = serde_json::from_value(
+        rpc(&url, "system_accountNextIndex", (alice.to_account_id(),)).await?,
+    )?;
+
+    let transfer = |amount: u128| {
+        EncodedCall::by_name(
+            &metadata,
+            "Balances",
+            "transfer_keep_alive",
+            (
+                MultiAddress::Id::<_, u32>(AccountKeyring::Bob.to_account_id()),
+                Compact(amount),
+            ),
+        )
+    };
+
+    let attempts = [
+        (
+            "a transfer that should work",
+            transfer(123456789012345)?,
+            nonce,
+        ),
+        (
+            "transferring more than Alice has",
+            transfer(u128::MAX / 2)?,
+            nonce,
+        ),
+        (
+            "skipping ahead a few nonces",
+            transfer(123456789012345)?,
+            nonce + 5,
+        ),
+    ];
+
+    for (description, call, nonce) in &attempts {
+        let extrinsic = signed_extrinsic(
+            &alice.pair(),
+            call,
+            *nonce,
+            0,
+            &runtime_version,
+            genesis_hash,
+        );
+
+        print!("Dry running {}: ", description);
+        match dry_run(&url, &metadata, &extrinsic, None).await? {
+            ApplyExtrinsicResult::Success => println!("success!"),
+            ApplyExtrinsicResult::DispatchFailed(e) => println!("the call would fail: {}", e),
+            ApplyExtrinsicResult::Invalid(e) => println!("it would be rejected: {}", e),
+        }
+    }
+
+    // Try the over-large transfer again without signing anything. Runtimes which don't
+    // provide the DryRunApi will give back an error here:
+    let (_, call, _) = &attempts[1];
+    print!("Dry running the same call from Bob: ");
+    let bob = AccountKeyring::Bob.to_account_id();
+    match dry_run_call(&url, &metadata, &bob, call, None).await {
+        Ok(Ok(())) => println!("success!"),
+        Ok(Err(e)) => println!("the call would fail: {}", e),
+        Err(e) => println!("the runtime can't dry run calls: {}", e),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,51 @@
+/*!
+Estimating the fees for a transaction before signing it.
+
+In example 07 we ask the node what fees an extrinsic would pay, but we need the finished,
+signed extrinsic to do so. Often we want to know the fees first; for instance, to show them
+to a user before asking them to sign anything.
+
+The fees don't depend on the signature itself; they're made up of a base fee, a fee based on
+the weight of the call, and a fee based on the length of the extrinsic. So, we can put our
+call into an extrinsic with an all-zero signature (which has the same length as a real one),
+and ask the `TransactionPaymentApi` runtime API what that would cost.
+
+Here we estimate the fees for the same transfer as example 05 (built in the same way, by
+hand), and for the same transfer with a tip.
+
+```
+cargo run --bin 13_estimate_fees
+```
+*/
+
+use parity_scale_codec::Compact;
+use sp_keyring::AccountKeyring;
+use sp_runtime::MultiAddress;
+use utils::{estimate_fees, get_token_info, rpc_url};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let url = rpc_url();
+
+    // The same call as in example 05:
+    let pallet_index: u8 = 5;
+    let call_index: u8 = 0;
+    let address = MultiAddress::Id::<_, u32>(AccountKeyring::Bob.to_account_id());
+    let balance = Compact::from(123456789012345u128);
+    let call = (pallet_index, call_index, address, balance);
+
+    let from = AccountKeyring::Alice.to_account_id();
+    let token = get_token_info(&url).await?;
+
+    for tip in [0, 500000000000000u128] {
+        let estimate = estimate_fees(&url, &from, &call, tip).await?;
+        println!(
+            "With a tip of {}, the fee would be {}: {:#?}",
+            token.format_amount(tip),
+            token.format_amount(estimate.partial_fee),
+            estimate
+        );
+    }
+
+    Ok(())
+}
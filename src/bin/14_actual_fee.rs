@@ -0,0 +1,111 @@
+/*!
+Working out the fee that an extrinsic in a block actually paid.
+
+Example 07 asks the node to estimate the fee for an extrinsic, and notes at the bottom that
+this isn't necessarily what ends up being paid. The estimate assumes the worst case weight of
+the call; once it's been dispatched, the actual weight used (and whether the call pays fees at
+all) is reported in the `System.ExtrinsicSuccess` event, and any overpayment is refunded. The
+tip is paid on top of this.
+
+Newer runtimes emit a `TransactionPayment.TransactionFeePaid` event containing the final fee
+and tip. For older ones, we calculate it ourselves:
+
+`fee = base_fee + len_fee + adjusted_weight_fee * actual_weight / estimated_weight`
+
+This example takes 2-3 args;
+- a block hash or number
+- the index of the extrinsic in that block
+- a URL to query (if not provided, use $POLKADOT_RPC_URL, or else point to localhost)
+
+```
+cargo run --bin 14_actual_fee -- 1 1
+```
+*/
+
+use std::{env, process};
+use utils::{actual_fee_paid, get_token_info, rpc, rpc_url, weight_fees, MetadataCache};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut args = env::args().skip(1);
+
+    let (block, extrinsic_index) = match (args.next(), args.next().and_then(|n| n.parse().ok())) {
+        (Some(block), Some(index)) => (block, index),
+        _ => {
+            eprintln!(
+                "cargo run --bin 14_actual_fee -- BLOCK_HASH_OR_NUMBER EXTRINSIC_INDEX [RPC_URL]"
+            );
+            process::exit(1);
+        }
+    };
+    let url = args.next().unwrap_or_else(rpc_url);
+
+    // Convert a block number into a block hash if need be:
+    let block_hash = match block.parse::<u64>() {
+        Ok(number) => rpc(&url, "chain_getBlockHash", [number])
+            .await?
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("no block hash found for block {}", number))?
+            .to_string(),
+        Err(_) => block,
+    };
+
+    // The events are decoded with the metadata of the block itself, in case the runtime has
+    // been upgraded since:
+    let metadata = MetadataCache::new(url.as_str())
+        .metadata_at(&block_hash)
+        .await?;
+    let token = get_token_info(&url).await?;
+    let fee = actual_fee_paid(&url, &metadata, &block_hash, extrinsic_index).await?;
+
+    println!("Block hash:         {}", block_hash);
+    println!("Extrinsic index:    {}", extrinsic_index);
+    println!();
+    println!("Estimated weight:   {:?}", fee.estimate.weight);
+    println!("Actual weight:      {:?}", fee.actual_weight);
+    println!("Pays fee:           {}", fee.pays_fee);
+
+    // On runtimes with two dimensional weights, we can see what each dimension of the
+    // actual weight costs (before adjusting for how busy the chain is):
+    if let Ok(weight_fees) = weight_fees(&url, fee.actual_weight, Some(&block_hash)).await {
+        println!(
+            "  ref time fee:     {}",
+            token.format_amount(weight_fees.ref_time_fee)
+        );
+        println!(
+            "  proof size fee:   {}",
+            token.format_amount(weight_fees.proof_size_fee)
+        );
+        println!(
+            "  weight fee:       {}",
+            token.format_amount(weight_fees.total)
+        );
+    }
+    println!();
+    println!(
+        "Estimated fee:      {}",
+        token.format_amount(fee.estimate.partial_fee)
+    );
+    println!(
+        "Actual fee:         {}",
+        token.format_amount(fee.partial_fee)
+    );
+    let difference = fee.difference_from_estimate();
+    println!(
+        "Difference:         {}{}",
+        if difference < 0 { "-" } else { "" },
+        token.format_amount(difference.unsigned_abs())
+    );
+    println!("Tip:                {}", token.format_amount(fee.tip));
+    println!("Total paid:         {}", token.format_amount(fee.total()));
+    println!(
+        "(fee {})",
+        if fee.from_event {
+            "taken from the TransactionFeePaid event"
+        } else {
+            "calculated from the actual weight"
+        }
+    );
+
+    Ok(())
+}
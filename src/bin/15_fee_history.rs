@@ -0,0 +1,109 @@
+/*!
+Tracking how fees change over a range of blocks.
+
+This builds on example 07: for every signed extrinsic in each block, we work out the base fee,
+length fee and adjusted weight fee (against the state of the parent block, which is what the
+extrinsic was applied on top of), and decode the tip from the extrinsic itself. We then print
+some statistics for each block, along with `TransactionPayment.NextFeeMultiplier`, which is
+how the adjusted weight fee responds to how busy the chain is. Extrinsics signed in a format we
+can't decode are left out of the statistics, and counted in "skipped_extrinsics".
+
+This example takes 2-4 args;
+- the first block number
+- the last block number
+- optionally, "--json" to print JSON lines rather than CSV
+- a URL to query (if not provided, use $POLKADOT_RPC_URL, or else point to localhost)
+
+```
+cargo run --bin 15_fee_history -- 1 100 > fees.csv
+```
+*/
+
+use serde_json::json;
+use std::{env, process};
+use utils::{block_fees, rpc_url, BlockFees, FEE_MULTIPLIER_ONE};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut args = env::args().skip(1).peekable();
+
+    let (start, end): (u64, u64) = match (
+        args.next().and_then(|n| n.parse().ok()),
+        args.next().and_then(|n| n.parse().ok()),
+    ) {
+        (Some(start), Some(end)) if start <= end => (start, end),
+        _ => {
+            eprintln!(
+                "cargo run --bin 15_fee_history -- FIRST_BLOCK LAST_BLOCK [--json] [RPC_URL]"
+            );
+            process::exit(1);
+        }
+    };
+    let as_json = args.next_if(|arg| arg == "--json").is_some();
+    let url = args.next().unwrap_or_else(rpc_url);
+
+    if !as_json {
+        println!(
+            "number,hash,signed_extrinsics,skipped_extrinsics,median_fee,total_fees,total_tips,next_fee_multiplier"
+        );
+    }
+
+    for number in start..=end {
+        let fees = block_fees(&url, number).await?;
+        if as_json {
+            println!("{}", to_json(&fees));
+        } else {
+            println!(
+                "{},{},{},{},{},{},{},{}",
+                fees.number,
+                fees.hash,
+                fees.extrinsics.len(),
+                fees.skipped_extrinsics,
+                fees.median_fee().map(|f| f.to_string()).unwrap_or_default(),
+                fees.total_fees(),
+                fees.total_tips(),
+                multiplier_to_string(fees.next_fee_multiplier),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn to_json(fees: &BlockFees) -> serde_json::Value {
+    // Balances can be larger than JSON numbers can safely represent, so we use strings:
+    let extrinsics: Vec<_> = fees
+        .extrinsics
+        .iter()
+        .map(|e| {
+            json!({
+                "index": e.index,
+                "baseFee": e.base_fee.to_string(),
+                "lenFee": e.len_fee.to_string(),
+                "adjustedWeightFee": e.adjusted_weight_fee.to_string(),
+                "partialFee": e.partial_fee().to_string(),
+                "tip": e.tip.to_string(),
+            })
+        })
+        .collect();
+
+    json!({
+        "number": fees.number,
+        "hash": fees.hash,
+        "skippedExtrinsics": fees.skipped_extrinsics,
+        "medianFee": fees.median_fee().map(|f| f.to_string()),
+        "totalFees": fees.total_fees().to_string(),
+        "totalTips": fees.total_tips().to_string(),
+        "nextFeeMultiplier": multiplier_to_string(fees.next_fee_multiplier),
+        "extrinsics": extrinsics,
+    })
+}
+
+/// The multiplier is a fixed point number with 18 decimal places.
+fn multiplier_to_string(multiplier: u128) -> String {
+    format!(
+        "{}.{:018}",
+        multiplier / FEE_MULTIPLIER_ONE,
+        multiplier % FEE_MULTIPLIER_ONE
+    )
+}
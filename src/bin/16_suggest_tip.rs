@@ -0,0 +1,64 @@
+/*!
+Deciding how much to tip.
+
+Example 05 pays a tip of 500000000000000 without much thought. A tip increases the priority of
+a transaction in the transaction pool, but that only helps if there's competition to get into
+the next few blocks. Here, we look at:
+
+- How full recent blocks were, using `System.BlockWeight` (the weight used in each block) and
+  the `System.BlockWeights` constant (the most weight that can be used).
+- How many transactions are waiting in the pool, using "author_pendingExtrinsics".
+- What tips recent transactions paid, by decoding the signed extrinsics in recent blocks.
+
+And then suggest a tip for getting into a block within some number of blocks.
+
+This example takes 0-3 args;
+- the number of blocks we'd like to be included within (default 1)
+- the number of recent blocks to look at (default 10)
+- a URL to query (if not provided, use $POLKADOT_RPC_URL, or else point to localhost)
+
+```
+cargo run --bin 16_suggest_tip -- 3 20
+```
+*/
+
+use std::env;
+use utils::{get_token_info, rpc_url, suggest_tip, MetadataCache};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut args = env::args().skip(1);
+    let target_blocks: u32 = args.next().and_then(|n| n.parse().ok()).unwrap_or(1);
+    let recent_blocks: u32 = args.next().and_then(|n| n.parse().ok()).unwrap_or(10);
+    let url = args.next().unwrap_or_else(rpc_url);
+
+    let metadata = MetadataCache::new(url.as_str()).metadata().await?;
+    let token = get_token_info(&url).await?;
+    let advice = suggest_tip(&url, &metadata, recent_blocks, target_blocks).await?;
+
+    println!("block   fullness  extrinsics  max tip");
+    for block in &advice.blocks {
+        println!(
+            "{:<7} {:>7.1}%  {:>10}  {}",
+            block.number,
+            block.fullness * 100.0,
+            block.signed_extrinsics,
+            token.format_amount(block.tips.iter().max().copied().unwrap_or(0))
+        );
+    }
+
+    println!();
+    println!(
+        "Average fullness:     {:.1}%",
+        advice.average_fullness * 100.0
+    );
+    println!("Pending extrinsics:   {}", advice.pending_extrinsics);
+    println!("Blocks to clear pool: {:.1}", advice.blocks_to_clear_pool);
+    println!(
+        "Suggested tip to be included within {} block(s): {}",
+        advice.target_blocks,
+        token.format_amount(advice.suggested_tip)
+    );
+
+    Ok(())
+}
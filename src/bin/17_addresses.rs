@@ -0,0 +1,59 @@
+/*!
+Converting between SS58 addresses and account IDs.
+
+An address like "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty" is an account ID (32 bytes)
+along with a prefix saying which chain it's for, and a checksum, encoded in base58 (see
+https://github.com/paritytech/substrate/wiki/External-Address-Format-(SS58)). The same account
+has a different address on each chain; Polkadot uses prefix 0, Kusama uses 2, and dev chains
+usually use the generic substrate prefix, 42.
+
+Here we take an address in any format (or a hex account ID), work out which prefix the
+connected chain uses, and print the account's address on this chain, complaining if the
+address we were given was meant for a different one.
+
+This example takes 1-2 args;
+- an SS58 address or 0x prefixed hex account ID
+- a URL to query (if not provided, use $POLKADOT_RPC_URL, or else point to localhost)
+
+```
+cargo run --bin 17_addresses -- 15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5
+```
+*/
+
+use std::{env, process};
+use utils::{format_address, get_ss58_prefix, parse_address, rpc_url, MetadataCache};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut args = env::args().skip(1);
+    let address = match args.next() {
+        Some(address) => address,
+        None => {
+            eprintln!("cargo run --bin 17_addresses -- ADDRESS [RPC_URL]");
+            process::exit(1);
+        }
+    };
+    let url = args.next().unwrap_or_else(rpc_url);
+
+    let metadata = MetadataCache::new(url.as_str()).metadata().await?;
+    let chain_prefix = get_ss58_prefix(&url, &metadata).await?;
+
+    let parsed = parse_address(&address)?;
+    println!("Account ID:          0x{}", hex::encode(&parsed.account));
+    match parsed.prefix {
+        Some(prefix) => println!("Given with prefix:   {}", prefix),
+        None => println!("Given as hex"),
+    }
+    println!("This chain's prefix: {}", chain_prefix);
+    println!(
+        "Address here:        {}",
+        format_address(&parsed.account, chain_prefix)
+    );
+
+    if let Err(e) = parsed.for_prefix(chain_prefix) {
+        println!();
+        println!("Warning: {}", e);
+    }
+
+    Ok(())
+}
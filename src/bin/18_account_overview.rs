@@ -0,0 +1,108 @@
+/*!
+An overview of everything the chain knows about an account.
+
+Example 04 decodes Bob's `AccountInfo` into types borrowed from the pallet crates, which only
+works if the runtime we're talking to uses exactly those types. Here we decode everything via
+the metadata instead, and pull together the state that various pallets hold about an account:
+
+- Its nonce and balances, from `System.Account`.
+- Locks and freezes on its balance (for instance, from staking or vesting), and the reasons
+  for any reserved balance (reserves and holds), from the Balances pallet. From these we
+  work out how much of the balance can actually be spent.
+- Vesting schedules, from `Vesting.Vesting`.
+- If it's a staking stash, its controller and staking ledger, from the Staking pallet.
+- Its on-chain identity, from `Identity.IdentityOf`.
+
+Runtimes without some of these pallets just have those parts left empty.
+
+This example takes 0-2 args;
+- an SS58 address or 0x prefixed hex account ID (default: Bob)
+- a URL to query (if not provided, use $POLKADOT_RPC_URL, or else point to localhost)
+
+```
+cargo run --bin 18_account_overview -- 5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty
+```
+*/
+
+use sp_keyring::AccountKeyring;
+use std::env;
+use utils::{
+    format_address, get_account_overview, get_ss58_prefix, get_token_info, identity_display_name,
+    parse_address, rpc_url, MetadataCache,
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut args = env::args().skip(1);
+    let address = args.next();
+    let url = args.next().unwrap_or_else(rpc_url);
+
+    let metadata = MetadataCache::new(url.as_str()).metadata().await?;
+    let token = get_token_info(&url).await?;
+    let ss58_prefix = get_ss58_prefix(&url, &metadata).await?;
+
+    let account = match address {
+        Some(address) => parse_address(&address)?.for_prefix(ss58_prefix)?,
+        None => AccountKeyring::Bob.to_account_id(),
+    };
+
+    let overview = get_account_overview(&url, &metadata, &account, None).await?;
+
+    println!("Account:  {}", format_address(&account, ss58_prefix));
+    println!("Nonce:    {}", overview.nonce);
+    let balances = &overview.balances;
+    println!("Free:      {}", token.format_amount(balances.free));
+    println!("Reserved:  {}", token.format_amount(balances.reserved));
+    println!("Frozen:    {}", token.format_amount(balances.frozen));
+    println!("Spendable: {}", token.format_amount(balances.spendable()));
+
+    for lock in &balances.locks {
+        println!(
+            "  locked by {}: {} ({})",
+            lock.id,
+            token.format_amount(lock.amount),
+            lock.reasons
+        );
+    }
+    let named = [
+        ("reserved", &balances.reserves),
+        ("held", &balances.holds),
+        ("frozen", &balances.freezes),
+    ];
+    for (what, items) in named {
+        for item in items {
+            println!(
+                "  {} by {}: {}",
+                what,
+                item.id,
+                token.format_amount(item.amount)
+            );
+        }
+    }
+
+    if !overview.vesting.is_empty() {
+        println!("\nVesting:");
+        for schedule in &overview.vesting {
+            println!("  {}", schedule);
+        }
+    }
+
+    if let Some(staking) = &overview.staking {
+        println!(
+            "\nStaking controller: {}",
+            format_address(&staking.controller, ss58_prefix)
+        );
+        if let Some(ledger) = &staking.ledger {
+            println!("Staking ledger: {}", ledger);
+        }
+    }
+
+    if let Some(identity) = &overview.identity {
+        match identity_display_name(identity) {
+            Some(name) => println!("\nIdentity: {}", name),
+            None => println!("\nIdentity: {}", identity),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+/*!
+A pretend node, for running the other examples without a real one.
+
+Every other example talks to a node on localhost (or some other URL). This one stands in for
+that node, listening on the usual ports (9933 for HTTP and 9944 for WS) and answering RPC calls
+with canned responses from a fixtures file. Each fixture gives the response to some method
+(optionally, only when called with certain params), and can hand back a result, an error, or a
+series of subscription notifications; see `utils::Fixture` for the format.
+
+"rpc_methods" lists the methods we have fixtures for unless a fixture says otherwise, and
+unknown methods get a "Method not found" error, like a real node would give.
+
+A session with a real node can be recorded into the same format (see example 20), and then
+replayed with "--replay"; in this mode, if a call was recorded several times, each recorded
+response is given in turn, so the session plays out just as it did against the real node.
+
+This example takes 1-3 args, along with some options;
+- the path to a JSON fixtures file
+- the port to serve HTTP on (default 9933)
+- the port to serve WS on (default 9944)
+- "--replay", to replay the fixtures in order
+- "--metadata FILE", to answer "state_getMetadata" with the metadata in FILE (as raw SCALE
+  bytes, or the hex string that a node hands back)
+
+```
+cargo run --bin 19_mock_node -- tests/fixtures/polkadot.json --metadata tests/fixtures/polkadot_metadata.scale
+cargo run --bin 19_mock_node -- recording.json --replay
+```
+
+And then, in another terminal, run any other example as usual. To run them against the mock
+node on other ports, point them at it with the POLKADOT_RPC_URL and POLKADOT_WS_URL
+environment variables.
+*/
+
+use std::{env, net::SocketAddr, process};
+use utils::{load_fixtures, metadata_fixture, MockNode};
+
+const USAGE: &str =
+    "cargo run --bin 19_mock_node -- FIXTURES [HTTP_PORT] [WS_PORT] [--replay] [--metadata FILE]";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut replay = false;
+    let mut metadata_path = None;
+    let mut positional = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--replay" => replay = true,
+            "--metadata" => metadata_path = args.next(),
+            _ => positional.push(arg),
+        }
+    }
+    let mut args = positional.into_iter();
+
+    let fixtures_path = match args.next() {
+        Some(path) => path,
+        None => {
+            eprintln!("{}", USAGE);
+            process::exit(1);
+        }
+    };
+    let http_port: u16 = args.next().and_then(|p| p.parse().ok()).unwrap_or(9933);
+    let ws_port: u16 = args.next().and_then(|p| p.parse().ok()).unwrap_or(9944);
+
+    let mut fixtures = load_fixtures(&fixtures_path)?;
+    println!("Loaded {} fixtures from {}", fixtures.len(), fixtures_path);
+    if let Some(path) = metadata_path {
+        fixtures.push(metadata_fixture(&path)?);
+        println!("Serving the metadata in {}", path);
+    }
+
+    let http_addr: SocketAddr = ([127, 0, 0, 1], http_port).into();
+    let ws_addr: SocketAddr = ([127, 0, 0, 1], ws_port).into();
+    let node = if replay {
+        MockNode::replay_on(fixtures, http_addr, ws_addr).await?
+    } else {
+        MockNode::start_on(fixtures, http_addr, ws_addr).await?
+    };
+    println!("Serving HTTP on {}", node.http_url);
+    println!("Serving WS on {}", node.ws_url);
+
+    tokio::signal::ctrl_c().await?;
+    Ok(())
+}
@@ -0,0 +1,93 @@
+/*!
+Recording a session with a node, to look at or replay later.
+
+When some query against a public node gives a strange result, it helps to capture exactly
+what the node said. `utils::Recorder` wraps the usual ways of talking to a node (a URL that
+we make requests to over HTTP, or `utils::ws_client` over WS), and writes every call, along
+with its response and any subscription notifications, to a file. Anything wrapped by
+`Recorder::wrap` can be handed to the utils in place of the URL.
+
+Here we record what example 18 does to show Bob's account, and what the fees were in the
+latest block (as example 15 works out), and then follow a few new block headers via a
+subscription. The recording can then be replayed by example 19, after which example 18 will
+run against it just as it did against the node:
+
+```
+cargo run --bin 20_record_session -- recording.json
+cargo run --bin 19_mock_node -- recording.json --replay
+cargo run --bin 18_account_overview
+```
+
+This example takes 1-3 args;
+- the path to write the recording to
+- an HTTP URL to query (if not provided, use $POLKADOT_RPC_URL, or else point to localhost)
+- a WS URL to query (if not provided, use $POLKADOT_WS_URL, or else point to localhost)
+*/
+
+use jsonrpsee::{
+    core::client::{Subscription, SubscriptionClientT},
+    rpc_params,
+};
+use serde_json::Value;
+use sp_keyring::AccountKeyring;
+use std::{env, process};
+use utils::{
+    block_fees, get_account_overview, get_metadata, get_ss58_prefix, get_token_info,
+    number_from_json, rpc, rpc_url, ws_url, Recorder,
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut args = env::args().skip(1);
+    let path = match args.next() {
+        Some(path) => path,
+        None => {
+            eprintln!("cargo run --bin 20_record_session -- FILE [RPC_URL] [WS_URL]");
+            process::exit(1);
+        }
+    };
+    let url = args.next().unwrap_or_else(rpc_url);
+    let ws_url = args.next().unwrap_or_else(ws_url);
+
+    let recorder = Recorder::create(&path)?;
+    let node = recorder.wrap(url.as_str());
+
+    // The same calls as example 18 makes (for Bob, since no address is given):
+    let metadata = get_metadata(&node).await?;
+    let token = get_token_info(&node).await?;
+    get_ss58_prefix(&node, &metadata).await?;
+    let bob = AccountKeyring::Bob.to_account_id();
+    let overview = get_account_overview(&node, &metadata, &bob, None).await?;
+    println!(
+        "Bob has {} free",
+        token.format_amount(overview.balances.free)
+    );
+
+    let header = rpc(&node, "chain_getHeader", ()).await?;
+    let latest = number_from_json(&header["number"], "number")? as u64;
+    let fees = block_fees(&node, latest).await?;
+    println!(
+        "Block {} paid {} in fees",
+        latest,
+        token.format_amount(fees.total_fees())
+    );
+
+    let client = recorder.ws_client(&ws_url).await?;
+    let mut headers: Subscription<Value> = client
+        .subscribe(
+            "chain_subscribeNewHeads",
+            rpc_params![],
+            "chain_unsubscribeNewHeads",
+        )
+        .await?;
+    for _ in 0..3 {
+        match headers.next().await {
+            Some(header) => println!("New block: {}", header?["number"]),
+            None => break,
+        }
+    }
+
+    recorder.finish()?;
+    println!("Recorded {} calls to {}", recorder.calls().len(), path);
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use serde_json::json;
+use utils::{
+    format_address, get_account_overview, get_ss58_prefix, get_token_info, identity_display_name,
+    parse_address,
+};
+
+use crate::{
+    output::{print_json, Output},
+    usage, Options,
+};
+
+/// Show everything the chain knows about an account (see example 18).
+pub async fn run(options: &Options, args: &[String]) -> anyhow::Result<()> {
+    let address = match args {
+        [address] => address,
+        _ => return Err(usage("expected 'account ADDRESS'")),
+    };
+
+    let url = &options.url;
+    let at = options.at_hash().await?;
+    let metadata = options.metadata().await?;
+    let token = get_token_info(url).await?;
+    let ss58_prefix = get_ss58_prefix(url, &metadata).await?;
+    let account = parse_address(address)?.for_prefix(ss58_prefix)?;
+
+    let overview = get_account_overview(url, &metadata, &account, at.as_deref()).await?;
+    let balances = &overview.balances;
+
+    if options.output == Output::Json {
+        let named = |items: &[utils::NamedBalance]| {
+            items
+                .iter()
+                .map(|item| json!({ "id": item.id, "amount": item.amount.to_string() }))
+                .collect::<Vec<_>>()
+        };
+        print_json(&json!({
+            "address": format_address(&account, ss58_prefix),
+            "nonce": overview.nonce,
+            "free": balances.free.to_string(),
+            "reserved": balances.reserved.to_string(),
+            "frozen": balances.frozen.to_string(),
+            "spendable": balances.spendable().to_string(),
+            "locks": balances.locks.iter().map(|lock| json!({
+                "id": lock.id,
+                "amount": lock.amount.to_string(),
+                "reasons": lock.reasons,
+            })).collect::<Vec<_>>(),
+            "reserves": named(&balances.reserves),
+            "holds": named(&balances.holds),
+            "freezes": named(&balances.freezes),
+            "vesting": overview.vesting,
+            "staking": overview.staking.as_ref().map(|staking| json!({
+                "controller": format_address(&staking.controller, ss58_prefix),
+                "ledger": staking.ledger,
+            })),
+            "identity": overview.identity,
+        }));
+        return Ok(());
+    }
+
+    println!("Account:   {}", format_address(&account, ss58_prefix));
+    println!("Nonce:     {}", overview.nonce);
+    println!("Free:      {}", token.format_amount(balances.free));
+    println!("Reserved:  {}", token.format_amount(balances.reserved));
+    println!("Frozen:    {}", token.format_amount(balances.frozen));
+    println!("Spendable: {}", token.format_amount(balances.spendable()));
+
+    for lock in &balances.locks {
+        println!(
+            "  locked by {}: {} ({})",
+            lock.id,
+            token.format_amount(lock.amount),
+            lock.reasons
+        );
+    }
+    let named = [
+        ("reserved", &balances.reserves),
+        ("held", &balances.holds),
+        ("frozen", &balances.freezes),
+    ];
+    for (what, items) in named {
+        for item in items {
+            println!(
+                "  {} by {}: {}",
+                what,
+                item.id,
+                token.format_amount(item.amount)
+            );
+        }
+    }
+
+    for schedule in &overview.vesting {
+        println!("Vesting:   {}", schedule);
+    }
+    if let Some(staking) = &overview.staking {
+        println!(
+            "Staking controller: {}",
+            format_address(&staking.controller, ss58_prefix)
+        );
+        if let Some(ledger) = &staking.ledger {
+            println!("Staking ledger:     {}", ledger);
+        }
+    }
+    if let Some(identity) = &overview.identity {
+        match identity_display_name(identity) {
+            Some(name) => println!("Identity:  {}", name),
+            None => println!("Identity:  {}", identity),
+        }
+    }
+    Ok(())
+}
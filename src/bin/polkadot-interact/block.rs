@@ -0,0 +1,119 @@
+use serde_json::{json, Value};
+use utils::{
+    decode_signed_details, format_address, get_block_hash, get_events, get_ss58_prefix,
+    get_token_info, number_from_json, rpc, ExtrinsicSigner,
+};
+
+use crate::{
+    output::{event_json, phase_name, print_json, Output},
+    usage, Options,
+};
+
+/// Show a block, along with who signed each of its extrinsics and the events it emitted
+/// (see example 02). Without a block, show the one given with "--at", or else the latest.
+pub async fn run(options: &Options, args: &[String]) -> anyhow::Result<()> {
+    let url = &options.url;
+    let block_hash = match args {
+        [] => match options.at_hash().await? {
+            Some(hash) => hash,
+            None => rpc(url, "chain_getBlockHash", ())
+                .await?
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("chain_getBlockHash did not return a string"))?
+                .to_string(),
+        },
+        [block] => get_block_hash(url, block).await?,
+        _ => return Err(usage("block takes at most one argument: BLOCK")),
+    };
+
+    let block = rpc(url, "chain_getBlock", [&block_hash]).await?;
+    let header = &block["block"]["header"];
+    let number = number_from_json(&header["number"], "number")? as u64;
+    let extrinsics: Vec<&str> = block["block"]["extrinsics"]
+        .as_array()
+        .ok_or_else(|| anyhow::anyhow!("block {} has no extrinsics", block_hash))?
+        .iter()
+        .filter_map(|ext| ext.as_str())
+        .collect();
+
+    let metadata = options.metadata_at(&block_hash).await?;
+    let events = get_events(url, &metadata, Some(&block_hash)).await?;
+    let ss58_prefix = get_ss58_prefix(url, &metadata).await?;
+    let token = get_token_info(url).await?;
+
+    if options.output == Output::Json {
+        let extrinsics: Vec<Value> = extrinsics
+            .iter()
+            .enumerate()
+            .map(|(index, ext)| {
+                let signed = hex::decode(ext.trim_start_matches("0x"))
+                    .ok()
+                    .and_then(|bytes| decode_signed_details(&bytes).signed().cloned());
+                json!({
+                    "index": index,
+                    "hex": ext,
+                    "signer": signed.as_ref().map(|s| format_address(&s.signer, ss58_prefix)),
+                    "nonce": signed.as_ref().map(|s| s.nonce),
+                    "tip": signed.as_ref().map(|s| s.tip.to_string()),
+                })
+            })
+            .collect();
+        print_json(&json!({
+            "number": number,
+            "hash": block_hash,
+            "header": header,
+            "extrinsics": extrinsics,
+            "events": events.iter().map(event_json).collect::<Vec<_>>(),
+        }));
+        return Ok(());
+    }
+
+    println!("Block:           #{} {}", number, block_hash);
+    println!(
+        "Parent:          {}",
+        header["parentHash"].as_str().unwrap_or_default()
+    );
+    println!(
+        "State root:      {}",
+        header["stateRoot"].as_str().unwrap_or_default()
+    );
+    println!(
+        "Extrinsics root: {}",
+        header["extrinsicsRoot"].as_str().unwrap_or_default()
+    );
+
+    println!();
+    println!("Extrinsics:");
+    for (index, ext) in extrinsics.iter().enumerate() {
+        let bytes = hex::decode(ext.trim_start_matches("0x"))?;
+        match decode_signed_details(&bytes) {
+            ExtrinsicSigner::Signed(signed) => println!(
+                "  {}: signed by {} (nonce {}, tip {}), {} bytes",
+                index,
+                format_address(&signed.signer, ss58_prefix),
+                signed.nonce,
+                token.format_amount(signed.tip),
+                bytes.len()
+            ),
+            ExtrinsicSigner::Unsigned => println!("  {}: unsigned, {} bytes", index, bytes.len()),
+            ExtrinsicSigner::Undecodable(_) => println!(
+                "  {}: signed (in a format we can't decode), {} bytes",
+                index,
+                bytes.len()
+            ),
+        }
+    }
+
+    println!();
+    println!("Events:");
+    for event in &events {
+        println!(
+            "  {}.{} ({}): {}",
+            event.pallet,
+            event.variant,
+            phase_name(event.phase),
+            event.fields
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use serde_json::json;
+use utils::{
+    block_hash_count, constants, existential_deposit, get_token_info, max_exposure_page_size,
+    max_nominators_rewarded, ss58_prefix,
+};
+
+use crate::{
+    output::{print_json, Output},
+    usage, Options,
+};
+
+/// Show the constants of every pallet (or of one pallet, or just one constant), decoded
+/// using the metadata, along with their docs (see example 03).
+pub async fn run(options: &Options, args: &[String]) -> anyhow::Result<()> {
+    let (pallet_name, constant_name) = match args {
+        [] => (None, None),
+        [pallet_name] => (Some(pallet_name), None),
+        [pallet_name, constant_name] => (Some(pallet_name), Some(constant_name)),
+        _ => return Err(usage("expected 'constants [PALLET [NAME]]'")),
+    };
+
+    let metadata = options.metadata().await?;
+    let constants: Vec<_> = constants(&metadata)?
+        .into_iter()
+        .filter(|c| pallet_name.iter().all(|name| &c.pallet == *name))
+        .filter(|c| constant_name.iter().all(|name| &c.name == *name))
+        .collect();
+    if constants.is_empty() {
+        anyhow::bail!("no constants found");
+    }
+
+    if options.output == Output::Json {
+        print_json(&json!(constants
+            .iter()
+            .map(|c| json!({
+                "pallet": c.pallet,
+                "name": c.name,
+                "value": c.value,
+                "docs": c.docs,
+            }))
+            .collect::<Vec<_>>()));
+        return Ok(());
+    }
+
+    // Start with the constants that people most often want to know about:
+    if pallet_name.is_none() {
+        let token = get_token_info(&options.url).await?;
+        let ed = existential_deposit(&metadata)?;
+        println!("Existential deposit:  {}", token.format_amount(ed));
+        println!("Block hash count:     {}", block_hash_count(&metadata)?);
+        if let Some(prefix) = ss58_prefix(&metadata)? {
+            println!("SS58 prefix:          {}", prefix);
+        }
+        if let Some(max) = max_nominators_rewarded(&metadata)? {
+            println!("Max nominators paid:  {} per validator", max);
+        }
+        if let Some(size) = max_exposure_page_size(&metadata)? {
+            println!("Rewards page size:    {} nominators", size);
+        }
+        println!();
+    }
+
+    for constant in &constants {
+        println!("{}.{}: {}", constant.pallet, constant.name, constant.value);
+        for line in &constant.docs {
+            println!("    {}", line.trim());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use serde_json::json;
+use utils::{
+    actual_fee_paid, get_block_hash, get_token_info, query_fees, FeeEstimate, TokenInfo, Weight,
+};
+
+use crate::{
+    output::{print_json, Output},
+    usage, Options,
+};
+
+/// Work out the fees for an extrinsic.
+///
+/// - "fees EXTRINSIC" estimates the fees that a hex encoded extrinsic would pay (see example 07).
+/// - "fees BLOCK INDEX" shows the fees that an extrinsic in some block actually paid (see
+///   example 14).
+pub async fn run(options: &Options, args: &[String]) -> anyhow::Result<()> {
+    let url = &options.url;
+    let token = get_token_info(url).await?;
+
+    match args {
+        [extrinsic_hex] => {
+            let extrinsic = hex::decode(extrinsic_hex.trim_start_matches("0x"))?;
+            let at = options.at_hash().await?;
+            let estimate = query_fees(url, &extrinsic, at.as_deref()).await?;
+
+            match options.output {
+                Output::Json => print_json(&estimate_json(&estimate)),
+                Output::Table => print_estimate(&token, &estimate),
+            }
+        }
+        [block, index] => {
+            let index: u32 = index
+                .parse()
+                .map_err(|_| usage(format!("'{}' is not an extrinsic index", index)))?;
+            let block_hash = get_block_hash(url, block).await?;
+            let metadata = options.metadata_at(&block_hash).await?;
+            let fee = actual_fee_paid(url, &metadata, &block_hash, index).await?;
+
+            match options.output {
+                Output::Json => print_json(&json!({
+                    "block_hash": block_hash,
+                    "extrinsic_index": index,
+                    "estimate": estimate_json(&fee.estimate),
+                    "actual_weight": weight_json(fee.actual_weight),
+                    "pays_fee": fee.pays_fee,
+                    "partial_fee": fee.partial_fee.to_string(),
+                    "tip": fee.tip.to_string(),
+                    "total": fee.total().to_string(),
+                    "from_event": fee.from_event,
+                })),
+                Output::Table => {
+                    print_estimate(&token, &fee.estimate);
+                    println!();
+                    println!("Actual weight:  {:?}", fee.actual_weight);
+                    println!("Pays fee:       {}", fee.pays_fee);
+                    println!("Fee paid:       {}", token.format_amount(fee.partial_fee));
+                    println!("Tip paid:       {}", token.format_amount(fee.tip));
+                    println!("Total paid:     {}", token.format_amount(fee.total()));
+                }
+            }
+        }
+        _ => return Err(usage("expected 'fees EXTRINSIC' or 'fees BLOCK INDEX'")),
+    }
+    Ok(())
+}
+
+fn estimate_json(estimate: &FeeEstimate) -> serde_json::Value {
+    json!({
+        "weight": weight_json(estimate.weight),
+        "class": estimate.class,
+        "partial_fee": estimate.partial_fee.to_string(),
+        "inclusion_fee": estimate.inclusion_fee.map(|fee| json!({
+            "base_fee": fee.base_fee.to_string(),
+            "len_fee": fee.len_fee.to_string(),
+            "adjusted_weight_fee": fee.adjusted_weight_fee.to_string(),
+        })),
+    })
+}
+
+fn weight_json(weight: Weight) -> serde_json::Value {
+    json!({ "ref_time": weight.ref_time, "proof_size": weight.proof_size })
+}
+
+fn print_estimate(token: &TokenInfo, estimate: &FeeEstimate) {
+    println!("Weight:         {:?}", estimate.weight);
+    println!("Class:          {}", estimate.class);
+    println!(
+        "Partial fee:    {}",
+        token.format_amount(estimate.partial_fee)
+    );
+    if let Some(fee) = &estimate.inclusion_fee {
+        println!("  base fee:     {}", token.format_amount(fee.base_fee));
+        println!("  length fee:   {}", token.format_amount(fee.len_fee));
+        println!(
+            "  weight fee:   {}",
+            token.format_amount(fee.adjusted_weight_fee)
+        );
+    }
+}
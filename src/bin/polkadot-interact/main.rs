@@ -0,0 +1,230 @@
+/*!
+One CLI for the things that the numbered examples show how to do.
+
+Each of the numbered examples does one thing against a hardcoded target (Bob's account, the
+XcmPallet, localhost and so on). This binary does the same things (reusing the logic in
+`utils`), but lets us say what we'd like to look at:
+
+```text
+polkadot-interact [OPTIONS] COMMAND [ARGS]
+
+Commands:
+  methods                           List the node's RPC methods (example 01)
+  block [BLOCK]                     Show a block, its extrinsics and events (example 02)
+  metadata                          Show the pallets in the metadata (example 03)
+  metadata save FILE                Save the metadata to a file
+  metadata diff OLD NEW             Compare the metadata at two blocks, or saved to two files
+  metadata codegen SOURCE OUT.rs    Generate Rust types, calls and storage keys from metadata
+  metadata docs [SOURCE] OUT.md     Render the pallets' docs to Markdown (or to HTML, as .html)
+  constants [PALLET [NAME]]         Show the pallets' constants, decoded, with their docs (03)
+  storage get PALLET ENTRY [KEY..]  Fetch a storage value, or its default (example 04)
+  storage iter PALLET ENTRY [KEY..] Fetch every value stored under an entry (example 06)
+  transfer DEST AMOUNT [--from S]   Transfer some balance, by default from Alice (example 05)
+  tx PALLET CALL [ARG=VALUE..]      Submit any call, by default from Alice (example 05)
+  fees EXTRINSIC                    Estimate the fees for a hex encoded extrinsic (example 07)
+  fees BLOCK INDEX                  Show the fees paid by an extrinsic in a block (example 14)
+  account ADDRESS                   Show an account's balances, staking and identity (example 18)
+
+Options:
+  --url URL        The node's HTTP RPC URL (default: $POLKADOT_RPC_URL, or http://localhost:9933)
+  --ws-url URL     The node's WS RPC URL, used to watch transfers (default: $POLKADOT_WS_URL,
+                   or ws://localhost:9944)
+  --at BLOCK       Look at the state as of this block number or hash (default: the latest)
+  --output FORMAT  "table" for human readable output (the default), or "json"
+```
+
+Blocks can be given as a number or as a 0x prefixed hash, and accounts as an SS58 address or
+as a 0x prefixed hex account ID. Amounts are given in the chain's token, like "1.5" or "1.5 DOT".
+Storage keys are given as JSON in the shape that values are printed in (see `utils::encode_value`),
+or as plain text for strings and addresses (as in `storage get System Account ADDRESS`).
+Call arguments are given in the same way, and balances are given in plancks unless they have
+the token's symbol (like "value=1.2DOT").
+
+The metadata is cached on disk between runs (see `utils::MetadataCache`), in the directory
+named by the POLKADOT_METADATA_CACHE environment variable, or else in the temporary directory.
+
+```
+cargo run --bin polkadot-interact -- account 5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty
+cargo run --bin polkadot-interact -- --at 10 --output json storage get System Number
+cargo run --bin polkadot-interact -- storage iter XcmPallet VersionNotifiers
+cargo run --bin polkadot-interact -- tx Balances transfer_keep_alive \
+    dest=5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty value=1.2DOT
+cargo run --bin polkadot-interact -- tx System remark_with_event remark="hello there" --from //Bob
+```
+*/
+
+mod account;
+mod block;
+mod constants;
+mod fees;
+mod metadata;
+mod methods;
+mod output;
+mod storage;
+mod transfer;
+mod tx;
+
+use frame_metadata::RuntimeMetadataV14;
+use output::Output;
+use std::{env, process, sync::Arc};
+use utils::{get_block_hash, rpc_url, ws_url, MetadataCache};
+
+const USAGE: &str =
+    "polkadot-interact [--url URL] [--ws-url URL] [--at BLOCK] [--output table|json] COMMAND [ARGS]
+
+Commands: methods, block, metadata, metadata save, metadata diff, metadata codegen,
+metadata docs, constants, storage get, storage iter, transfer, tx, fees, account
+(see the docs at the top of src/bin/polkadot-interact/main.rs for more)";
+
+/// The options which apply to every command.
+pub struct Options {
+    pub url: String,
+    pub ws_url: String,
+    /// The block number or hash given with "--at", if any.
+    pub at: Option<String>,
+    pub output: Output,
+    /// One cache for the whole run, so that metadata is only decoded once, and so that
+    /// [`MetadataCache::watch_upgrades`] can keep it up to date while we wait on a transaction.
+    pub metadata_cache: MetadataCache,
+}
+
+impl Options {
+    /// The hash of the block given with "--at", if any.
+    pub async fn at_hash(&self) -> anyhow::Result<Option<String>> {
+        match &self.at {
+            Some(block) => Ok(Some(get_block_hash(&self.url, block).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The metadata as of the block given with "--at", or the latest block. This is cached
+    /// (see `utils::MetadataCache`), so it's only downloaded again after a runtime upgrade.
+    pub async fn metadata(&self) -> anyhow::Result<Arc<RuntimeMetadataV14>> {
+        match self.at_hash().await? {
+            Some(block_hash) => self.metadata_at(&block_hash).await,
+            None => self.latest_metadata().await,
+        }
+    }
+
+    /// The metadata for the latest block, whatever "--at" says (for submitting transactions).
+    pub async fn latest_metadata(&self) -> anyhow::Result<Arc<RuntimeMetadataV14>> {
+        self.metadata_cache.metadata().await
+    }
+
+    /// The metadata as of some block hash, from the cache.
+    pub async fn metadata_at(&self, block_hash: &str) -> anyhow::Result<Arc<RuntimeMetadataV14>> {
+        self.metadata_cache.metadata_at(block_hash).await
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let (options, args) = match parse_args(env::args().skip(1)) {
+        Ok(parsed) => parsed,
+        Err(e) => usage_error(&e.to_string()),
+    };
+
+    let command = args.first().map(|c| c.as_str()).unwrap_or_default();
+    let command_args = args.get(1..).unwrap_or_default();
+    let result = match command {
+        "methods" => methods::run(&options).await,
+        "block" => block::run(&options, command_args).await,
+        "metadata" => metadata::run(&options, command_args).await,
+        "constants" => constants::run(&options, command_args).await,
+        "storage" => storage::run(&options, command_args).await,
+        "transfer" => transfer::run(&options, command_args).await,
+        "tx" => tx::run(&options, command_args).await,
+        "fees" => fees::run(&options, command_args).await,
+        "account" => account::run(&options, command_args).await,
+        "" => usage_error("no command given"),
+        other => usage_error(&format!("unknown command '{}'", other)),
+    };
+
+    if let Err(e) = result {
+        // Arguments which don't fit the command are worth reminding the user how to use it:
+        if e.is::<UsageError>() {
+            usage_error(&e.to_string());
+        }
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Pull the global options out of the arguments (they can go anywhere), handing back
+/// the options and the remaining arguments (the command and its arguments).
+fn parse_args(mut args: impl Iterator<Item = String>) -> anyhow::Result<(Options, Vec<String>)> {
+    let mut url = rpc_url();
+    let mut ws_url = ws_url();
+    let mut at = None;
+    let mut output = Output::Table;
+    let mut rest = Vec::new();
+
+    while let Some(arg) = args.next() {
+        let (name, inline_value) = split_option(&arg);
+        if !["--url", "--ws-url", "--at", "--output"].contains(&name) {
+            rest.push(arg);
+            continue;
+        }
+
+        let value = match inline_value {
+            Some(value) => value.to_string(),
+            None => args
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("{} needs a value", name))?,
+        };
+        match name {
+            "--url" => url = value,
+            "--ws-url" => ws_url = value,
+            "--at" => at = Some(value),
+            _ => output = value.parse()?,
+        }
+    }
+
+    let options = Options {
+        metadata_cache: MetadataCache::new(url.as_str()),
+        url,
+        ws_url,
+        at,
+        output,
+    };
+    Ok((options, rest))
+}
+
+/// Options can be given as "--name VALUE" or "--name=VALUE"; in the latter case, this splits
+/// the value off, handing back the name and the value.
+pub fn split_option(arg: &str) -> (&str, Option<&str>) {
+    match arg.split_once('=') {
+        Some((name, value)) if name.starts_with("--") => (name, Some(value)),
+        _ => (arg, None),
+    }
+}
+
+/// An error in the arguments given to a command.
+#[derive(Debug)]
+pub struct UsageError(pub String);
+
+impl std::fmt::Display for UsageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for UsageError {}
+
+/// Complain about the arguments given to a command.
+pub fn usage(message: impl Into<String>) -> anyhow::Error {
+    UsageError(message.into()).into()
+}
+
+/// Storage keys and call arguments are given as JSON, in the same shape that values are
+/// printed in (so `1`, `[1, 2]` or `{"V3": ...}`; see `utils::encode_value`). Anything which
+/// isn't valid JSON is taken to be a string, so that addresses, hex and text don't need to be
+/// wrapped in quotes.
+pub fn parse_value(text: &str) -> serde_json::Value {
+    serde_json::from_str(text).unwrap_or_else(|_| serde_json::Value::String(text.to_string()))
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("Error: {}\n\n{}", message, USAGE);
+    process::exit(1);
+}
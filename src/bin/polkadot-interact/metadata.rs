@@ -0,0 +1,181 @@
+use frame_metadata::RuntimeMetadataV14;
+use serde_json::json;
+use std::{path::Path, sync::Arc};
+use utils::{
+    diff_metadata, generate_code, get_block_hash, load_metadata, metadata_docs, rpc, variants,
+    DocsFormat,
+};
+
+use crate::{
+    output::{print_json, Output},
+    usage, Options,
+};
+
+/// Show the metadata (see example 03).
+///
+/// - "metadata" shows the pallets in the metadata, or the whole thing as JSON.
+/// - "metadata save FILE" saves the metadata (as the hex that "state_getMetadata" hands back).
+/// - "metadata diff OLD NEW" compares two versions of the metadata, each given as a block
+///   number or hash, or as a file that the metadata was saved to.
+/// - "metadata codegen SOURCE OUT.rs" generates Rust code for the types, calls, storage and
+///   constants in the metadata at a block, or saved to a file (see `utils::generate_code`).
+/// - "metadata docs [SOURCE] OUT.md" renders the docs of every pallet's calls, events, errors,
+///   storage and constants to Markdown, or to HTML if the file ends in ".html".
+pub async fn run(options: &Options, args: &[String]) -> anyhow::Result<()> {
+    match args {
+        [] => show(options).await,
+        [save, path] if save == "save" => self::save(options, path).await,
+        [diff, old, new] if diff == "diff" => self::diff(options, old, new).await,
+        [codegen, source, path] if codegen == "codegen" => {
+            self::codegen(options, source, path).await
+        }
+        [docs, path] if docs == "docs" => self::docs(options, None, path).await,
+        [docs, source, path] if docs == "docs" => self::docs(options, Some(source), path).await,
+        _ => Err(usage(
+            "expected 'metadata', 'metadata save FILE', 'metadata diff OLD NEW', \
+             'metadata codegen SOURCE OUT.rs' or 'metadata docs [SOURCE] OUT.md'",
+        )),
+    }
+}
+
+async fn show(options: &Options) -> anyhow::Result<()> {
+    let metadata = options.metadata().await?;
+
+    if options.output == Output::Json {
+        print_json(&serde_json::to_value(metadata.as_ref())?);
+        return Ok(());
+    }
+
+    // The number of variants in the enum of calls, events or errors, if the pallet has one:
+    let count = |ty: Option<u32>| {
+        ty.and_then(|ty| variants(&metadata.types, ty))
+            .map(|variants| variants.len())
+            .unwrap_or(0)
+    };
+
+    println!(
+        "{:>5}  {:<28} {:>6} {:>6} {:>6} {:>8} {:>10}",
+        "index", "pallet", "calls", "events", "errors", "storage", "constants"
+    );
+    for pallet in &metadata.pallets {
+        println!(
+            "{:>5}  {:<28} {:>6} {:>6} {:>6} {:>8} {:>10}",
+            pallet.index,
+            pallet.name,
+            count(pallet.calls.as_ref().map(|c| c.ty.id())),
+            count(pallet.event.as_ref().map(|e| e.ty.id())),
+            count(pallet.error.as_ref().map(|e| e.ty.id())),
+            pallet
+                .storage
+                .as_ref()
+                .map(|s| s.entries.len())
+                .unwrap_or(0),
+            pallet.constants.len(),
+        );
+    }
+    println!();
+    println!(
+        "Signed extensions: {}",
+        metadata
+            .extrinsic
+            .signed_extensions
+            .iter()
+            .map(|e| e.identifier.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    Ok(())
+}
+
+/// Save the metadata to a file, to compare against later on (or to use offline).
+async fn save(options: &Options, path: &str) -> anyhow::Result<()> {
+    let at = options.at_hash().await?;
+    let metadata_hex = rpc(&options.url, "state_getMetadata", (at,)).await?;
+    let metadata_hex = metadata_hex
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("state_getMetadata did not return a string"))?;
+    std::fs::write(path, metadata_hex)?;
+    println!("Saved the metadata to {}", path);
+    Ok(())
+}
+
+/// List what changed between two versions of the metadata, starting with the changes which
+/// break hardcoded indices and encodings.
+async fn diff(options: &Options, old: &str, new: &str) -> anyhow::Result<()> {
+    let old_metadata = load(options, old).await?;
+    let new_metadata = load(options, new).await?;
+    let mut changes = diff_metadata(&old_metadata, &new_metadata);
+    changes.sort_by_key(|change| !change.breaking);
+
+    if options.output == Output::Json {
+        print_json(&json!(changes
+            .iter()
+            .map(|change| json!({
+                "pallet": change.pallet,
+                "description": change.description,
+                "breaking": change.breaking,
+            }))
+            .collect::<Vec<_>>()));
+        return Ok(());
+    }
+
+    for change in &changes {
+        println!(
+            "{:<9} {}: {}",
+            if change.breaking { "BREAKING" } else { "" },
+            change.pallet.as_deref().unwrap_or("Extrinsic"),
+            change.description
+        );
+    }
+    println!();
+    println!(
+        "{} changes, {} of them breaking",
+        changes.len(),
+        changes.iter().filter(|change| change.breaking).count()
+    );
+    Ok(())
+}
+
+/// Generate Rust code from the metadata and write it to a file, to `include!` in place of
+/// the runtime's own crates.
+async fn codegen(options: &Options, source: &str, path: &str) -> anyhow::Result<()> {
+    let metadata = load(options, source).await?;
+    std::fs::write(path, generate_code(&metadata)?)?;
+    println!(
+        "Generated code for {} pallets in {}",
+        metadata.pallets.len(),
+        path
+    );
+    Ok(())
+}
+
+/// Render the docs in the metadata (as of `--at`, or from `source`) to a file, for those who
+/// would rather not read JSON or Rust to find out what the chain supports.
+async fn docs(options: &Options, source: Option<&String>, path: &str) -> anyhow::Result<()> {
+    let metadata = match source {
+        Some(source) => load(options, source).await?,
+        None => options.metadata().await?,
+    };
+    let format = if path.ends_with(".html") || path.ends_with(".htm") {
+        DocsFormat::Html
+    } else {
+        DocsFormat::Markdown
+    };
+    std::fs::write(path, metadata_docs(&metadata, format))?;
+    println!(
+        "Wrote the docs for {} pallets to {}",
+        metadata.pallets.len(),
+        path
+    );
+    Ok(())
+}
+
+/// Load metadata from a file if one exists at the path given, and otherwise fetch
+/// it as of the block given.
+async fn load(options: &Options, source: &str) -> anyhow::Result<Arc<RuntimeMetadataV14>> {
+    if Path::new(source).is_file() {
+        return Ok(Arc::new(load_metadata(source)?));
+    }
+    let block_hash = get_block_hash(&options.url, source).await?;
+    options.metadata_at(&block_hash).await
+}
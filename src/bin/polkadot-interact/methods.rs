@@ -0,0 +1,28 @@
+use serde_json::json;
+use utils::rpc;
+
+use crate::{
+    output::{print_json, Output},
+    Options,
+};
+
+/// List the RPC methods that the node supports (see example 01).
+pub async fn run(options: &Options) -> anyhow::Result<()> {
+    let result = rpc(&options.url, "rpc_methods", ()).await?;
+    let methods: Vec<&str> = result["methods"]
+        .as_array()
+        .ok_or_else(|| anyhow::anyhow!("rpc_methods did not return a list of methods"))?
+        .iter()
+        .filter_map(|method| method.as_str())
+        .collect();
+
+    match options.output {
+        Output::Json => print_json(&json!(methods)),
+        Output::Table => {
+            for method in methods {
+                println!("{}", method);
+            }
+        }
+    }
+    Ok(())
+}
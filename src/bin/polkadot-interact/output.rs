@@ -0,0 +1,48 @@
+use serde_json::{json, Value};
+use std::str::FromStr;
+use utils::{EventDetails, Phase};
+
+/// How to print the results of a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    /// Human readable output.
+    Table,
+    /// JSON, for handing to other tools (like `jq`).
+    Json,
+}
+
+impl FromStr for Output {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(Output::Table),
+            "json" => Ok(Output::Json),
+            _ => anyhow::bail!("unknown output format '{}' (expected table or json)", s),
+        }
+    }
+}
+
+pub fn print_json(value: &Value) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).expect("JSON values can be serialized; qed")
+    );
+}
+
+pub fn phase_name(phase: Phase) -> String {
+    match phase {
+        Phase::ApplyExtrinsic(index) => format!("extrinsic {}", index),
+        Phase::Finalization => "finalization".to_string(),
+        Phase::Initialization => "initialization".to_string(),
+    }
+}
+
+pub fn event_json(event: &EventDetails) -> Value {
+    json!({
+        "phase": phase_name(event.phase),
+        "pallet": event.pallet,
+        "variant": event.variant,
+        "fields": event.fields,
+    })
+}
@@ -0,0 +1,93 @@
+use serde_json::{json, Value};
+use utils::{encode_storage_keys, get_storage_value, iter_storage_values};
+
+use crate::{
+    output::{print_json, Output},
+    parse_value, usage, Options,
+};
+
+/// Fetch storage values by pallet and entry name, decoding them using the metadata.
+///
+/// - "storage get PALLET ENTRY [KEY..]" fetches a single value (see example 04). Storage maps
+///   need a key for each of their hashers. Entries with a default value hand that back when
+///   nothing is stored.
+/// - "storage iter PALLET ENTRY [KEY..]" fetches every value stored under the entry (see
+///   example 06), or under the first few keys of a map with more than one hasher.
+///
+/// Keys are encoded as the key types given in the metadata; see [`parse_value`].
+pub async fn run(options: &Options, args: &[String]) -> anyhow::Result<()> {
+    let (subcommand, pallet_name, entry_name, keys) = match args {
+        [subcommand, pallet_name, entry_name, keys @ ..] => {
+            (subcommand.as_str(), pallet_name, entry_name, keys)
+        }
+        _ => return Err(usage(
+            "expected 'storage get PALLET ENTRY [KEY..]' or 'storage iter PALLET ENTRY [KEY..]'",
+        )),
+    };
+
+    let at = options.at_hash().await?;
+    let metadata = options.metadata().await?;
+    let keys: Vec<Value> = keys.iter().map(|key| parse_value(key)).collect();
+    let encoded_keys = encode_storage_keys(&metadata, pallet_name, entry_name, &keys)?;
+
+    match subcommand {
+        "get" => {
+            let value = get_storage_value(
+                &options.url,
+                &metadata,
+                pallet_name,
+                entry_name,
+                &encoded_keys,
+                at.as_deref(),
+            )
+            .await?;
+
+            match (options.output, value) {
+                (Output::Json, value) => print_json(&value.unwrap_or(Value::Null)),
+                (Output::Table, Some(value)) => println!("{}", value),
+                (Output::Table, None) => println!("(nothing stored)"),
+            }
+        }
+        "iter" => {
+            let entries = iter_storage_values(
+                &options.url,
+                &metadata,
+                pallet_name,
+                entry_name,
+                &encoded_keys,
+                at.as_deref(),
+            )
+            .await?;
+
+            match options.output {
+                Output::Json => print_json(&json!(entries
+                    .iter()
+                    .map(|entry| json!({
+                        "key": format!("0x{}", hex::encode(&entry.key)),
+                        "keys": entry.keys,
+                        "value": entry.value,
+                    }))
+                    .collect::<Vec<_>>())),
+                Output::Table => {
+                    for entry in &entries {
+                        // Keys hashed without their value appended can't be read back,
+                        // so show the raw storage key for those:
+                        if entry.keys.iter().any(Value::is_null) {
+                            println!("0x{}: {}", hex::encode(&entry.key), entry.value);
+                        } else {
+                            println!("{}: {}", Value::from(entry.keys.clone()), entry.value);
+                        }
+                    }
+                    println!("({} entries)", entries.len());
+                }
+            }
+        }
+        other => {
+            return Err(usage(format!(
+                "unknown storage command '{}' (expected get or iter)",
+                other
+            )))
+        }
+    }
+    Ok(())
+}
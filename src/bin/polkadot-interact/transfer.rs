@@ -0,0 +1,53 @@
+use parity_scale_codec::{Compact, Encode};
+use utils::{
+    encode_account_arg, format_address, get_ss58_prefix, get_token_info, parse_address, EncodedCall,
+};
+
+use crate::{
+    tx::{submit, take_signer},
+    usage, Options,
+};
+
+/// Transfer some balance (see example 05), and wait for the transfer to make it into a block.
+///
+/// "transfer DEST AMOUNT [--from SECRET]", where the secret is a secret URI like "//Alice" (the
+/// default) or a mnemonic phrase.
+pub async fn run(options: &Options, args: &[String]) -> anyhow::Result<()> {
+    let (signer, positional) = take_signer(args)?;
+    // The amount can be given as one argument ("1.5 DOT") or two (1.5 DOT):
+    let (dest, amount) = match positional.split_first() {
+        Some((dest, amount)) if !amount.is_empty() => (*dest, amount.join(" ")),
+        _ => return Err(usage("expected 'transfer DEST AMOUNT [--from SECRET]'")),
+    };
+
+    let url = &options.url;
+    // Submitting always happens at the latest block, whatever "--at" says:
+    let metadata = options.latest_metadata().await?;
+    let token = get_token_info(url).await?;
+    let ss58_prefix = get_ss58_prefix(url, &metadata).await?;
+    let dest = parse_address(dest)?.for_prefix(ss58_prefix)?;
+    let amount = token.parse_amount(&amount)?;
+
+    // `transfer_keep_alive` exists in every runtime version, unlike `transfer`, and won't
+    // let us accidentally reap the sender's account:
+    let call = EncodedCall::from_fields(
+        &metadata,
+        "Balances",
+        "transfer_keep_alive",
+        |field, out| match field.name().map(|name| name.as_str()) {
+            Some("dest") => encode_account_arg(&metadata.types, field.ty().id(), &dest, out),
+            Some("value") => {
+                Compact(amount).encode_to(out);
+                Ok(())
+            }
+            other => anyhow::bail!("unexpected transfer_keep_alive argument {:?}", other),
+        },
+    )?;
+
+    let description = format!(
+        "Transferring {} to {}",
+        token.format_amount(amount),
+        format_address(&dest, ss58_prefix)
+    );
+    submit(options, &metadata, &signer, &call, &description).await
+}
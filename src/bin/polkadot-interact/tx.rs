@@ -0,0 +1,171 @@
+use frame_metadata::RuntimeMetadataV14;
+use serde_json::{json, Value};
+use sp_core::{sr25519, Pair};
+use std::sync::Arc;
+use utils::{
+    encode_value, estimate_fees, format_address, get_events, get_ss58_prefix, get_token_info,
+    sign_and_watch, ws_client, EncodedCall, NonceManager,
+};
+
+use crate::{
+    output::{event_json, print_json, Output},
+    parse_value, split_option, usage, Options,
+};
+
+/// Build any call from textual arguments, and submit it (see example 05).
+///
+/// "tx PALLET CALL [NAME=VALUE..] [--from SECRET]", where each argument of the call is given
+/// by name and parsed with [`parse_value`], and then encoded as the type that the metadata
+/// says the argument has. Balances can also be given in the chain's token (like "1.2DOT").
+pub async fn run(options: &Options, args: &[String]) -> anyhow::Result<()> {
+    let (signer, positional) = take_signer(args)?;
+    let (pallet_name, call_name, call_args) = match positional.as_slice() {
+        [pallet_name, call_name, call_args @ ..] => (*pallet_name, *call_name, call_args),
+        _ => {
+            return Err(usage(
+                "expected 'tx PALLET CALL [NAME=VALUE..] [--from SECRET]'",
+            ))
+        }
+    };
+    let mut call_args = call_args
+        .iter()
+        .map(|arg| match arg.split_once('=') {
+            Some((name, value)) => Ok((name, value)),
+            None => Err(usage(format!("expected NAME=VALUE, but got '{}'", arg))),
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let url = &options.url;
+    // Submitting always happens at the latest block, whatever "--at" says:
+    let metadata = options.latest_metadata().await?;
+    let token = get_token_info(url).await?;
+
+    let call = EncodedCall::from_fields(&metadata, pallet_name, call_name, |field, out| {
+        let name = field.name().map(|name| name.as_str()).unwrap_or_default();
+        let position = call_args
+            .iter()
+            .position(|(arg_name, _)| *arg_name == name)
+            .ok_or_else(|| usage(format!("{}.{} needs '{}='", pallet_name, call_name, name)))?;
+        let (_, text) = call_args.remove(position);
+
+        let is_balance =
+            matches!(field.type_name(), Some(type_name) if type_name.contains("Balance"));
+        // Balances with the token's symbol on the end (like "1.2DOT" or "1.2 DOT") are given
+        // in the token, and anything else is taken as it is:
+        let in_token = text
+            .to_ascii_lowercase()
+            .ends_with(&token.symbol.to_ascii_lowercase());
+        let value = if is_balance && in_token {
+            Value::String(token.parse_amount(text)?.to_string())
+        } else {
+            parse_value(text)
+        };
+        encode_value(&metadata.types, field.ty().id(), &value, out)
+            .map_err(|e| anyhow::anyhow!("argument '{}' is not valid: {}", name, e))
+    })?;
+    if let Some((name, _)) = call_args.first() {
+        return Err(usage(format!(
+            "{}.{} has no argument '{}'",
+            pallet_name, call_name, name
+        )));
+    }
+
+    let description = format!("Submitting {}.{}", pallet_name, call_name);
+    submit(options, &metadata, &signer, &call, &description).await
+}
+
+/// Pull "--from SECRET" (or "--from=SECRET") out of a command's arguments, handing back the
+/// signer (Alice unless a secret URI like "//Bob" or a mnemonic phrase is given) and the other
+/// arguments.
+pub fn take_signer(args: &[String]) -> anyhow::Result<(sr25519::Pair, Vec<&str>)> {
+    let mut from = "//Alice";
+    let mut positional = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match split_option(arg) {
+            ("--from", Some(value)) => from = value,
+            ("--from", None) => {
+                from = args
+                    .next()
+                    .ok_or_else(|| usage("--from needs a secret URI, like //Alice"))?
+            }
+            _ => positional.push(arg.as_str()),
+        }
+    }
+
+    let signer = sr25519::Pair::from_string(from, None)
+        .map_err(|e| anyhow::anyhow!("invalid secret given with --from: {:?}", e))?;
+    Ok((signer, positional))
+}
+
+/// Sign and submit a call, wait for it to make it into a block, and then show the events
+/// that it emitted. `description` says what we're doing, and is printed along with the
+/// estimated fee before submitting.
+pub async fn submit(
+    options: &Options,
+    metadata: &Arc<RuntimeMetadataV14>,
+    signer: &sr25519::Pair,
+    call: &EncodedCall,
+    description: &str,
+) -> anyhow::Result<()> {
+    let url = &options.url;
+    let token = get_token_info(url).await?;
+    let ss58_prefix = get_ss58_prefix(url, metadata).await?;
+
+    let from_account = signer.public().into();
+    let fees = estimate_fees(url, &from_account, call, 0).await?;
+    if options.output == Output::Table {
+        println!(
+            "{} from {} (estimated fee {})",
+            description,
+            format_address(&from_account, ss58_prefix),
+            token.format_amount(fees.partial_fee)
+        );
+    }
+
+    let client = ws_client(&options.ws_url).await?;
+    // If the runtime has been upgraded since we built the call, its pallet and call indexes
+    // may mean something else now, so check before signing. Watching for upgrades means we
+    // don't have to ask the node which runtime it's on for every check after the first:
+    options.metadata_cache.watch_upgrades(&client).await?;
+    if !Arc::ptr_eq(&options.latest_metadata().await?, metadata) {
+        anyhow::bail!("the runtime was upgraded while building the call; please try again");
+    }
+    let nonces = NonceManager::new(url.as_str());
+    let in_block = sign_and_watch(&client, url, &nonces, signer, call).await?;
+
+    // The events are decoded with the metadata of the block they're in, in case the runtime
+    // was upgraded while we waited:
+    let block_metadata = options.metadata_at(&in_block.block_hash).await?;
+    let events: Vec<_> = get_events(url, &block_metadata, Some(&in_block.block_hash))
+        .await?
+        .into_iter()
+        .filter(|event| event.is_from_extrinsic(in_block.extrinsic_index))
+        .collect();
+    let succeeded = events.iter().any(|e| e.is("System", "ExtrinsicSuccess"));
+
+    match options.output {
+        Output::Json => print_json(&json!({
+            "block_hash": in_block.block_hash,
+            "extrinsic_index": in_block.extrinsic_index,
+            "estimated_fee": fees.partial_fee.to_string(),
+            "success": succeeded,
+            "events": events.iter().map(event_json).collect::<Vec<_>>(),
+        })),
+        Output::Table => {
+            println!(
+                "Included in block {} at index {}",
+                in_block.block_hash, in_block.extrinsic_index
+            );
+            for event in &events {
+                println!("  {}.{}: {}", event.pallet, event.variant, event.fields);
+            }
+        }
+    }
+
+    // Either way, a failed extrinsic is an error, so that scripts can tell:
+    if !succeeded {
+        anyhow::bail!("the extrinsic failed");
+    }
+    Ok(())
+}
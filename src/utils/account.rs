@@ -0,0 +1,133 @@
+use frame_metadata::RuntimeMetadataV14;
+use parity_scale_codec::Encode;
+use serde_json::Value;
+use sp_runtime::AccountId32;
+
+use crate::{
+    account_from_json, get_account_balances, get_storage_value, storage_entry, u32_from_json,
+    AccountBalances, RpcTransport,
+};
+
+/// Everything we can find out about an account from the usual pallets. Each part of this is
+/// decoded using the metadata, so it copes with the layout differing between runtimes, and
+/// parts are left empty (or `None`) if the runtime doesn't have the relevant pallet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountOverview {
+    pub account: AccountId32,
+    /// The number of transactions this account has made, from `System.Account`.
+    pub nonce: u32,
+    /// The account's balances, and any locks, reserves, holds or freezes on them.
+    pub balances: AccountBalances,
+    /// Vesting schedules, from `Vesting.Vesting`.
+    pub vesting: Vec<Value>,
+    /// If the account is a staking stash, its controller and staking ledger (from
+    /// `Staking.Bonded` and `Staking.Ledger`).
+    pub staking: Option<StakingDetails>,
+    /// The account's on-chain identity, from `Identity.IdentityOf`.
+    pub identity: Option<Value>,
+}
+
+/// A stash account's staking details.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StakingDetails {
+    pub controller: AccountId32,
+    pub ledger: Option<Value>,
+}
+
+/// Fetch an overview of some account, at the given block (or the latest block).
+pub async fn get_account_overview(
+    url: &(impl RpcTransport + ?Sized),
+    metadata: &RuntimeMetadataV14,
+    account: &AccountId32,
+    at: Option<&str>,
+) -> anyhow::Result<AccountOverview> {
+    let key = vec![account.encode()];
+
+    let account_info = get_storage_value(url, metadata, "System", "Account", &key, at)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("System.Account should have a default value"))?;
+    let nonce = u32_from_json(&account_info["nonce"])?;
+    let balances = get_account_balances(url, metadata, account, at).await?;
+
+    let vesting = list_entry(url, metadata, "Vesting", "Vesting", &key, at).await?;
+
+    let staking = match optional_entry(url, metadata, "Staking", "Bonded", &key, at).await? {
+        Some(controller) => {
+            let controller = account_from_json(&controller)?;
+            let ledger = optional_entry(
+                url,
+                metadata,
+                "Staking",
+                "Ledger",
+                &[controller.encode()],
+                at,
+            )
+            .await?;
+            Some(StakingDetails { controller, ledger })
+        }
+        None => None,
+    };
+
+    let identity = optional_entry(url, metadata, "Identity", "IdentityOf", &key, at).await?;
+
+    Ok(AccountOverview {
+        account: account.clone(),
+        nonce,
+        balances,
+        vesting,
+        staking,
+        identity,
+    })
+}
+
+/// Newer runtimes store an identity alongside a username, as a tuple; find the
+/// display name set in the identity, if there is one.
+pub fn identity_display_name(identity: &Value) -> Option<String> {
+    let registration = match identity {
+        Value::Array(parts) => parts.first()?,
+        registration => registration,
+    };
+    // The display name is a `Data` enum; names are usually stored as `RawN`, where N is the
+    // number of bytes. We decode those bytes to hex, so turn them back into a string here:
+    let display = registration["info"]["display"].as_object()?;
+    let (variant, bytes_hex) = display.iter().next()?;
+    if !variant.starts_with("Raw") {
+        return None;
+    }
+    let bytes = hex::decode(bytes_hex.as_str()?.trim_start_matches("0x")).ok()?;
+    String::from_utf8(bytes).ok()
+}
+
+/// Fetch a storage entry if the runtime has it.
+async fn optional_entry(
+    url: &(impl RpcTransport + ?Sized),
+    metadata: &RuntimeMetadataV14,
+    pallet_name: &str,
+    entry_name: &str,
+    keys: &[Vec<u8>],
+    at: Option<&str>,
+) -> anyhow::Result<Option<Value>> {
+    if storage_entry(metadata, pallet_name, entry_name).is_err() {
+        return Ok(None);
+    }
+    get_storage_value(url, metadata, pallet_name, entry_name, keys, at).await
+}
+
+/// Fetch a storage entry which holds a list of things, if the runtime has it. Older versions
+/// of some entries (like `Vesting.Vesting`) hold a single thing rather than a list.
+async fn list_entry(
+    url: &(impl RpcTransport + ?Sized),
+    metadata: &RuntimeMetadataV14,
+    pallet_name: &str,
+    entry_name: &str,
+    keys: &[Vec<u8>],
+    at: Option<&str>,
+) -> anyhow::Result<Vec<Value>> {
+    Ok(
+        match optional_entry(url, metadata, pallet_name, entry_name, keys, at).await? {
+            Some(Value::Array(items)) => items,
+            Some(Value::Null) | None => Vec::new(),
+            Some(item) => vec![item],
+        },
+    )
+}
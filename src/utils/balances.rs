@@ -0,0 +1,190 @@
+use frame_metadata::RuntimeMetadataV14;
+use parity_scale_codec::Encode;
+use serde_json::Value;
+use sp_runtime::AccountId32;
+
+use crate::{get_storage_value, storage_entry, u128_from_json, RpcTransport};
+
+/// An account's balances, from its `AccountData` (see example 04), along with the locks,
+/// reserves, holds and freezes which explain why some of it can't be spent.
+///
+/// Older runtimes store `{ free, reserved, misc_frozen, fee_frozen }`, and newer ones store
+/// `{ free, reserved, frozen, flags }`. In the former case, `frozen` is the larger of the two
+/// frozen amounts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountBalances {
+    pub free: u128,
+    pub reserved: u128,
+    pub frozen: u128,
+    /// On newer runtimes, the frozen amount applies to the free and reserved balance together
+    /// (so reserved funds count towards it). On older runtimes, it applies to the free balance.
+    pub frozen_includes_reserved: bool,
+    /// Locks on the balance, from `Balances.Locks`. Newer runtimes use freezes instead.
+    pub locks: Vec<BalanceLock>,
+    /// Named reserves, from `Balances.Reserves`. Newer runtimes use holds instead.
+    pub reserves: Vec<NamedBalance>,
+    /// Holds, from `Balances.Holds`.
+    pub holds: Vec<NamedBalance>,
+    /// Freezes, from `Balances.Freezes`.
+    pub freezes: Vec<NamedBalance>,
+}
+
+/// A lock on some of an account's balance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceLock {
+    /// Who placed the lock (see [`lock_id_name`]).
+    pub id: String,
+    pub amount: u128,
+    /// What the locked funds can't be used for: "Fee", "Misc" or "All".
+    pub reasons: String,
+}
+
+/// An amount reserved, held or frozen for some reason.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedBalance {
+    /// Why the amount is reserved, held or frozen. Reserves have an 8 byte ID like locks do
+    /// (see [`lock_id_name`]), while holds and freezes have a reason from the pallet that placed
+    /// them, like "Preimage.Preimage".
+    pub id: String,
+    pub amount: u128,
+}
+
+impl AccountBalances {
+    /// Read the balances from a decoded `AccountData`. This doesn't include any locks,
+    /// reserves, holds or freezes, which are stored separately; see [`get_account_balances`].
+    pub fn from_json(data: &Value) -> anyhow::Result<Self> {
+        let (frozen, frozen_includes_reserved) = match data.get("frozen") {
+            Some(frozen) => (u128_from_json(frozen)?, true),
+            None => (
+                u128_from_json(&data["misc_frozen"])?.max(u128_from_json(&data["fee_frozen"])?),
+                false,
+            ),
+        };
+        Ok(AccountBalances {
+            free: u128_from_json(&data["free"])?,
+            reserved: u128_from_json(&data["reserved"])?,
+            frozen,
+            frozen_includes_reserved,
+            ..Default::default()
+        })
+    }
+
+    /// How much of the free balance can be spent (ignoring the existential deposit, which
+    /// must also be left behind if the account is to be kept alive).
+    ///
+    /// Locks and freezes overlap rather than adding up, so the frozen amount is the largest of
+    /// them. On newer runtimes, reserved (held) funds count towards it, so only the part of the
+    /// frozen amount that isn't already reserved takes away from the free balance.
+    pub fn spendable(&self) -> u128 {
+        let untouchable = if self.frozen_includes_reserved {
+            self.frozen.saturating_sub(self.reserved)
+        } else {
+            self.frozen
+        };
+        self.free.saturating_sub(untouchable)
+    }
+}
+
+/// Fetch an account's balances, along with its locks, reserves, holds and freezes, at the
+/// given block (or the latest block).
+pub async fn get_account_balances(
+    url: &(impl RpcTransport + ?Sized),
+    metadata: &RuntimeMetadataV14,
+    account: &AccountId32,
+    at: Option<&str>,
+) -> anyhow::Result<AccountBalances> {
+    let key = vec![account.encode()];
+
+    let account_info = get_storage_value(url, metadata, "System", "Account", &key, at)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("System.Account should have a default value"))?;
+    let mut balances = AccountBalances::from_json(&account_info["data"])?;
+
+    balances.locks = balances_list(url, metadata, "Locks", &key, at)
+        .await?
+        .iter()
+        .map(|lock| {
+            Ok(BalanceLock {
+                id: lock_id_name(&lock["id"]),
+                amount: u128_from_json(&lock["amount"])?,
+                reasons: lock["reasons"].as_str().unwrap_or("Unknown").to_string(),
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    let named = |items: Vec<Value>| -> anyhow::Result<Vec<NamedBalance>> {
+        items
+            .iter()
+            .map(|item| {
+                Ok(NamedBalance {
+                    id: reason_name(&item["id"]),
+                    amount: u128_from_json(&item["amount"])?,
+                })
+            })
+            .collect()
+    };
+    balances.reserves = named(balances_list(url, metadata, "Reserves", &key, at).await?)?;
+    balances.holds = named(balances_list(url, metadata, "Holds", &key, at).await?)?;
+    balances.freezes = named(balances_list(url, metadata, "Freezes", &key, at).await?)?;
+
+    Ok(balances)
+}
+
+/// Lock (and reserve) IDs are 8 bytes, which are usually ASCII text padded with spaces. We
+/// give the well known ones a friendlier name, and show the rest as text, or hex if they
+/// aren't text.
+pub fn lock_id_name(id: &Value) -> String {
+    let id_hex = id.as_str().unwrap_or_default();
+    let bytes = hex::decode(id_hex.trim_start_matches("0x")).unwrap_or_default();
+    match &bytes[..] {
+        b"staking " => "Staking".to_string(),
+        b"vesting " => "Vesting".to_string(),
+        b"democrac" => "Democracy".to_string(),
+        b"pyconvot" => "ConvictionVoting".to_string(),
+        b"phrelect" => "Elections".to_string(),
+        b"stkngdel" => "DelegatedStaking".to_string(),
+        b"nomprpls" => "NominationPools".to_string(),
+        _ => match std::str::from_utf8(&bytes) {
+            Ok(text)
+                if !text.trim().is_empty()
+                    && text.chars().all(|c| c.is_ascii_graphic() || c == ' ') =>
+            {
+                text.trim_end().to_string()
+            }
+            _ => id_hex.to_string(),
+        },
+    }
+}
+
+/// Hold and freeze reasons are enums generated for each runtime, so they decode to something
+/// like `{ "Preimage": "Preimage" }`, which we turn into "Preimage.Preimage". Reserve IDs are
+/// 8 bytes like lock IDs are.
+fn reason_name(id: &Value) -> String {
+    match id {
+        Value::String(s) if s.starts_with("0x") => lock_id_name(id),
+        Value::String(name) => name.clone(),
+        Value::Object(map) => match map.iter().next() {
+            Some((pallet, Value::String(reason))) => format!("{}.{}", pallet, reason),
+            Some((pallet, _)) => pallet.clone(),
+            None => "Unknown".to_string(),
+        },
+        other => other.to_string(),
+    }
+}
+
+/// Fetch one of the Balances pallet's per-account lists, if the runtime has it.
+async fn balances_list(
+    url: &(impl RpcTransport + ?Sized),
+    metadata: &RuntimeMetadataV14,
+    entry_name: &str,
+    keys: &[Vec<u8>],
+    at: Option<&str>,
+) -> anyhow::Result<Vec<Value>> {
+    if storage_entry(metadata, "Balances", entry_name).is_err() {
+        return Ok(Vec::new());
+    }
+    match get_storage_value(url, metadata, "Balances", entry_name, keys, at).await? {
+        Some(Value::Array(items)) => Ok(items),
+        _ => Ok(Vec::new()),
+    }
+}
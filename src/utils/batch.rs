@@ -0,0 +1,218 @@
+use frame_metadata::RuntimeMetadataV14;
+use serde_json::Value;
+
+use crate::{EncodedCall, EventDetails};
+
+/// The ways in which the Utility pallet can dispatch a list of calls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchMode {
+    /// `Utility.batch`: dispatch calls in order, stopping at the first one that fails.
+    /// Calls before the failure are not reverted.
+    Batch,
+    /// `Utility.batch_all`: dispatch calls in order, reverting all of them if any fail.
+    BatchAll,
+    /// `Utility.force_batch`: dispatch every call, carrying on past any that fail.
+    ForceBatch,
+}
+
+impl BatchMode {
+    /// The name of the call in the Utility pallet.
+    pub fn call_name(&self) -> &'static str {
+        match self {
+            BatchMode::Batch => "batch",
+            BatchMode::BatchAll => "batch_all",
+            BatchMode::ForceBatch => "force_batch",
+        }
+    }
+}
+
+/// Wrap a list of calls into a single Utility batch call. Each of the batch calls takes
+/// a single `Vec<RuntimeCall>` argument, and since each of our calls is already encoded
+/// in the same way a `RuntimeCall` would be, a `Vec<EncodedCall>` encodes identically.
+pub fn batch_call(
+    metadata: &RuntimeMetadataV14,
+    mode: BatchMode,
+    calls: Vec<EncodedCall>,
+) -> anyhow::Result<EncodedCall> {
+    EncodedCall::by_name(metadata, "Utility", mode.call_name(), calls)
+}
+
+/// What happened to a single call in a batch.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BatchItemResult {
+    /// The call was dispatched successfully (though with `batch_all`, it may
+    /// still have been reverted if the batch failed as a whole).
+    Completed,
+    /// The call failed with the given `DispatchError`.
+    Failed(Value),
+    /// The call was never dispatched because an earlier call interrupted the batch.
+    NotExecuted,
+}
+
+/// The outcome of a batch extrinsic, worked out from the events it emitted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchOutcome {
+    /// One result per call in the batch, in order.
+    pub items: Vec<BatchItemResult>,
+    /// If the extrinsic as a whole failed (for instance, a `batch_all` where one of the calls
+    /// failed), this holds the `DispatchError` from `System.ExtrinsicFailed`. In this case all
+    /// of the batch's own events are reverted, so we can't tell which call was at fault.
+    pub extrinsic_failed: Option<Value>,
+}
+
+impl BatchOutcome {
+    /// Did every call in the batch complete successfully?
+    pub fn all_completed(&self) -> bool {
+        self.extrinsic_failed.is_none()
+            && self
+                .items
+                .iter()
+                .all(|item| *item == BatchItemResult::Completed)
+    }
+}
+
+/// Work out which items in a batch succeeded, given the events from the block the batch
+/// was included in, the index of the batch extrinsic in that block, and the number of calls
+/// that were in the batch.
+///
+/// The Utility pallet emits an `ItemCompleted` event for each call that succeeds, and for
+/// `force_batch`, an `ItemFailed` for each call that doesn't. A `batch` that's stopped early
+/// emits `BatchInterrupted { index, error }`, and the calls after that index are never run.
+pub fn batch_outcome(
+    events: &[EventDetails],
+    extrinsic_index: u32,
+    num_calls: usize,
+) -> BatchOutcome {
+    let mut items = Vec::with_capacity(num_calls);
+    let mut extrinsic_failed = None;
+
+    for event in events
+        .iter()
+        .filter(|e| e.is_from_extrinsic(extrinsic_index))
+    {
+        if event.is("Utility", "ItemCompleted") {
+            items.push(BatchItemResult::Completed);
+        } else if event.is("Utility", "ItemFailed") {
+            items.push(BatchItemResult::Failed(field_or_null(event, "error", 0)));
+        } else if event.is("Utility", "BatchInterrupted") {
+            // Older runtimes don't emit `ItemCompleted`, so trust the index
+            // here to tell us how many calls succeeded before this one:
+            let index = event
+                .field("index", 0)
+                .and_then(|i| i.as_u64())
+                .map(|i| i as usize)
+                .unwrap_or_else(|| items.len());
+            items.resize(index, BatchItemResult::Completed);
+            items.push(BatchItemResult::Failed(field_or_null(event, "error", 1)));
+        } else if event.is("System", "ExtrinsicFailed") {
+            extrinsic_failed = Some(field_or_null(event, "dispatch_error", 0));
+        }
+    }
+
+    // Anything we didn't see an event for was never run (or was reverted):
+    items.resize(num_calls.max(items.len()), BatchItemResult::NotExecuted);
+
+    BatchOutcome {
+        items,
+        extrinsic_failed,
+    }
+}
+
+fn field_or_null(event: &EventDetails, name: &str, position: usize) -> Value {
+    event.field(name, position).cloned().unwrap_or(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Phase;
+    use serde_json::json;
+
+    fn event(extrinsic_index: u32, pallet: &str, variant: &str, fields: Value) -> EventDetails {
+        EventDetails {
+            phase: Phase::ApplyExtrinsic(extrinsic_index),
+            pallet: pallet.to_string(),
+            variant: variant.to_string(),
+            fields,
+        }
+    }
+
+    #[test]
+    fn completed_items() {
+        let events = vec![
+            event(1, "Utility", "ItemCompleted", Value::Null),
+            // Events from other extrinsics are ignored:
+            event(2, "Utility", "ItemCompleted", Value::Null),
+            event(1, "Utility", "ItemCompleted", Value::Null),
+            event(1, "Utility", "BatchCompleted", Value::Null),
+        ];
+        let outcome = batch_outcome(&events, 1, 2);
+        assert_eq!(
+            outcome.items,
+            vec![BatchItemResult::Completed, BatchItemResult::Completed]
+        );
+        assert_eq!(outcome.extrinsic_failed, None);
+        assert!(outcome.all_completed());
+    }
+
+    #[test]
+    fn failed_items_in_a_force_batch() {
+        let error = json!({"Module": {"index": 5, "error": "0x02000000"}});
+        let events = vec![
+            event(1, "Utility", "ItemCompleted", Value::Null),
+            event(1, "Utility", "ItemFailed", json!({ "error": error })),
+            event(1, "Utility", "ItemCompleted", Value::Null),
+            event(1, "Utility", "BatchCompletedWithErrors", Value::Null),
+        ];
+        let outcome = batch_outcome(&events, 1, 3);
+        assert_eq!(
+            outcome.items,
+            vec![
+                BatchItemResult::Completed,
+                BatchItemResult::Failed(error),
+                BatchItemResult::Completed
+            ]
+        );
+        assert!(!outcome.all_completed());
+    }
+
+    #[test]
+    fn interrupted_batch() {
+        // Older runtimes don't emit `ItemCompleted`, and the fields may be unnamed:
+        let events = vec![event(
+            3,
+            "Utility",
+            "BatchInterrupted",
+            json!([1, "BadOrigin"]),
+        )];
+        let outcome = batch_outcome(&events, 3, 3);
+        assert_eq!(
+            outcome.items,
+            vec![
+                BatchItemResult::Completed,
+                BatchItemResult::Failed(json!("BadOrigin")),
+                BatchItemResult::NotExecuted
+            ]
+        );
+        assert_eq!(outcome.extrinsic_failed, None);
+    }
+
+    #[test]
+    fn failed_extrinsic() {
+        // A `batch_all` with a failing call reverts the Utility events, leaving just this:
+        let error = json!({"Module": {"index": 5, "error": "0x02000000"}});
+        let events = vec![event(
+            1,
+            "System",
+            "ExtrinsicFailed",
+            json!({ "dispatch_error": error, "dispatch_info": {} }),
+        )];
+        let outcome = batch_outcome(&events, 1, 2);
+        assert_eq!(
+            outcome.items,
+            vec![BatchItemResult::NotExecuted, BatchItemResult::NotExecuted]
+        );
+        assert_eq!(outcome.extrinsic_failed, Some(error));
+        assert!(!outcome.all_completed());
+    }
+}
@@ -0,0 +1,845 @@
+/*!
+Generate Rust code from the metadata, so that we can work with a runtime's types without
+depending on the runtime's own crates (like `pallet_balances` and `xcm`, which examples 04 and
+06 used to depend on; they now use [`crate::polkadot`], which `build.rs` generates with this).
+
+The generated code only needs `parity-scale-codec` (with the "derive" feature), and `sp-core`
+for hashing storage keys. It has, relative to wherever it's put:
+
+- `types`: a struct or enum for every struct and enum in the metadata's type registry, which
+  SCALE encode and decode in the same way as the runtime's own types.
+- A module per pallet (`balances`, `xcm_pallet` and so on) with its `PALLET_INDEX`, `Call`,
+  `Event` and `Error` types, and:
+  - `calls`: a function per call, handing back the generated `EncodedCall`, ready to sign
+    (the one in [`crate::polkadot`] converts into an [`EncodedCall`] with `into()`).
+  - `storage`: a function per storage entry, handing back its storage key given its map keys,
+    and a type alias for the value stored there.
+  - `constants`: a function per constant, decoding its value.
+
+Generate the code using the "metadata codegen" command of `polkadot-interact`, or from a build
+script, writing to `OUT_DIR` and then including it in a module (as `src/utils/polkadot.rs` does):
+
+```text
+// lib.rs:
+pub mod polkadot;
+
+// polkadot.rs:
+include!(concat!(env!("OUT_DIR"), "/polkadot.rs"));
+```
+
+This module is also compiled into `build.rs` (with `#[path]`), so it must only use
+`crate::storage_prefix_key` from the rest of `utils`.
+*/
+
+use frame_metadata::{
+    PalletMetadata, RuntimeMetadataV14, StorageEntryMetadata, StorageEntryModifier,
+    StorageEntryType,
+};
+use scale_info::{form::PortableForm, Field, PortableRegistry, Type, TypeDef, TypeDefPrimitive};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+};
+
+use crate::storage_prefix_key;
+
+/// Types which only wrap a single field, and are encoded as that field. We use the type of
+/// the field in their place, which saves generating a type for each thing they wrap.
+const TRANSPARENT_TYPES: &[&str] = &[
+    "BoundedVec",
+    "WeakBoundedVec",
+    "BoundedSlice",
+    "BTreeMap",
+    "BTreeSet",
+    "BoundedBTreeMap",
+    "BoundedBTreeSet",
+    "Cow",
+];
+
+const DERIVES: &str = "#[derive(Debug, Clone, PartialEq, Eq, \
+    ::parity_scale_codec::Encode, ::parity_scale_codec::Decode)]";
+const ALLOWS: &str = "#[allow(non_camel_case_types, non_snake_case, dead_code, unused_mut, \
+    clippy::all, rustdoc::all)]";
+
+/// Generate Rust code for the types, calls, storage entries, events and constants in the
+/// metadata (see the module docs above for what's generated).
+pub fn generate_code(metadata: &RuntimeMetadataV14) -> anyhow::Result<String> {
+    let mut codegen = Codegen::new(&metadata.types);
+    let mut out = String::new();
+
+    writeln!(
+        out,
+        "// Generated from the runtime's metadata by `utils::generate_code`. Don't edit this \
+         by hand;\n// generate it again after a runtime upgrade instead."
+    )?;
+    out.push_str(ENCODED_CALL);
+    out.push_str(HASH_KEY);
+
+    writeln!(out, "{}\npub mod types {{", ALLOWS)?;
+    for ty in metadata.types.types() {
+        if codegen.names.contains_key(&ty.id()) {
+            codegen.type_definition(ty.id(), ty.ty(), &mut out)?;
+        }
+    }
+    for store_bytes in codegen.bit_sequences.clone() {
+        writeln!(out, "{}", bit_sequence(store_bytes * 8))?;
+    }
+    writeln!(out, "}}\n")?;
+
+    for pallet in &metadata.pallets {
+        codegen.pallet(pallet, &mut out)?;
+    }
+
+    // Each item is followed by a blank line, which we don't want before closing braces:
+    let lines: Vec<&str> = out.lines().collect();
+    let mut tidied = String::new();
+    for (index, line) in lines.iter().enumerate() {
+        let next = lines.get(index + 1).map(|next| next.trim_start());
+        if line.is_empty() && matches!(next, Some(next) if next.starts_with('}')) {
+            continue;
+        }
+        tidied.push_str(line);
+        tidied.push('\n');
+    }
+    Ok(tidied)
+}
+
+struct Codegen<'a> {
+    types: &'a PortableRegistry,
+    /// The name we've given to each struct or enum that we generate.
+    names: HashMap<u32, String>,
+    /// The size (in bytes) of the store of every bit sequence that we've seen.
+    bit_sequences: Vec<u32>,
+}
+
+impl<'a> Codegen<'a> {
+    fn new(types: &'a PortableRegistry) -> Self {
+        let named: Vec<&scale_info::PortableType> = types
+            .types()
+            .iter()
+            .filter(|ty| is_generated(ty.ty()))
+            .collect();
+
+        // Types are named after the last part of their path where that's unique, and
+        // otherwise after their crate too (so `PalletBalancesCall` and `PalletXcmCall`), or
+        // their whole path, and failing that (as with different instances of a generic type),
+        // with a number.
+        let candidates = |ty: &scale_info::PortableType| -> Vec<String> {
+            let segments = ty.ty().path().segments();
+            let base = base_name(ty.id(), ty.ty());
+            vec![
+                base.clone(),
+                format!("{}{}", upper_camel_case(&segments[..1].concat()), base),
+                segments.iter().map(|s| upper_camel_case(s)).collect(),
+            ]
+        };
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for ty in &named {
+            let mut unique: Vec<String> = candidates(ty);
+            unique.dedup();
+            for candidate in unique {
+                *counts.entry(candidate).or_default() += 1;
+            }
+        }
+        let mut names = HashMap::new();
+        let mut taken: HashSet<String> = HashSet::new();
+        for ty in &named {
+            let candidates = candidates(ty);
+            let mut name = match candidates.iter().find(|c| !c.is_empty() && counts[*c] == 1) {
+                Some(name) => name.clone(),
+                None => candidates[0].clone(),
+            };
+            if taken.contains(&name) {
+                let mut n = 2;
+                while taken.contains(&format!("{}{}", name, n)) {
+                    n += 1;
+                }
+                name = format!("{}{}", name, n);
+            }
+            taken.insert(name.clone());
+            names.insert(ty.id(), name);
+        }
+
+        Codegen {
+            types,
+            names,
+            bit_sequences: Vec::new(),
+        }
+    }
+
+    fn resolve(&self, type_id: u32) -> anyhow::Result<&'a Type<PortableForm>> {
+        self.types
+            .resolve(type_id)
+            .ok_or_else(|| anyhow::anyhow!("type {} not found in the metadata", type_id))
+    }
+
+    /// The Rust type to use for some type ID. `types` is the path to the generated `types`
+    /// module from wherever the type is used.
+    fn type_expr(&mut self, type_id: u32, types: &str) -> anyhow::Result<String> {
+        if let Some(name) = self.names.get(&type_id) {
+            return Ok(format!("{}{}", types, name));
+        }
+
+        let ty = self.resolve(type_id)?;
+        let path: Vec<&str> = ty.path().segments().iter().map(|s| s.as_str()).collect();
+        Ok(match ty.type_def() {
+            TypeDef::Variant(variant) if path == ["Option"] => {
+                let some = variant
+                    .variants()
+                    .iter()
+                    .find(|v| v.name() == "Some")
+                    .and_then(|v| v.fields().first())
+                    .ok_or_else(|| anyhow::anyhow!("Option type {} has no Some", type_id))?;
+                format!("Option<{}>", self.type_expr(some.ty().id(), types)?)
+            }
+            TypeDef::Variant(variant) if path == ["Result"] => {
+                let mut field = |name: &str| -> anyhow::Result<String> {
+                    let field = variant
+                        .variants()
+                        .iter()
+                        .find(|v| v.name() == name)
+                        .and_then(|v| v.fields().first())
+                        .ok_or_else(|| {
+                            anyhow::anyhow!("Result type {} has no {}", type_id, name)
+                        })?;
+                    self.type_expr(field.ty().id(), types)
+                };
+                let ok = field("Ok")?;
+                format!("Result<{}, {}>", ok, field("Err")?)
+            }
+            TypeDef::Composite(composite) if is_transparent(ty) => {
+                self.type_expr(composite.fields()[0].ty().id(), types)?
+            }
+            TypeDef::Composite(_) | TypeDef::Variant(_) => {
+                anyhow::bail!("no name was given to type {}", type_id)
+            }
+            TypeDef::Sequence(seq) => {
+                format!("Vec<{}>", self.type_expr(seq.type_param().id(), types)?)
+            }
+            TypeDef::Array(arr) => format!(
+                "[{}; {}]",
+                self.type_expr(arr.type_param().id(), types)?,
+                arr.len()
+            ),
+            TypeDef::Tuple(tuple) => {
+                let fields = tuple
+                    .fields()
+                    .iter()
+                    .map(|field| self.type_expr(field.id(), types))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                match fields.len() {
+                    1 => format!("({},)", fields[0]),
+                    _ => format!("({})", fields.join(", ")),
+                }
+            }
+            TypeDef::Primitive(prim) => primitive_name(prim).to_string(),
+            TypeDef::Compact(compact) => format!(
+                "::parity_scale_codec::Compact<{}>",
+                self.compact_inner(compact.type_param().id())?
+            ),
+            TypeDef::BitSequence(bits) => {
+                let store_bytes = match self.resolve(bits.bit_store_type().id())?.type_def() {
+                    TypeDef::Primitive(TypeDefPrimitive::U8) => 1,
+                    TypeDef::Primitive(TypeDefPrimitive::U16) => 2,
+                    TypeDef::Primitive(TypeDefPrimitive::U32) => 4,
+                    TypeDef::Primitive(TypeDefPrimitive::U64) => 8,
+                    _ => anyhow::bail!("unsupported bit sequence store in type {}", type_id),
+                };
+                if !self.bit_sequences.contains(&store_bytes) {
+                    self.bit_sequences.push(store_bytes);
+                }
+                format!("{}BitSequence{}", types, store_bytes * 8)
+            }
+        })
+    }
+
+    /// Compact encoding is for unsigned integers, or for types (like `Perbill`) which wrap
+    /// one and are compact encoded as that integer; we use the integer in their place.
+    fn compact_inner(&self, type_id: u32) -> anyhow::Result<String> {
+        let ty = self.resolve(type_id)?;
+        match ty.type_def() {
+            TypeDef::Primitive(prim) => Ok(primitive_name(prim).to_string()),
+            TypeDef::Composite(composite) if composite.fields().len() == 1 => {
+                self.compact_inner(composite.fields()[0].ty().id())
+            }
+            TypeDef::Tuple(tuple) if tuple.fields().len() == 1 => {
+                self.compact_inner(tuple.fields()[0].id())
+            }
+            TypeDef::Composite(composite) if composite.fields().is_empty() => Ok("()".to_string()),
+            TypeDef::Tuple(tuple) if tuple.fields().is_empty() => Ok("()".to_string()),
+            _ => anyhow::bail!("type {} cannot be compact encoded", type_id),
+        }
+    }
+
+    /// The type of a field, and whether it needs `#[codec(compact)]`. Fields which contain
+    /// the type that they're in (`container`) are boxed (see [`Codegen::boxed`]).
+    fn field_type(
+        &mut self,
+        container: Option<u32>,
+        field: &Field<PortableForm>,
+        types: &str,
+    ) -> anyhow::Result<(String, bool)> {
+        let field_ty = field.ty().id();
+        if let TypeDef::Compact(compact) = self.resolve(field_ty)?.type_def() {
+            return Ok((self.compact_inner(compact.type_param().id())?, true));
+        }
+        let expr = self.type_expr(field_ty, types)?;
+        match container {
+            Some(container) if self.boxed(container, field)? => {
+                Ok((format!("Box<{}>", expr), false))
+            }
+            _ => Ok((expr, false)),
+        }
+    }
+
+    /// Whether a field needs boxing to stop the type that it's in from being infinitely large
+    /// (as with a call which takes another call). The runtime boxes named fields (like the
+    /// `call` of `Utility.as_derivative`) rather than the variants of `RuntimeCall`, so we do
+    /// the same, and only box unnamed fields when a cycle has no named fields to box.
+    fn boxed(&self, container: u32, field: &Field<PortableForm>) -> anyhow::Result<bool> {
+        self.contains_inline(field.ty().id(), container, field.name().is_some())
+    }
+
+    /// Whether a value of type `type_id` contains a value of type `target` directly (rather
+    /// than behind a `Vec`), optionally only looking through unnamed fields.
+    fn contains_inline(
+        &self,
+        type_id: u32,
+        target: u32,
+        through_named: bool,
+    ) -> anyhow::Result<bool> {
+        let mut to_visit = vec![type_id];
+        let mut visited = HashSet::new();
+        let followed = |field: &&Field<PortableForm>| through_named || field.name().is_none();
+        while let Some(type_id) = to_visit.pop() {
+            if type_id == target {
+                return Ok(true);
+            }
+            if !visited.insert(type_id) {
+                continue;
+            }
+            match self.resolve(type_id)?.type_def() {
+                TypeDef::Composite(composite) => to_visit.extend(
+                    composite
+                        .fields()
+                        .iter()
+                        .filter(followed)
+                        .map(|f| f.ty().id()),
+                ),
+                TypeDef::Variant(variant) => to_visit.extend(
+                    variant
+                        .variants()
+                        .iter()
+                        .flat_map(|v| v.fields())
+                        .filter(followed)
+                        .map(|f| f.ty().id()),
+                ),
+                TypeDef::Array(arr) => to_visit.push(arr.type_param().id()),
+                TypeDef::Tuple(tuple) => to_visit.extend(tuple.fields().iter().map(|f| f.id())),
+                TypeDef::Sequence(_)
+                | TypeDef::Primitive(_)
+                | TypeDef::Compact(_)
+                | TypeDef::BitSequence(_) => {}
+            }
+        }
+        Ok(false)
+    }
+
+    fn type_definition(
+        &mut self,
+        type_id: u32,
+        ty: &Type<PortableForm>,
+        out: &mut String,
+    ) -> anyhow::Result<()> {
+        let name = self.names[&type_id].clone();
+        write_docs(out, "    ", ty.docs());
+        if !ty.path().is_empty() {
+            writeln!(out, "    /// `{}` in the runtime.", ty.path())?;
+        }
+        writeln!(out, "    {}", DERIVES)?;
+
+        match ty.type_def() {
+            TypeDef::Composite(composite) => {
+                let fields = self.fields(type_id, composite.fields(), "        ", true)?;
+                match fields {
+                    Fields::Unit => writeln!(out, "    pub struct {};", name)?,
+                    Fields::Named(fields) => {
+                        writeln!(out, "    pub struct {} {{\n{}    }}", name, fields)?
+                    }
+                    Fields::Unnamed(fields) => {
+                        writeln!(out, "    pub struct {}({});", name, fields)?
+                    }
+                }
+            }
+            TypeDef::Variant(variant) => {
+                writeln!(out, "    pub enum {} {{", name)?;
+                for v in variant.variants() {
+                    write_docs(out, "        ", v.docs());
+                    writeln!(out, "        #[codec(index = {})]", v.index())?;
+                    let variant_name = ident(v.name());
+                    match self.fields(type_id, v.fields(), "            ", false)? {
+                        Fields::Unit => writeln!(out, "        {},", variant_name)?,
+                        Fields::Named(fields) => {
+                            writeln!(out, "        {} {{\n{}        }},", variant_name, fields)?
+                        }
+                        Fields::Unnamed(fields) => {
+                            writeln!(out, "        {}({}),", variant_name, fields)?
+                        }
+                    }
+                }
+                writeln!(out, "    }}")?;
+            }
+            _ => anyhow::bail!("type {} is not a struct or enum", type_id),
+        }
+        writeln!(out)?;
+        Ok(())
+    }
+
+    fn fields(
+        &mut self,
+        container: u32,
+        fields: &[Field<PortableForm>],
+        indent: &str,
+        public: bool,
+    ) -> anyhow::Result<Fields> {
+        let vis = if public { "pub " } else { "" };
+        if fields.is_empty() {
+            return Ok(Fields::Unit);
+        }
+
+        let mut rendered = Vec::new();
+        for field in fields {
+            let (field_ty, compact) = self.field_type(Some(container), field, "")?;
+            let compact = if compact { "#[codec(compact)] " } else { "" };
+            rendered.push(match field.name() {
+                Some(name) => {
+                    let mut docs = String::new();
+                    write_docs(&mut docs, indent, field.docs());
+                    format!(
+                        "{}{}{}{}{}: {},\n",
+                        docs,
+                        indent,
+                        compact,
+                        vis,
+                        ident(name),
+                        field_ty
+                    )
+                }
+                None => format!("{}{}{}", compact, vis, field_ty),
+            });
+        }
+
+        if fields.iter().all(|f| f.name().is_some()) {
+            Ok(Fields::Named(rendered.concat()))
+        } else {
+            Ok(Fields::Unnamed(rendered.join(", ")))
+        }
+    }
+
+    fn pallet(
+        &mut self,
+        pallet: &PalletMetadata<PortableForm>,
+        out: &mut String,
+    ) -> anyhow::Result<()> {
+        let types = "super::types::";
+        writeln!(out, "/// The `{}` pallet.\n{}", pallet.name, ALLOWS)?;
+        writeln!(out, "pub mod {} {{", ident(&snake_case(&pallet.name)))?;
+        writeln!(out, "    pub const PALLET_INDEX: u8 = {};\n", pallet.index)?;
+
+        let enums = [
+            (
+                "Call",
+                "The calls that can be made to this pallet.",
+                pallet.calls.as_ref().map(|c| c.ty.id()),
+            ),
+            (
+                "Event",
+                "The events that this pallet emits.",
+                pallet.event.as_ref().map(|e| e.ty.id()),
+            ),
+            (
+                "Error",
+                "The errors that this pallet's calls can fail with.",
+                pallet.error.as_ref().map(|e| e.ty.id()),
+            ),
+        ];
+        for &(name, docs, type_id) in &enums {
+            if let Some(type_id) = type_id {
+                writeln!(out, "    /// {}", docs)?;
+                writeln!(
+                    out,
+                    "    pub type {} = {};\n",
+                    name,
+                    self.type_expr(type_id, types)?
+                )?;
+            }
+        }
+
+        if let Some(calls) = &pallet.calls {
+            self.calls(calls.ty.id(), out)?;
+        }
+        if let Some(storage) = &pallet.storage {
+            writeln!(out, "    pub mod storage {{")?;
+            for entry in &storage.entries {
+                self.storage_entry(&storage.prefix, entry, out)?;
+            }
+            writeln!(out, "    }}\n")?;
+        }
+        if !pallet.constants.is_empty() {
+            writeln!(out, "    pub mod constants {{")?;
+            for constant in &pallet.constants {
+                write_docs(out, "        ", &constant.docs);
+                writeln!(
+                    out,
+                    "        pub fn {}() -> {} {{\n            \
+                     ::parity_scale_codec::Decode::decode(&mut &{:?}[..])\n                \
+                     .expect(\"the constant was encoded by the runtime; qed\")\n        }}\n",
+                    ident(&snake_case(&constant.name)),
+                    self.type_expr(constant.ty.id(), "super::super::types::")?,
+                    constant.value
+                )?;
+            }
+            writeln!(out, "    }}")?;
+        }
+        writeln!(out, "}}\n")?;
+        Ok(())
+    }
+
+    /// A function per call, which takes the call's arguments and encodes them along with the
+    /// pallet and call index.
+    fn calls(&mut self, calls_ty: u32, out: &mut String) -> anyhow::Result<()> {
+        let types = "super::super::types::";
+        let variants = match self.resolve(calls_ty)?.type_def() {
+            TypeDef::Variant(variant) => variant.variants(),
+            _ => anyhow::bail!("calls type {} is not an enum", calls_ty),
+        };
+
+        writeln!(out, "    pub mod calls {{")?;
+        for call in variants {
+            let mut params = Vec::new();
+            let mut args = Vec::new();
+            for (index, field) in call.fields().iter().enumerate() {
+                let name = match field.name() {
+                    Some(name) => ident(name),
+                    None => format!("arg{}", index),
+                };
+                let (field_ty, _) = self.field_type(None, field, types)?;
+                params.push(format!("{}: {}", name, field_ty));
+                match field.name() {
+                    Some(_) if self.boxed(calls_ty, field)? => {
+                        args.push(format!("{}: Box::new({})", name, name))
+                    }
+                    Some(_) => args.push(name),
+                    None if self.boxed(calls_ty, field)? => {
+                        args.push(format!("Box::new({})", name))
+                    }
+                    None => args.push(name),
+                }
+            }
+            let construct = if call.fields().is_empty() {
+                String::new()
+            } else if call.fields().iter().all(|f| f.name().is_some()) {
+                format!(" {{ {} }}", args.join(", "))
+            } else {
+                format!("({})", args.join(", "))
+            };
+
+            write_docs(out, "        ", call.docs());
+            writeln!(
+                out,
+                "        pub fn {}({}) -> super::super::EncodedCall {{\n            \
+                 super::super::EncodedCall::new(super::PALLET_INDEX, &super::Call::{}{})\n        \
+                 }}\n",
+                ident(call.name()),
+                params.join(", "),
+                ident(call.name()),
+                construct
+            )?;
+        }
+        writeln!(out, "    }}\n")?;
+        Ok(())
+    }
+
+    /// A function which builds the storage key of an entry given its map keys (see example 04),
+    /// and a type alias for the value that's stored.
+    fn storage_entry(
+        &mut self,
+        prefix: &str,
+        entry: &StorageEntryMetadata<PortableForm>,
+        out: &mut String,
+    ) -> anyhow::Result<()> {
+        let types = "super::super::types::";
+        let (hashers, key_types, value_ty) = match &entry.ty {
+            StorageEntryType::Plain(value) => (&[][..], Vec::new(), value.id()),
+            StorageEntryType::Map {
+                hashers,
+                key,
+                value,
+            } => {
+                let key_types = match self.resolve(key.id())?.type_def() {
+                    TypeDef::Tuple(tuple) if hashers.len() > 1 => {
+                        tuple.fields().iter().map(|f| f.id()).collect()
+                    }
+                    _ => vec![key.id()],
+                };
+                (&hashers[..], key_types, value.id())
+            }
+        };
+        if hashers.len() != key_types.len() {
+            anyhow::bail!(
+                "storage entry {} has a different number of hashers and keys",
+                entry.name
+            );
+        }
+
+        let mut params = Vec::new();
+        let mut body = format!(
+            "            let mut storage_key = {:?}.to_vec();\n",
+            storage_prefix_key(prefix, &entry.name)
+        );
+        for (index, (hasher, key_ty)) in hashers.iter().zip(key_types).enumerate() {
+            let name = if hashers.len() == 1 {
+                "key".to_string()
+            } else {
+                format!("key{}", index + 1)
+            };
+            params.push(format!("{}: &{}", name, self.type_expr(key_ty, types)?));
+            writeln!(
+                body,
+                "            storage_key.extend(super::super::hash_key(\
+                 super::super::StorageHasher::{:?}, &::parity_scale_codec::Encode::encode({})));",
+                hasher, name
+            )?;
+        }
+
+        write_docs(out, "        ", &entry.docs);
+        writeln!(
+            out,
+            "        pub fn {}({}) -> Vec<u8> {{\n{}            storage_key\n        }}",
+            ident(&snake_case(&entry.name)),
+            params.join(", "),
+            body
+        )?;
+        let default = match entry.modifier {
+            StorageEntryModifier::Default => " Nothing being stored means the default value.",
+            StorageEntryModifier::Optional => "",
+        };
+        writeln!(
+            out,
+            "        /// The value stored at [`{}`].{}\n        pub type {} = {};\n",
+            ident(&snake_case(&entry.name)),
+            default,
+            ident(&entry.name),
+            self.type_expr(value_ty, types)?
+        )?;
+        Ok(())
+    }
+}
+
+enum Fields {
+    Unit,
+    Named(String),
+    Unnamed(String),
+}
+
+/// Which types we generate a struct or enum for. Options and results are Rust's own, and
+/// transparent types (see [`TRANSPARENT_TYPES`]) are replaced by what they wrap.
+fn is_generated(ty: &Type<PortableForm>) -> bool {
+    let path: Vec<&str> = ty.path().segments().iter().map(|s| s.as_str()).collect();
+    match ty.type_def() {
+        TypeDef::Variant(_) => path != ["Option"] && path != ["Result"],
+        TypeDef::Composite(_) => !is_transparent(ty),
+        _ => false,
+    }
+}
+
+fn is_transparent(ty: &Type<PortableForm>) -> bool {
+    match (ty.type_def(), ty.path().ident()) {
+        (TypeDef::Composite(composite), Some(ident)) => {
+            composite.fields().len() == 1 && TRANSPARENT_TYPES.contains(&ident.as_str())
+        }
+        _ => false,
+    }
+}
+
+fn base_name(type_id: u32, ty: &Type<PortableForm>) -> String {
+    match ty.path().ident() {
+        Some(ident) => upper_camel_case(&ident),
+        None => format!("Type{}", type_id),
+    }
+}
+
+fn primitive_name(prim: &TypeDefPrimitive) -> &'static str {
+    match prim {
+        TypeDefPrimitive::Bool => "bool",
+        // parity-scale-codec has no impls for char, so we use the u32 it is encoded as:
+        TypeDefPrimitive::Char => "u32",
+        TypeDefPrimitive::Str => "String",
+        TypeDefPrimitive::U8 => "u8",
+        TypeDefPrimitive::U16 => "u16",
+        TypeDefPrimitive::U32 => "u32",
+        TypeDefPrimitive::U64 => "u64",
+        TypeDefPrimitive::U128 => "u128",
+        // These are encoded as their 32 little endian bytes:
+        TypeDefPrimitive::U256 | TypeDefPrimitive::I256 => "[u8; 32]",
+        TypeDefPrimitive::I8 => "i8",
+        TypeDefPrimitive::I16 => "i16",
+        TypeDefPrimitive::I32 => "i32",
+        TypeDefPrimitive::I64 => "i64",
+        TypeDefPrimitive::I128 => "i128",
+    }
+}
+
+/// Doc comments, with any code blocks marked as text so that rustdoc doesn't try to run them.
+fn write_docs(out: &mut String, indent: &str, docs: &[String]) {
+    let mut in_code_block = false;
+    for line in docs.iter().flat_map(|doc| doc.split('\n')) {
+        let line = line.strip_prefix(' ').unwrap_or(line);
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            if in_code_block {
+                out.push_str(&format!("{}/// ```text\n", indent));
+                continue;
+            }
+        }
+        out.push_str(&format!("{}/// {}\n", indent, line).replace("/// \n", "///\n"));
+    }
+}
+
+/// A name which is safe to use as a Rust identifier.
+fn ident(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern",
+        "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+        "pub", "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use",
+        "where", "while", "abstract", "become", "box", "do", "final", "macro", "override", "priv",
+        "try", "typeof", "unsized", "virtual", "yield",
+    ];
+    match name {
+        "self" | "Self" | "super" | "crate" => format!("{}_", name),
+        _ if KEYWORDS.contains(&name) => format!("r#{}", name),
+        _ => name.to_string(),
+    }
+}
+
+/// "XcmPallet" to "xcm_pallet", and "SS58Prefix" to "ss58_prefix".
+fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+    for (index, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && index > 0 {
+            let prev = chars[index - 1];
+            let next_is_lower = matches!(chars.get(index + 1), Some(n) if n.is_lowercase());
+            if prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_uppercase() && next_is_lower)
+            {
+                snake.push('_');
+            }
+        }
+        snake.extend(c.to_lowercase());
+    }
+    snake
+}
+
+/// "pallet_balances" to "PalletBalances".
+fn upper_camel_case(name: &str) -> String {
+    name.split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+fn bit_sequence(store_bits: u32) -> String {
+    format!(
+        r#"    /// A sequence of bits (a `BitVec` in the runtime), kept as the bytes of its
+    /// u{bits} store (which are little endian).
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct BitSequence{bits} {{
+        pub len: u32,
+        pub bytes: Vec<u8>,
+    }}
+
+    impl ::parity_scale_codec::Encode for BitSequence{bits} {{
+        fn encode_to<T: ::parity_scale_codec::Output + ?Sized>(&self, dest: &mut T) {{
+            ::parity_scale_codec::Encode::encode_to(&::parity_scale_codec::Compact(self.len), dest);
+            dest.write(&self.bytes);
+        }}
+    }}
+
+    impl ::parity_scale_codec::Decode for BitSequence{bits} {{
+        fn decode<I: ::parity_scale_codec::Input>(
+            input: &mut I,
+        ) -> Result<Self, ::parity_scale_codec::Error> {{
+            let len: ::parity_scale_codec::Compact<u32> =
+                ::parity_scale_codec::Decode::decode(input)?;
+            let len = len.0;
+            let store_bytes = {bytes};
+            let mut bytes = vec![0; (len as usize + {bits} - 1) / {bits} * store_bytes];
+            input.read(&mut bytes)?;
+            Ok(BitSequence{bits} {{ len, bytes }})
+        }}
+    }}
+"#,
+        bits = store_bits,
+        bytes = store_bits / 8
+    )
+}
+
+const ENCODED_CALL: &str = r#"
+/// A call, encoded with its pallet and call index, ready to be signed.
+/// This encodes as just those bytes (a `Vec<u8>` would encode its length first).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedCall(pub Vec<u8>);
+
+impl EncodedCall {
+    pub fn new(pallet_index: u8, call: &impl ::parity_scale_codec::Encode) -> Self {
+        let mut bytes = vec![pallet_index];
+        call.encode_to(&mut bytes);
+        EncodedCall(bytes)
+    }
+}
+
+impl ::parity_scale_codec::Encode for EncodedCall {
+    fn size_hint(&self) -> usize {
+        self.0.len()
+    }
+
+    fn encode_to<T: ::parity_scale_codec::Output + ?Sized>(&self, dest: &mut T) {
+        dest.write(&self.0)
+    }
+}
+
+"#;
+
+const HASH_KEY: &str = r#"#[allow(non_camel_case_types, dead_code)]
+enum StorageHasher {
+    Blake2_128,
+    Blake2_256,
+    Blake2_128Concat,
+    Twox128,
+    Twox256,
+    Twox64Concat,
+    Identity,
+}
+
+/// Hash a SCALE encoded storage map key (see `utils::hash_key`).
+fn hash_key(hasher: StorageHasher, key: &[u8]) -> Vec<u8> {
+    use ::sp_core::hashing;
+    match hasher {
+        StorageHasher::Blake2_128 => hashing::blake2_128(key).to_vec(),
+        StorageHasher::Blake2_256 => hashing::blake2_256(key).to_vec(),
+        StorageHasher::Blake2_128Concat => [&hashing::blake2_128(key)[..], key].concat(),
+        StorageHasher::Twox128 => hashing::twox_128(key).to_vec(),
+        StorageHasher::Twox256 => hashing::twox_256(key).to_vec(),
+        StorageHasher::Twox64Concat => [&hashing::twox_64(key)[..], key].concat(),
+        StorageHasher::Identity => key.to_vec(),
+    }
+}
+
+"#;
@@ -0,0 +1,111 @@
+use frame_metadata::RuntimeMetadataV14;
+use serde_json::Value;
+
+use crate::{
+    decode::{u128_from_json, u32_from_json},
+    decode_value, pallet,
+};
+
+/// A constant from the metadata of some pallet, decoded using the type registry.
+#[derive(Debug, Clone)]
+pub struct ConstantDetails {
+    pub pallet: String,
+    pub name: String,
+    pub value: Value,
+    /// The doc comments on the constant in the runtime, one string per line.
+    pub docs: Vec<String>,
+}
+
+/// Every constant of every pallet in the metadata, decoded (see [`decode_value`]). Pallets
+/// are in the order that the metadata lists them, and so are the constants of each pallet.
+pub fn constants(metadata: &RuntimeMetadataV14) -> anyhow::Result<Vec<ConstantDetails>> {
+    let mut details = Vec::new();
+    for pallet in &metadata.pallets {
+        for constant in &pallet.constants {
+            let value = decode_value(&metadata.types, constant.ty.id(), &mut &*constant.value)
+                .map_err(|e| {
+                    anyhow::anyhow!(
+                        "cannot decode constant '{}.{}': {}",
+                        pallet.name,
+                        constant.name,
+                        e
+                    )
+                })?;
+            details.push(ConstantDetails {
+                pallet: pallet.name.clone(),
+                name: constant.name.clone(),
+                value,
+                docs: constant.docs.clone(),
+            });
+        }
+    }
+    Ok(details)
+}
+
+/// Decode a single constant, or hand back `None` if the pallet doesn't have it (constants
+/// come and go between runtime versions, so this isn't always an error).
+pub fn constant_value(
+    metadata: &RuntimeMetadataV14,
+    pallet_name: &str,
+    constant_name: &str,
+) -> anyhow::Result<Option<Value>> {
+    let constant = match pallet(metadata, pallet_name)?
+        .constants
+        .iter()
+        .find(|c| c.name == constant_name)
+    {
+        Some(constant) => constant,
+        None => return Ok(None),
+    };
+    Ok(Some(decode_value(
+        &metadata.types,
+        constant.ty.id(),
+        &mut &*constant.value,
+    )?))
+}
+
+/// Like [`constant_value`], but for constants that we expect to exist.
+pub fn constant(
+    metadata: &RuntimeMetadataV14,
+    pallet_name: &str,
+    constant_name: &str,
+) -> anyhow::Result<Value> {
+    constant_value(metadata, pallet_name, constant_name)?
+        .ok_or_else(|| anyhow::anyhow!("constant '{}.{}' not found", pallet_name, constant_name))
+}
+
+/// The smallest balance that an account can hold (in plancks), from the
+/// `Balances.ExistentialDeposit` constant. Accounts whose balance drops below this are reaped.
+pub fn existential_deposit(metadata: &RuntimeMetadataV14) -> anyhow::Result<u128> {
+    u128_from_json(&constant(metadata, "Balances", "ExistentialDeposit")?)
+}
+
+/// How many recent block hashes the runtime keeps, from the `System.BlockHashCount` constant.
+/// Mortal extrinsics have to refer to a block within this many blocks of the best block.
+pub fn block_hash_count(metadata: &RuntimeMetadataV14) -> anyhow::Result<u32> {
+    u32_from_json(&constant(metadata, "System", "BlockHashCount")?)
+}
+
+/// The most nominators of a validator that are rewarded each era, from the
+/// `Staking.MaxNominatorRewardedPerValidator` constant, or `None` if the runtime doesn't have
+/// it. Newer runtimes pay rewards out in pages instead (see [`max_exposure_page_size`]).
+pub fn max_nominators_rewarded(metadata: &RuntimeMetadataV14) -> anyhow::Result<Option<u32>> {
+    staking_u32(metadata, "MaxNominatorRewardedPerValidator")
+}
+
+/// How many nominators of a validator are rewarded in each page of its rewards, from the
+/// `Staking.MaxExposurePageSize` constant, or `None` if the runtime doesn't page rewards.
+pub fn max_exposure_page_size(metadata: &RuntimeMetadataV14) -> anyhow::Result<Option<u32>> {
+    staking_u32(metadata, "MaxExposurePageSize")
+}
+
+/// A `u32` constant of the `Staking` pallet, or `None` if there's no such constant (or no
+/// `Staking` pallet at all, as on parachains).
+fn staking_u32(metadata: &RuntimeMetadataV14, constant_name: &str) -> anyhow::Result<Option<u32>> {
+    if pallet(metadata, "Staking").is_err() {
+        return Ok(None);
+    }
+    constant_value(metadata, "Staking", constant_name)?
+        .map(|value| u32_from_json(&value))
+        .transpose()
+}
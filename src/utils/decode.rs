@@ -0,0 +1,324 @@
+use parity_scale_codec::{Compact, Decode};
+use scale_info::{form::PortableForm, Field, PortableRegistry, TypeDef, TypeDefPrimitive};
+use serde_json::{json, Map, Value};
+use sp_runtime::AccountId32;
+use std::convert::TryInto;
+
+/// Decode some SCALE encoded bytes into JSON, using the type registry in the
+/// metadata to work out what shape the bytes should have.
+///
+/// In earlier examples we had to dig through the substrate and polkadot repos to find out
+/// which Rust types to decode things into. V14 metadata contains a description of every type
+/// that the runtime might hand back to us, so we can instead follow that description to decode
+/// the bytes without knowing any of the types ahead of time. We translate the types like so:
+///
+/// - Structs with named fields become JSON objects; tuples and unnamed structs become arrays,
+///   except that a struct containing a single unnamed field is replaced by that field.
+/// - Enum variants become `{ "VariantName": fields }`, or just `"VariantName"` if the variant
+///   has no fields.
+/// - Sequences and arrays of bytes become `0x` prefixed hex strings.
+/// - Integers wider than 64 bits become decimal strings (JSON numbers can't represent them).
+///
+/// The bytes we decoded are removed from the front of `data`.
+pub fn decode_value(
+    types: &PortableRegistry,
+    type_id: u32,
+    data: &mut &[u8],
+) -> anyhow::Result<Value> {
+    let ty = types
+        .resolve(type_id)
+        .ok_or_else(|| anyhow::anyhow!("type {} not found in the metadata", type_id))?;
+
+    let value = match ty.type_def() {
+        TypeDef::Composite(composite) => decode_fields(types, composite.fields(), data)?,
+        TypeDef::Variant(variant) => {
+            let index = u8::decode(data)?;
+            let variant = variant
+                .variants()
+                .iter()
+                .find(|v| v.index() == index)
+                .ok_or_else(|| {
+                    anyhow::anyhow!("variant {} not found in type {}", index, type_id)
+                })?;
+
+            if variant.fields().is_empty() {
+                Value::String(variant.name().clone())
+            } else {
+                json!({ variant.name(): decode_fields(types, variant.fields(), data)? })
+            }
+        }
+        TypeDef::Sequence(seq) => {
+            let len = Compact::<u32>::decode(data)?.0 as usize;
+            decode_items(types, seq.type_param().id(), len, data)?
+        }
+        TypeDef::Array(arr) => {
+            decode_items(types, arr.type_param().id(), arr.len() as usize, data)?
+        }
+        TypeDef::Tuple(tuple) => Value::Array(
+            tuple
+                .fields()
+                .iter()
+                .map(|f| decode_value(types, f.id(), data))
+                .collect::<anyhow::Result<_>>()?,
+        ),
+        TypeDef::Primitive(prim) => decode_primitive(prim, data)?,
+        TypeDef::Compact(compact) => decode_compact(types, compact.type_param().id(), data)?,
+        TypeDef::BitSequence(bits) => {
+            // Bit sequences are a compact encoded number of bits, followed by
+            // however many of the "store" type it takes to hold them all:
+            let num_bits = Compact::<u32>::decode(data)?.0 as usize;
+            let store_bits = match types
+                .resolve(bits.bit_store_type().id())
+                .map(|t| t.type_def())
+            {
+                Some(TypeDef::Primitive(TypeDefPrimitive::U8)) => 8,
+                Some(TypeDef::Primitive(TypeDefPrimitive::U16)) => 16,
+                Some(TypeDef::Primitive(TypeDefPrimitive::U32)) => 32,
+                Some(TypeDef::Primitive(TypeDefPrimitive::U64)) => 64,
+                _ => anyhow::bail!("unsupported bit store type in type {}", type_id),
+            };
+            let num_bytes = num_bits.div_ceil(store_bits) * (store_bits / 8);
+            Value::String(format!("0x{}", hex::encode(take_bytes(data, num_bytes)?)))
+        }
+    };
+
+    Ok(value)
+}
+
+/// Decode the fields of a struct or enum variant.
+pub(crate) fn decode_fields(
+    types: &PortableRegistry,
+    fields: &[Field<PortableForm>],
+    data: &mut &[u8],
+) -> anyhow::Result<Value> {
+    let is_named = fields.iter().all(|f| f.name().is_some());
+
+    if fields.is_empty() {
+        Ok(Value::Null)
+    } else if is_named {
+        let mut map = Map::new();
+        for field in fields {
+            let name = field.name().expect("all fields are named; qed").clone();
+            map.insert(name, decode_value(types, field.ty().id(), data)?);
+        }
+        Ok(Value::Object(map))
+    } else if fields.len() == 1 {
+        decode_value(types, fields[0].ty().id(), data)
+    } else {
+        Ok(Value::Array(
+            fields
+                .iter()
+                .map(|f| decode_value(types, f.ty().id(), data))
+                .collect::<anyhow::Result<_>>()?,
+        ))
+    }
+}
+
+/// Decode the items of a sequence or array, representing bytes as hex.
+fn decode_items(
+    types: &PortableRegistry,
+    item_type_id: u32,
+    len: usize,
+    data: &mut &[u8],
+) -> anyhow::Result<Value> {
+    if is_u8(types, item_type_id) {
+        let bytes = take_bytes(data, len)?;
+        return Ok(Value::String(format!("0x{}", hex::encode(bytes))));
+    }
+
+    Ok(Value::Array(
+        (0..len)
+            .map(|_| decode_value(types, item_type_id, data))
+            .collect::<anyhow::Result<_>>()?,
+    ))
+}
+
+fn decode_primitive(prim: &TypeDefPrimitive, data: &mut &[u8]) -> anyhow::Result<Value> {
+    let value = match prim {
+        TypeDefPrimitive::Bool => json!(bool::decode(data)?),
+        TypeDefPrimitive::Char => {
+            let c = char::from_u32(u32::decode(data)?)
+                .ok_or_else(|| anyhow::anyhow!("invalid char"))?;
+            json!(c.to_string())
+        }
+        TypeDefPrimitive::Str => json!(String::decode(data)?),
+        TypeDefPrimitive::U8 => json!(u8::decode(data)?),
+        TypeDefPrimitive::U16 => json!(u16::decode(data)?),
+        TypeDefPrimitive::U32 => json!(u32::decode(data)?),
+        TypeDefPrimitive::U64 => json!(u64::decode(data)?),
+        TypeDefPrimitive::U128 => json!(u128::decode(data)?.to_string()),
+        TypeDefPrimitive::I8 => json!(i8::decode(data)?),
+        TypeDefPrimitive::I16 => json!(i16::decode(data)?),
+        TypeDefPrimitive::I32 => json!(i32::decode(data)?),
+        TypeDefPrimitive::I64 => json!(i64::decode(data)?),
+        TypeDefPrimitive::I128 => json!(i128::decode(data)?.to_string()),
+        // We don't do arithmetic on 256 bit numbers anywhere, so just
+        // hand back the little endian bytes:
+        TypeDefPrimitive::U256 | TypeDefPrimitive::I256 => {
+            json!(format!("0x{}", hex::encode(take_bytes(data, 32)?)))
+        }
+    };
+    Ok(value)
+}
+
+/// Compact encoding is only valid for unsigned integers, or for structs
+/// which wrap a single unsigned integer (like `Perbill`).
+fn decode_compact(
+    types: &PortableRegistry,
+    type_id: u32,
+    data: &mut &[u8],
+) -> anyhow::Result<Value> {
+    let ty = types
+        .resolve(type_id)
+        .ok_or_else(|| anyhow::anyhow!("type {} not found in the metadata", type_id))?;
+
+    let value = match ty.type_def() {
+        TypeDef::Primitive(TypeDefPrimitive::U8) => json!(Compact::<u8>::decode(data)?.0),
+        TypeDef::Primitive(TypeDefPrimitive::U16) => json!(Compact::<u16>::decode(data)?.0),
+        TypeDef::Primitive(TypeDefPrimitive::U32) => json!(Compact::<u32>::decode(data)?.0),
+        TypeDef::Primitive(TypeDefPrimitive::U64) => json!(Compact::<u64>::decode(data)?.0),
+        TypeDef::Primitive(TypeDefPrimitive::U128) => {
+            json!(Compact::<u128>::decode(data)?.0.to_string())
+        }
+        TypeDef::Composite(composite) if composite.fields().len() == 1 => {
+            decode_compact(types, composite.fields()[0].ty().id(), data)?
+        }
+        TypeDef::Tuple(tuple) if tuple.fields().len() == 1 => {
+            decode_compact(types, tuple.fields()[0].id(), data)?
+        }
+        _ => anyhow::bail!("type {} cannot be compact encoded", type_id),
+    };
+    Ok(value)
+}
+
+fn is_u8(types: &PortableRegistry, type_id: u32) -> bool {
+    matches!(
+        types.resolve(type_id).map(|t| t.type_def()),
+        Some(TypeDef::Primitive(TypeDefPrimitive::U8))
+    )
+}
+
+fn take_bytes<'a>(data: &mut &'a [u8], len: usize) -> anyhow::Result<&'a [u8]> {
+    if data.len() < len {
+        anyhow::bail!(
+            "not enough bytes left to decode: need {}, have {}",
+            len,
+            data.len()
+        );
+    }
+    let (bytes, rest) = data.split_at(len);
+    *data = rest;
+    Ok(bytes)
+}
+
+/// Convert an account ID that we've decoded (which will be a hex string) back into an `AccountId32`.
+pub(crate) fn account_from_json(value: &Value) -> anyhow::Result<AccountId32> {
+    let hex_str = value
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("account ID should be a hex string"))?;
+    let bytes: [u8; 32] = hex::decode(hex_str.trim_start_matches("0x"))?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("account ID should be 32 bytes"))?;
+    Ok(bytes.into())
+}
+
+pub(crate) fn u32_from_json(value: &Value) -> anyhow::Result<u32> {
+    value
+        .as_u64()
+        .and_then(|n| n.try_into().ok())
+        .ok_or_else(|| anyhow::anyhow!("expected a u32 but got {}", value))
+}
+
+/// Wide integers are decoded to strings, and narrower ones to numbers; accept either.
+pub(crate) fn u128_from_json(value: &Value) -> anyhow::Result<u128> {
+    parse_number(value).ok_or_else(|| anyhow::anyhow!("expected a u128 but got {}", value))
+}
+
+/// Parse a balance, fee or other number that a node has handed back over RPC. Depending on the
+/// node version and the RPC method, these come back as `0x` prefixed hex strings (like the fees
+/// from "payment_queryFeeDetails"), decimal strings (like "partialFee" from "payment_queryInfo")
+/// or plain JSON numbers. `field` is the name of the field being parsed, for the error message.
+pub fn number_from_json(value: &Value, field: &str) -> anyhow::Result<u128> {
+    match value {
+        Value::Null => anyhow::bail!("field '{}' is missing", field),
+        _ => parse_number(value).ok_or_else(|| {
+            anyhow::anyhow!(
+                "field '{}' should be a number up to u128::MAX, but got {}",
+                field,
+                value
+            )
+        }),
+    }
+}
+
+fn parse_number(value: &Value) -> Option<u128> {
+    match value {
+        Value::String(s) => match s.strip_prefix("0x") {
+            Some(hex_str) => u128::from_str_radix(hex_str, 16).ok(),
+            None => s.parse().ok(),
+        },
+        // Numbers too big for a u64 (or with a fractional part) would have lost precision:
+        Value::Number(n) => n.as_u64().map(Into::into),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_numbers_in_any_form() {
+        // Hex (like the fees from "payment_queryFeeDetails"), decimal strings (like
+        // "partialFee") and plain JSON numbers:
+        assert_eq!(number_from_json(&json!("0x2a"), "fee").unwrap(), 42);
+        assert_eq!(number_from_json(&json!("0x0"), "fee").unwrap(), 0);
+        assert_eq!(
+            number_from_json(&json!("15600000000"), "fee").unwrap(),
+            15_600_000_000
+        );
+        assert_eq!(
+            number_from_json(&json!(u64::MAX), "fee").unwrap(),
+            u64::MAX as u128
+        );
+
+        // Strings can hold numbers up to u128::MAX, which JSON numbers can't:
+        let max = format!("0x{:x}", u128::MAX);
+        assert_eq!(number_from_json(&json!(max), "fee").unwrap(), u128::MAX);
+        let max = u128::MAX.to_string();
+        assert_eq!(number_from_json(&json!(max), "fee").unwrap(), u128::MAX);
+    }
+
+    #[test]
+    fn rejects_numbers_that_lost_precision() {
+        // Bigger than a u64, so serde_json has made it a float:
+        let too_big: Value = serde_json::from_str("18446744073709551616").unwrap();
+        assert_eq!(parse_number(&too_big), None);
+        assert_eq!(parse_number(&json!(1.5)), None);
+        assert_eq!(parse_number(&json!(-1)), None);
+
+        // Strings that don't fit in a u128 are rejected too:
+        let too_big = format!("{}0", u128::MAX);
+        assert_eq!(parse_number(&json!(too_big)), None);
+        assert_eq!(parse_number(&json!("0x1g")), None);
+        assert_eq!(parse_number(&json!("1.5")), None);
+        assert_eq!(parse_number(&json!(true)), None);
+    }
+
+    #[test]
+    fn errors_name_the_field() {
+        let fees = json!({ "partialFee": 1.5 });
+        assert_eq!(
+            number_from_json(&fees["inclusionFee"], "inclusionFee")
+                .unwrap_err()
+                .to_string(),
+            "field 'inclusionFee' is missing"
+        );
+        assert_eq!(
+            number_from_json(&fees["partialFee"], "partialFee")
+                .unwrap_err()
+                .to_string(),
+            "field 'partialFee' should be a number up to u128::MAX, but got 1.5"
+        );
+    }
+}
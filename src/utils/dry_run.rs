@@ -0,0 +1,337 @@
+use frame_metadata::RuntimeMetadataV14;
+use parity_scale_codec::{Compact, Decode, Encode};
+use serde_json::Value;
+use sp_runtime::AccountId32;
+use std::fmt;
+
+use crate::{
+    decode_value, result_from_response, rpc, rpc_response, type_id_by_path, variant_by_index,
+    variant_by_name, EncodedCall, RpcTransport,
+};
+
+/// The JSON-RPC error code for "Method not found". Nodes also give this when an unsafe method
+/// (like "system_dryRun") isn't exposed.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// What would happen if an extrinsic was applied to a block. This mirrors
+/// `sp_runtime::ApplyExtrinsicResult`, which is a
+/// `Result<Result<(), DispatchError>, TransactionValidityError>`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApplyExtrinsicResult {
+    /// The extrinsic is valid, and the call it contains succeeded.
+    Success,
+    /// The extrinsic is valid (so it'd be included in a block and fees paid), but
+    /// the call it contains failed.
+    DispatchFailed(DispatchError),
+    /// The extrinsic is not valid, and would be rejected by the transaction pool.
+    Invalid(TransactionValidityError),
+}
+
+/// Why a call failed. `name` is something like "BadOrigin" or, for errors coming from
+/// a pallet, the pallet and error name, like "Balances.InsufficientBalance".
+#[derive(Debug, Clone, PartialEq)]
+pub struct DispatchError {
+    pub name: String,
+    /// The docs for a pallet error, if any.
+    pub docs: Vec<String>,
+    /// The error as it was decoded.
+    pub details: Value,
+}
+
+impl fmt::Display for DispatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.docs.is_empty() {
+            write!(f, " ({})", self.docs.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+impl DispatchError {
+    /// Make sense of a `DispatchError` that we've decoded to JSON (for instance, as a field of
+    /// the `System.ExtrinsicFailed` event), finding the names of any pallet errors in the metadata.
+    pub fn from_json(metadata: &RuntimeMetadataV14, value: &Value) -> Self {
+        let (name, docs) = match value {
+            // Variants without fields, like "BadOrigin", decode to just their name:
+            Value::String(name) => (name.clone(), Vec::new()),
+            Value::Object(map) if map.contains_key("Module") => {
+                module_error_name(metadata, &map["Module"])
+                    .unwrap_or_else(|| ("Module".to_string(), Vec::new()))
+            }
+            // Other variants, like `Token(FundsUnavailable)`, become "Token.FundsUnavailable":
+            Value::Object(map) => match map.iter().next() {
+                Some((variant, Value::String(inner))) => {
+                    (format!("{}.{}", variant, inner), Vec::new())
+                }
+                Some((variant, _)) => (variant.clone(), Vec::new()),
+                None => ("Unknown".to_string(), Vec::new()),
+            },
+            _ => ("Unknown".to_string(), Vec::new()),
+        };
+
+        DispatchError {
+            name,
+            docs,
+            details: value.clone(),
+        }
+    }
+}
+
+/// Module errors contain the index of the pallet, and the index of the error in that
+/// pallet's error enum. On older runtimes the error index is a single `u8`; on newer
+/// ones it's 4 bytes, the first of which is the error index.
+fn module_error_name(
+    metadata: &RuntimeMetadataV14,
+    module: &Value,
+) -> Option<(String, Vec<String>)> {
+    let pallet_index = module["index"].as_u64()? as u8;
+    let error_index = match &module["error"] {
+        Value::Number(n) => n.as_u64()? as u8,
+        Value::String(hex_str) => *hex::decode(hex_str.trim_start_matches("0x"))
+            .ok()?
+            .first()?,
+        _ => return None,
+    };
+
+    let pallet = metadata.pallets.iter().find(|p| p.index == pallet_index)?;
+    let error = variant_by_index(&metadata.types, pallet.error.as_ref()?.ty.id(), error_index)?;
+    Some((
+        format!("{}.{}", pallet.name, error.name()),
+        error.docs().to_vec(),
+    ))
+}
+
+/// Why a transaction would be rejected. These are described in
+/// `substrate/primitives/runtime/src/transaction_validity.rs`; they don't appear in
+/// the metadata (which only describes types used by calls, events, storage and
+/// constants), so we decode them by hand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionValidityError {
+    /// The transaction is invalid; for instance "Stale" (the nonce has already been used),
+    /// "Future" (the nonce is too high), "Payment" (can't pay the fees) or "BadProof"
+    /// (bad signature).
+    Invalid(String),
+    /// We can't tell whether the transaction is valid or not.
+    Unknown(String),
+}
+
+impl fmt::Display for TransactionValidityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionValidityError::Invalid(reason) => {
+                write!(f, "Invalid transaction: {}", reason)
+            }
+            TransactionValidityError::Unknown(reason) => write!(f, "Unknown validity: {}", reason),
+        }
+    }
+}
+
+impl TransactionValidityError {
+    fn decode(data: &mut &[u8]) -> anyhow::Result<Self> {
+        const INVALID: [&str; 13] = [
+            "Call",
+            "Payment",
+            "Future",
+            "Stale",
+            "BadProof",
+            "AncientBirthBlock",
+            "ExhaustsResources",
+            "Custom",
+            "BadMandatory",
+            "MandatoryValidation",
+            "BadSigner",
+            "IndeterminateImplicit",
+            "UnknownOrigin",
+        ];
+        const UNKNOWN: [&str; 3] = ["CannotLookup", "NoUnsignedValidator", "Custom"];
+
+        let (is_invalid, names) = match u8::decode(data)? {
+            0 => (true, &INVALID[..]),
+            1 => (false, &UNKNOWN[..]),
+            n => anyhow::bail!("invalid TransactionValidityError variant {}", n),
+        };
+
+        let index = u8::decode(data)? as usize;
+        let mut reason = names
+            .get(index)
+            .ok_or_else(|| anyhow::anyhow!("unknown transaction validity reason {}", index))?
+            .to_string();
+        // Custom errors carry a u8 which is up to the runtime to interpret:
+        if reason == "Custom" {
+            reason = format!("Custom({})", u8::decode(data)?);
+        }
+
+        Ok(if is_invalid {
+            TransactionValidityError::Invalid(reason)
+        } else {
+            TransactionValidityError::Unknown(reason)
+        })
+    }
+}
+
+/// Find out what would happen if we submitted some (signed) extrinsic, without submitting it.
+///
+/// This uses the "system_dryRun" RPC method, which applies the extrinsic on top of the given
+/// block (or the latest block) and throws the result away. That method is considered unsafe
+/// and so isn't always exposed; if the node says it doesn't know about it, we call the
+/// `BlockBuilder_apply_extrinsic` runtime API directly via "state_call" instead, which is what
+/// "system_dryRun" does under the hood. Any other error is handed back as is.
+pub async fn dry_run(
+    url: &(impl RpcTransport + ?Sized),
+    metadata: &RuntimeMetadataV14,
+    extrinsic: &[u8],
+    at: Option<&str>,
+) -> anyhow::Result<ApplyExtrinsicResult> {
+    let extrinsic_hex = format!("0x{}", hex::encode(extrinsic));
+
+    let response = rpc_response(url, "system_dryRun", (&extrinsic_hex, at)).await?;
+    let result_hex = if response["error"]["code"] == METHOD_NOT_FOUND {
+        rpc(
+            url,
+            "state_call",
+            ("BlockBuilder_apply_extrinsic", &extrinsic_hex, at),
+        )
+        .await?
+    } else {
+        result_from_response("system_dryRun", response)?
+    };
+
+    let result_bytes = hex::decode(
+        result_hex
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("dry run did not return a hex string"))?
+            .trim_start_matches("0x"),
+    )?;
+    decode_apply_extrinsic_result(metadata, &result_bytes)
+}
+
+/// Decode the SCALE encoded bytes of an `ApplyExtrinsicResult`.
+pub fn decode_apply_extrinsic_result(
+    metadata: &RuntimeMetadataV14,
+    mut bytes: &[u8],
+) -> anyhow::Result<ApplyExtrinsicResult> {
+    let data = &mut bytes;
+
+    // The outer result says whether the transaction is valid:
+    if u8::decode(data)? == 1 {
+        return Ok(ApplyExtrinsicResult::Invalid(
+            TransactionValidityError::decode(data)?,
+        ));
+    }
+
+    // The inner result says whether the call succeeded:
+    if u8::decode(data)? == 0 {
+        return Ok(ApplyExtrinsicResult::Success);
+    }
+
+    Ok(ApplyExtrinsicResult::DispatchFailed(decode_dispatch_error(
+        metadata, data,
+    )?))
+}
+
+/// Decode a SCALE encoded `DispatchError` using the metadata.
+pub fn decode_dispatch_error(
+    metadata: &RuntimeMetadataV14,
+    data: &mut &[u8],
+) -> anyhow::Result<DispatchError> {
+    let dispatch_error_ty = type_id_by_path(&metadata.types, &["sp_runtime", "DispatchError"])
+        .ok_or_else(|| anyhow::anyhow!("DispatchError not found in the metadata"))?;
+    let value = decode_value(&metadata.types, dispatch_error_ty, data)?;
+    Ok(DispatchError::from_json(metadata, &value))
+}
+
+/// Dispatch a call from some account using the `DryRunApi_dry_run_call` runtime API, which
+/// newer runtimes provide. Unlike [`dry_run`], this doesn't need a signed extrinsic (so it
+/// doesn't check the nonce, signature or fees), and only tells us whether the call itself
+/// would succeed.
+pub async fn dry_run_call(
+    url: &(impl RpcTransport + ?Sized),
+    metadata: &RuntimeMetadataV14,
+    origin: &AccountId32,
+    call: &EncodedCall,
+    at: Option<&str>,
+) -> anyhow::Result<Result<(), DispatchError>> {
+    let origin = signed_origin(metadata, origin)?;
+    let args = (&origin, call).encode();
+
+    // Version 2 of the API added an XCM version argument; try without it first, and if the
+    // runtime rejects that, try again with it:
+    let response = rpc_response(
+        url,
+        "state_call",
+        (
+            "DryRunApi_dry_run_call",
+            format!("0x{}", hex::encode(&args)),
+            at,
+        ),
+    )
+    .await?;
+    let result_hex = if response.get("error").is_some() {
+        const XCM_VERSION: u32 = 4;
+        let args = (&origin, call, XCM_VERSION).encode();
+        rpc(
+            url,
+            "state_call",
+            (
+                "DryRunApi_dry_run_call",
+                format!("0x{}", hex::encode(&args)),
+                at,
+            ),
+        )
+        .await?
+    } else {
+        result_from_response("state_call", response)?
+    };
+
+    let result_bytes = hex::decode(
+        result_hex
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("dry run did not return a hex string"))?
+            .trim_start_matches("0x"),
+    )?;
+    let data = &mut &*result_bytes;
+
+    // We get back `Result<CallDryRunEffects, Error>`:
+    if u8::decode(data)? == 1 {
+        anyhow::bail!("the runtime was unable to dry run the call");
+    }
+
+    // `CallDryRunEffects` begins with `execution_result`, which is a `Result<PostDispatchInfo,
+    // DispatchErrorWithPostInfo>`. Both sides start with a `PostDispatchInfo`, which contains
+    // the actual weight used (if known) and whether fees are paid, which we skip over. The
+    // rest of the effects (events and XCMs) follow; we don't look at those here.
+    let is_err = u8::decode(data)? == 1;
+    let _actual_weight = Option::<(Compact<u64>, Compact<u64>)>::decode(data)?;
+    let _pays_fee = u8::decode(data)?;
+
+    if is_err {
+        Ok(Err(decode_dispatch_error(metadata, data)?))
+    } else {
+        Ok(Ok(()))
+    }
+}
+
+/// Encode a signed origin, `OriginCaller::system(RawOrigin::Signed(who))`. The `OriginCaller`
+/// enum is generated for each runtime, so we look up the variant indexes in the metadata.
+fn signed_origin(metadata: &RuntimeMetadataV14, who: &AccountId32) -> anyhow::Result<Vec<u8>> {
+    let origin_caller_ty = metadata
+        .types
+        .types()
+        .iter()
+        .find(|t| t.ty().path().ident().as_deref() == Some("OriginCaller"))
+        .ok_or_else(|| anyhow::anyhow!("OriginCaller not found in the metadata"))?
+        .id();
+    let system = variant_by_name(&metadata.types, origin_caller_ty, "system")
+        .ok_or_else(|| anyhow::anyhow!("OriginCaller has no 'system' variant"))?;
+    let raw_origin_ty = system
+        .fields()
+        .first()
+        .ok_or_else(|| anyhow::anyhow!("OriginCaller::system has no fields"))?
+        .ty()
+        .id();
+    let signed = variant_by_name(&metadata.types, raw_origin_ty, "Signed")
+        .ok_or_else(|| anyhow::anyhow!("RawOrigin has no 'Signed' variant"))?;
+
+    Ok((system.index(), signed.index(), who).encode())
+}
@@ -0,0 +1,388 @@
+use parity_scale_codec::{Compact, Encode};
+use scale_info::{form::PortableForm, Field, PortableRegistry, TypeDef, TypeDefPrimitive};
+use serde_json::Value;
+use std::convert::TryInto;
+
+use crate::{decode::u128_from_json, parse_address};
+
+/// SCALE encode some JSON as the type with the given ID in the metadata. This is the
+/// opposite of [`crate::decode_value`], and accepts JSON in the same shape that it hands
+/// back, along with a few shortcuts for things that are awkward to write out by hand:
+///
+/// - Byte sequences and arrays can be given as `0x` prefixed hex, and byte sequences as
+///   plain text (which is encoded as UTF-8), as well as arrays of numbers.
+/// - 32 byte arrays (like account IDs) can be given as SS58 addresses.
+/// - A `MultiAddress` can be given as just the account (an SS58 address or hex), which
+///   is encoded as `MultiAddress::Id`, unless it's the name of one of its variants.
+/// - Numbers can be given as JSON numbers or as strings, which is the only way to give
+///   numbers too big for a JSON number.
+pub fn encode_value(
+    types: &PortableRegistry,
+    type_id: u32,
+    value: &Value,
+    out: &mut Vec<u8>,
+) -> anyhow::Result<()> {
+    let ty = types
+        .resolve(type_id)
+        .ok_or_else(|| anyhow::anyhow!("type {} not found in the metadata", type_id))?;
+
+    match ty.type_def() {
+        TypeDef::Composite(composite) => encode_fields(types, composite.fields(), value, out)?,
+        TypeDef::Variant(variant) => {
+            let is_multi_address = ty.path().ident().as_deref() == Some("MultiAddress");
+            let (name, fields) = match value {
+                // SS58 addresses can start with an uppercase letter (Kusama's do), so anything
+                // that isn't the name of a variant is taken to be the account:
+                Value::String(name)
+                    if is_multi_address && !variant.variants().iter().any(|v| v.name() == name) =>
+                {
+                    ("Id", value)
+                }
+                Value::String(name) => (name.as_str(), &Value::Null),
+                Value::Object(map) if map.len() == 1 => {
+                    let (name, fields) = map.iter().next().expect("map has one entry; qed");
+                    (name.as_str(), fields)
+                }
+                _ => anyhow::bail!(
+                    "expected an enum variant like \"Name\" or {{ \"Name\": fields }}, but got {}",
+                    value
+                ),
+            };
+            let variant = variant
+                .variants()
+                .iter()
+                .find(|v| v.name() == name)
+                .ok_or_else(|| {
+                    let names: Vec<_> = variant.variants().iter().map(|v| v.name()).collect();
+                    anyhow::anyhow!(
+                        "'{}' is not one of the variants {:?} of type {}",
+                        name,
+                        names,
+                        type_id
+                    )
+                })?;
+            out.push(variant.index());
+            encode_fields(types, variant.fields(), fields, out)?;
+        }
+        TypeDef::Sequence(seq) => {
+            let item_ty = seq.type_param().id();
+            if is_u8(types, item_ty) {
+                if let Value::String(s) = value {
+                    let bytes = match s.strip_prefix("0x") {
+                        Some(hex_str) => hex::decode(hex_str)?,
+                        None => s.as_bytes().to_vec(),
+                    };
+                    bytes.encode_to(out);
+                    return Ok(());
+                }
+            }
+            let items = as_array(value)?;
+            Compact(items.len() as u32).encode_to(out);
+            for item in items {
+                encode_value(types, item_ty, item, out)?;
+            }
+        }
+        TypeDef::Array(arr) => {
+            let item_ty = arr.type_param().id();
+            let len = arr.len() as usize;
+            if is_u8(types, item_ty) {
+                if let Value::String(s) = value {
+                    let bytes = match s.strip_prefix("0x") {
+                        Some(hex_str) => hex::decode(hex_str)?,
+                        None if len == 32 => <[u8; 32]>::from(parse_address(s)?.account).to_vec(),
+                        None => anyhow::bail!("expected {} bytes as hex, but got '{}'", len, s),
+                    };
+                    if bytes.len() != len {
+                        anyhow::bail!("expected {} bytes, but got {}", len, bytes.len());
+                    }
+                    out.extend(bytes);
+                    return Ok(());
+                }
+            }
+            let items = as_array(value)?;
+            if items.len() != len {
+                anyhow::bail!("expected {} items, but got {}", len, items.len());
+            }
+            for item in items {
+                encode_value(types, item_ty, item, out)?;
+            }
+        }
+        TypeDef::Tuple(tuple) => {
+            let fields = tuple.fields();
+            // A tuple of one thing can be given as that thing:
+            let items = match value {
+                Value::Array(items) if fields.len() != 1 || items.len() == 1 => items.as_slice(),
+                single if fields.len() == 1 => std::slice::from_ref(single),
+                Value::Null if fields.is_empty() => &[],
+                _ => anyhow::bail!(
+                    "expected a tuple of {} items, but got {}",
+                    fields.len(),
+                    value
+                ),
+            };
+            if items.len() != fields.len() {
+                anyhow::bail!("expected {} items, but got {}", fields.len(), items.len());
+            }
+            for (field, item) in fields.iter().zip(items) {
+                encode_value(types, field.id(), item, out)?;
+            }
+        }
+        TypeDef::Primitive(prim) => encode_primitive(prim, value, out)?,
+        TypeDef::Compact(compact) => encode_compact(types, compact.type_param().id(), value, out)?,
+        TypeDef::BitSequence(_) => anyhow::bail!("encoding bit sequences is not supported"),
+    }
+    Ok(())
+}
+
+/// Encode the fields of a struct or enum variant, given in the same shape as
+/// [`crate::decode_value`] hands them back.
+fn encode_fields(
+    types: &PortableRegistry,
+    fields: &[Field<PortableForm>],
+    value: &Value,
+    out: &mut Vec<u8>,
+) -> anyhow::Result<()> {
+    let is_named = fields.iter().all(|f| f.name().is_some());
+
+    if fields.is_empty() {
+        Ok(())
+    } else if is_named {
+        let map = value.as_object().ok_or_else(|| {
+            anyhow::anyhow!(
+                "expected an object with fields {:?}, but got {}",
+                field_names(fields),
+                value
+            )
+        })?;
+        for field in fields {
+            let name = field.name().expect("all fields are named; qed");
+            let field_value = map
+                .get(name)
+                .ok_or_else(|| anyhow::anyhow!("field '{}' is missing from {}", name, value))?;
+            encode_value(types, field.ty().id(), field_value, out)?;
+        }
+        Ok(())
+    } else if fields.len() == 1 {
+        encode_value(types, fields[0].ty().id(), value, out)
+    } else {
+        let items = as_array(value)?;
+        if items.len() != fields.len() {
+            anyhow::bail!("expected {} fields, but got {}", fields.len(), items.len());
+        }
+        for (field, item) in fields.iter().zip(items) {
+            encode_value(types, field.ty().id(), item, out)?;
+        }
+        Ok(())
+    }
+}
+
+fn encode_primitive(
+    prim: &TypeDefPrimitive,
+    value: &Value,
+    out: &mut Vec<u8>,
+) -> anyhow::Result<()> {
+    let out_of_range = || anyhow::anyhow!("{} is out of range for a {:?}", value, prim);
+    match prim {
+        TypeDefPrimitive::Bool => value
+            .as_bool()
+            .ok_or_else(|| anyhow::anyhow!("expected true or false, but got {}", value))?
+            .encode_to(out),
+        TypeDefPrimitive::Char => {
+            let s = value.as_str().unwrap_or_default();
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => (c as u32).encode_to(out),
+                _ => anyhow::bail!("expected a single character, but got {}", value),
+            }
+        }
+        TypeDefPrimitive::Str => value
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("expected a string, but got {}", value))?
+            .encode_to(out),
+        TypeDefPrimitive::U8 => {
+            let n: u8 = u128_from_json(value)?
+                .try_into()
+                .map_err(|_| out_of_range())?;
+            n.encode_to(out)
+        }
+        TypeDefPrimitive::U16 => {
+            let n: u16 = u128_from_json(value)?
+                .try_into()
+                .map_err(|_| out_of_range())?;
+            n.encode_to(out)
+        }
+        TypeDefPrimitive::U32 => {
+            let n: u32 = u128_from_json(value)?
+                .try_into()
+                .map_err(|_| out_of_range())?;
+            n.encode_to(out)
+        }
+        TypeDefPrimitive::U64 => {
+            let n: u64 = u128_from_json(value)?
+                .try_into()
+                .map_err(|_| out_of_range())?;
+            n.encode_to(out)
+        }
+        TypeDefPrimitive::U128 => u128_from_json(value)?.encode_to(out),
+        TypeDefPrimitive::I8 => {
+            let n: i8 = i128_from_json(value)?
+                .try_into()
+                .map_err(|_| out_of_range())?;
+            n.encode_to(out)
+        }
+        TypeDefPrimitive::I16 => {
+            let n: i16 = i128_from_json(value)?
+                .try_into()
+                .map_err(|_| out_of_range())?;
+            n.encode_to(out)
+        }
+        TypeDefPrimitive::I32 => {
+            let n: i32 = i128_from_json(value)?
+                .try_into()
+                .map_err(|_| out_of_range())?;
+            n.encode_to(out)
+        }
+        TypeDefPrimitive::I64 => {
+            let n: i64 = i128_from_json(value)?
+                .try_into()
+                .map_err(|_| out_of_range())?;
+            n.encode_to(out)
+        }
+        TypeDefPrimitive::I128 => i128_from_json(value)?.encode_to(out),
+        // These are decoded as their little endian bytes, so we expect the same back:
+        TypeDefPrimitive::U256 | TypeDefPrimitive::I256 => {
+            let hex_str = value.as_str().unwrap_or_default().trim_start_matches("0x");
+            let bytes = hex::decode(hex_str)?;
+            if bytes.len() != 32 {
+                anyhow::bail!("expected 32 little endian bytes as hex, but got {}", value);
+            }
+            out.extend(bytes);
+        }
+    }
+    Ok(())
+}
+
+/// Compact encoding is only valid for unsigned integers, or for structs
+/// which wrap a single unsigned integer (like `Perbill`).
+fn encode_compact(
+    types: &PortableRegistry,
+    type_id: u32,
+    value: &Value,
+    out: &mut Vec<u8>,
+) -> anyhow::Result<()> {
+    let ty = types
+        .resolve(type_id)
+        .ok_or_else(|| anyhow::anyhow!("type {} not found in the metadata", type_id))?;
+    let out_of_range = || anyhow::anyhow!("{} is out of range for type {}", value, type_id);
+
+    match ty.type_def() {
+        TypeDef::Primitive(TypeDefPrimitive::U8) => {
+            let n: u8 = u128_from_json(value)?
+                .try_into()
+                .map_err(|_| out_of_range())?;
+            Compact(n).encode_to(out)
+        }
+        TypeDef::Primitive(TypeDefPrimitive::U16) => {
+            let n: u16 = u128_from_json(value)?
+                .try_into()
+                .map_err(|_| out_of_range())?;
+            Compact(n).encode_to(out)
+        }
+        TypeDef::Primitive(TypeDefPrimitive::U32) => {
+            let n: u32 = u128_from_json(value)?
+                .try_into()
+                .map_err(|_| out_of_range())?;
+            Compact(n).encode_to(out)
+        }
+        TypeDef::Primitive(TypeDefPrimitive::U64) => {
+            let n: u64 = u128_from_json(value)?
+                .try_into()
+                .map_err(|_| out_of_range())?;
+            Compact(n).encode_to(out)
+        }
+        TypeDef::Primitive(TypeDefPrimitive::U128) => {
+            Compact(u128_from_json(value)?).encode_to(out)
+        }
+        TypeDef::Composite(composite) if composite.fields().len() == 1 => {
+            encode_compact(types, composite.fields()[0].ty().id(), value, out)?
+        }
+        TypeDef::Tuple(tuple) if tuple.fields().len() == 1 => {
+            encode_compact(types, tuple.fields()[0].id(), value, out)?
+        }
+        _ => anyhow::bail!("type {} cannot be compact encoded", type_id),
+    }
+    Ok(())
+}
+
+fn as_array(value: &Value) -> anyhow::Result<&Vec<Value>> {
+    value
+        .as_array()
+        .ok_or_else(|| anyhow::anyhow!("expected an array, but got {}", value))
+}
+
+fn field_names(fields: &[Field<PortableForm>]) -> Vec<&str> {
+    fields
+        .iter()
+        .filter_map(|f| f.name().map(|n| n.as_str()))
+        .collect()
+}
+
+fn is_u8(types: &PortableRegistry, type_id: u32) -> bool {
+    matches!(
+        types.resolve(type_id).map(|t| t.type_def()),
+        Some(TypeDef::Primitive(TypeDefPrimitive::U8))
+    )
+}
+
+fn i128_from_json(value: &Value) -> anyhow::Result<i128> {
+    match value {
+        Value::String(s) => s.parse().ok(),
+        Value::Number(n) => n.as_i64().map(Into::into),
+        _ => None,
+    }
+    .ok_or_else(|| anyhow::anyhow!("expected an i128 but got {}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scale_info::{MetaType, Registry};
+    use sp_keyring::AccountKeyring;
+    use sp_runtime::{AccountId32, MultiAddress};
+
+    fn multi_address_registry() -> (PortableRegistry, u32) {
+        let mut registry = Registry::new();
+        let id = registry
+            .register_type(&MetaType::new::<MultiAddress<AccountId32, u32>>())
+            .id();
+        (registry.into(), id)
+    }
+
+    #[test]
+    fn encodes_addresses_as_multi_address_ids() {
+        let (types, id) = multi_address_registry();
+        let alice = MultiAddress::<AccountId32, u32>::Id(AccountKeyring::Alice.to_account_id());
+
+        // Alice's address on Polkadot, Kusama (which starts with an uppercase letter) and
+        // the generic substrate format:
+        for address in [
+            "15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5",
+            "HNZata7iMYWmk5RvZRTiAsSDhV8366zq2YGb3tLH5Upf74F",
+            "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY",
+        ] {
+            let mut out = Vec::new();
+            encode_value(&types, id, &Value::String(address.to_string()), &mut out).unwrap();
+            assert_eq!(out, alice.encode(), "encoding {}", address);
+        }
+    }
+
+    #[test]
+    fn encodes_multi_address_variants_by_name() {
+        let (types, id) = multi_address_registry();
+
+        let mut out = Vec::new();
+        let value = serde_json::json!({ "Index": 42 });
+        encode_value(&types, id, &value, &mut out).unwrap();
+        assert_eq!(out, MultiAddress::<AccountId32, u32>::Index(42).encode());
+    }
+}
@@ -0,0 +1,124 @@
+use frame_metadata::RuntimeMetadataV14;
+use parity_scale_codec::{Compact, Decode};
+use serde_json::Value;
+
+use crate::{
+    decode::decode_fields, get_storage, storage_prefix_key, variant_by_index, RpcTransport,
+};
+
+/// When, during the block's execution, an event was emitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Emitted while applying the extrinsic at this index in the block.
+    ApplyExtrinsic(u32),
+    /// Emitted at the end of the block.
+    Finalization,
+    /// Emitted at the start of the block.
+    Initialization,
+}
+
+/// A single event, decoded using the metadata.
+#[derive(Debug, Clone)]
+pub struct EventDetails {
+    pub phase: Phase,
+    pub pallet: String,
+    pub variant: String,
+    pub fields: Value,
+}
+
+impl EventDetails {
+    /// Is this the given event from the given pallet?
+    pub fn is(&self, pallet: &str, variant: &str) -> bool {
+        self.pallet == pallet && self.variant == variant
+    }
+
+    /// Fetch a field of the event. Depending on the runtime version, event fields may or may
+    /// not be named, so we look for the field by name and fall back to its position.
+    pub fn field(&self, name: &str, position: usize) -> Option<&Value> {
+        match &self.fields {
+            Value::Object(fields) => fields.get(name),
+            Value::Array(fields) => fields.get(position),
+            // A single unnamed field is decoded as the field itself:
+            Value::Null => None,
+            single if position == 0 => Some(single),
+            _ => None,
+        }
+    }
+
+    /// Was this event emitted while applying the extrinsic at the given index?
+    pub fn is_from_extrinsic(&self, extrinsic_index: u32) -> bool {
+        self.phase == Phase::ApplyExtrinsic(extrinsic_index)
+    }
+}
+
+/// Fetch and decode the events emitted in some block (or the latest block if no block
+/// hash is given). Events live in storage at `System.Events`.
+pub async fn get_events(
+    url: &(impl RpcTransport + ?Sized),
+    metadata: &RuntimeMetadataV14,
+    block_hash: Option<&str>,
+) -> anyhow::Result<Vec<EventDetails>> {
+    let events_key = storage_prefix_key("System", "Events");
+    match get_storage(url, &events_key, block_hash).await? {
+        Some(bytes) => decode_events(metadata, &bytes),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Decode the SCALE encoded `Vec<EventRecord>` stored at `System.Events`.
+///
+/// Each record consists of the phase, then the event itself (the index of the pallet
+/// emitting it, the index of the event variant in that pallet's event enum, and then the
+/// fields of that variant), and finally a list of "topics" (hashes) that we skip over.
+pub fn decode_events(
+    metadata: &RuntimeMetadataV14,
+    mut bytes: &[u8],
+) -> anyhow::Result<Vec<EventDetails>> {
+    let data = &mut bytes;
+    let num_events = Compact::<u32>::decode(data)?.0;
+
+    let mut events = Vec::with_capacity(num_events as usize);
+    for _ in 0..num_events {
+        let phase = match u8::decode(data)? {
+            0 => Phase::ApplyExtrinsic(u32::decode(data)?),
+            1 => Phase::Finalization,
+            2 => Phase::Initialization,
+            n => anyhow::bail!("invalid event phase {}", n),
+        };
+
+        let pallet_index = u8::decode(data)?;
+        let variant_index = u8::decode(data)?;
+
+        let pallet = metadata
+            .pallets
+            .iter()
+            .find(|p| p.index == pallet_index)
+            .ok_or_else(|| anyhow::anyhow!("pallet with index {} not found", pallet_index))?;
+        let event_ty = pallet
+            .event
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("pallet '{}' has no events", pallet.name))?;
+        let variant = variant_by_index(&metadata.types, event_ty.ty.id(), variant_index)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "event {} not found in pallet '{}'",
+                    variant_index,
+                    pallet.name
+                )
+            })?;
+
+        let fields = decode_fields(&metadata.types, variant.fields(), data)?;
+
+        // Topics are a Vec<Hash>; skip over them:
+        let _topics = Vec::<[u8; 32]>::decode(data)?;
+
+        events.push(EventDetails {
+            phase,
+            pallet: pallet.name.clone(),
+            variant: variant.name().clone(),
+            fields,
+        });
+    }
+
+    Ok(events)
+}
@@ -0,0 +1,353 @@
+use frame_metadata::RuntimeMetadataV14;
+use jsonrpsee::async_client::Client;
+use parity_scale_codec::{Compact, Decode, Encode, Output};
+use scale_info::{form::PortableForm, Field, PortableRegistry};
+use sp_core::{blake2_256, sr25519, Pair, H256};
+use sp_runtime::{generic::Era, AccountId32, MultiAddress, MultiSignature};
+use sp_version::RuntimeVersion;
+use std::str::FromStr;
+
+use crate::{
+    call_index, pallet, rpc, submit_and_watch, variant_by_name, NonceManager, RpcTransport,
+};
+
+/// Call data which has already been SCALE encoded (for example, the pallet index, call index
+/// and arguments of example 05). It encodes to exactly these bytes, so calls built in different
+/// ways (or from different pallets) can be put side by side, as in a `Utility.batch`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedCall(pub Vec<u8>);
+
+impl EncodedCall {
+    /// Encode a call from its pallet index, call index and (SCALE encodable) arguments.
+    pub fn new<Args: Encode>(pallet_index: u8, call_index: u8, args: Args) -> Self {
+        EncodedCall((pallet_index, call_index, args).encode())
+    }
+
+    /// Like [`EncodedCall::new`], but looks up the pallet and call indexes by name in the metadata.
+    pub fn by_name<Args: Encode>(
+        metadata: &RuntimeMetadataV14,
+        pallet_name: &str,
+        call_name: &str,
+        args: Args,
+    ) -> anyhow::Result<Self> {
+        let (pallet_index, call_index) = call_index(metadata, pallet_name, call_name)?;
+        Ok(EncodedCall::new(pallet_index, call_index, args))
+    }
+
+    /// Build a call by encoding, in order, each of the arguments that the metadata says it
+    /// takes. This is handy when a call's arguments have changed shape between runtime versions;
+    /// `encode_arg` is given each argument (its name and type) and writes its encoded value.
+    pub fn from_fields(
+        metadata: &RuntimeMetadataV14,
+        pallet_name: &str,
+        call_name: &str,
+        mut encode_arg: impl FnMut(&Field<PortableForm>, &mut Vec<u8>) -> anyhow::Result<()>,
+    ) -> anyhow::Result<Self> {
+        let (pallet_index, call_index) = call_index(metadata, pallet_name, call_name)?;
+        let calls_ty = pallet(metadata, pallet_name)?
+            .calls
+            .as_ref()
+            .expect("call_index found the call; qed")
+            .ty
+            .id();
+        let variant = variant_by_name(&metadata.types, calls_ty, call_name)
+            .expect("call_index found the call; qed");
+
+        let mut bytes = vec![pallet_index, call_index];
+        for field in variant.fields() {
+            encode_arg(field, &mut bytes)?;
+        }
+        Ok(EncodedCall(bytes))
+    }
+}
+
+/// Encode an account ID as an argument of the given type. Some calls take a plain `AccountId`,
+/// and others take a `MultiAddress` (which we saw in example 05), and this has changed between
+/// runtime versions for some calls; the metadata tells us which we need.
+pub fn encode_account_arg(
+    types: &PortableRegistry,
+    type_id: u32,
+    account: &AccountId32,
+    out: &mut Vec<u8>,
+) -> anyhow::Result<()> {
+    let ty = types
+        .resolve(type_id)
+        .ok_or_else(|| anyhow::anyhow!("type {} not found in the metadata", type_id))?;
+
+    if ty.path().ident().as_deref() == Some("MultiAddress") {
+        MultiAddress::Id::<_, u32>(account.clone()).encode_to(out);
+    } else {
+        account.encode_to(out);
+    }
+    Ok(())
+}
+
+impl Encode for EncodedCall {
+    fn size_hint(&self) -> usize {
+        self.0.len()
+    }
+
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        dest.write(&self.0)
+    }
+}
+
+/// Fetch the genesis hash from the node.
+pub async fn get_genesis_hash(url: &(impl RpcTransport + ?Sized)) -> anyhow::Result<H256> {
+    let genesis_hash_json = rpc(url, "chain_getBlockHash", [0]).await?;
+    let genesis_hash_hex = genesis_hash_json
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("chain_getBlockHash did not return a string"))?;
+    Ok(H256::from_str(genesis_hash_hex)?)
+}
+
+/// Find the hash of a block given either its number or its hash (which is handed back as is),
+/// so that commands can accept either.
+pub async fn get_block_hash(
+    url: &(impl RpcTransport + ?Sized),
+    block: &str,
+) -> anyhow::Result<String> {
+    if block.starts_with("0x") {
+        return Ok(block.to_string());
+    }
+    let number: u64 = block
+        .parse()
+        .map_err(|_| anyhow::anyhow!("'{}' is not a block number or hash", block))?;
+    let hash = rpc(url, "chain_getBlockHash", [number]).await?;
+    hash.as_str()
+        .map(|hash| hash.to_string())
+        .ok_or_else(|| anyhow::anyhow!("no block hash found for block {}", number))
+}
+
+/// Fetch runtime information from the node.
+pub async fn get_runtime_version(
+    url: &(impl RpcTransport + ?Sized),
+) -> anyhow::Result<RuntimeVersion> {
+    let runtime_version_json = rpc(url, "state_getRuntimeVersion", ()).await?;
+    Ok(serde_json::from_value(runtime_version_json)?)
+}
+
+/// Build and sign an immortal extrinsic for some call in the same way that
+/// example 05 does, handing back the SCALE encoded bytes ready for submission.
+pub fn signed_extrinsic<C: Encode>(
+    signer: &sr25519::Pair,
+    call: C,
+    nonce: u32,
+    tip: u128,
+    runtime_version: &RuntimeVersion,
+    genesis_hash: H256,
+) -> Vec<u8> {
+    let from: AccountId32 = signer.public().into();
+    let extra = (Era::Immortal, Compact(nonce), Compact(tip));
+    let additional = (
+        runtime_version.spec_version,
+        runtime_version.transaction_version,
+        genesis_hash,
+        genesis_hash,
+    );
+
+    let signature = {
+        let full_unsigned_payload_scale_bytes = (&call, &extra, &additional).encode();
+
+        // If payload is longer than 256 bytes, we hash it and sign the hash instead:
+        if full_unsigned_payload_scale_bytes.len() > 256 {
+            signer.sign(&blake2_256(&full_unsigned_payload_scale_bytes)[..])
+        } else {
+            signer.sign(&full_unsigned_payload_scale_bytes)
+        }
+    };
+
+    let signature_to_encode = Some((
+        MultiAddress::Id::<_, u32>(from),
+        MultiSignature::Sr25519(signature),
+        extra,
+    ));
+
+    encode_extrinsic(signature_to_encode, call)
+}
+
+/// Build an extrinsic which looks just like a signed one from `from`, but whose signature is
+/// all zeroes. It has exactly the same length as the real thing would, which makes it useful
+/// for estimating fees (which depend on the length, but don't check the signature) before
+/// anything has been signed.
+pub fn dummy_signed_extrinsic<C: Encode>(
+    from: &AccountId32,
+    call: C,
+    nonce: u32,
+    tip: u128,
+) -> Vec<u8> {
+    let extra = (Era::Immortal, Compact(nonce), Compact(tip));
+    let signature = sr25519::Signature::from_raw([0u8; 64]);
+
+    let signature_to_encode = Some((
+        MultiAddress::Id::<_, u32>(from.clone()),
+        MultiSignature::Sr25519(signature),
+        extra,
+    ));
+
+    encode_extrinsic(signature_to_encode, call)
+}
+
+/// Encode the extrinsic into the expected format. De-optimised a little
+/// for simplicity, and taken from sp_runtime/src/generic/unchecked_extrinsic.rs
+/// (see example 05 for a walkthrough).
+pub fn encode_extrinsic<S: Encode, C: Encode>(signature: Option<S>, call: C) -> Vec<u8> {
+    let mut tmp: Vec<u8> = vec![];
+
+    // The top bit is 1 if signature present, 0 if not.
+    // The remaining 7 bits encode the version number (here, 4).
+    const EXTRINSIC_VERSION: u8 = 4;
+    match signature.as_ref() {
+        Some(s) => {
+            tmp.push(EXTRINSIC_VERSION | 0b1000_0000);
+            s.encode_to(&mut tmp);
+        }
+        None => {
+            tmp.push(EXTRINSIC_VERSION & 0b0111_1111);
+        }
+    }
+
+    call.encode_to(&mut tmp);
+
+    // Prefix the encoded data with its compact encoded length:
+    let compact_len = Compact(tmp.len() as u32);
+    let mut output: Vec<u8> = vec![];
+    compact_len.encode_to(&mut output);
+    output.extend(tmp);
+
+    output
+}
+
+/// Who signed an extrinsic, and the signed extra data that we care about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedDetails {
+    pub signer: AccountId32,
+    pub nonce: u32,
+    pub tip: u128,
+}
+
+/// What we can tell about who signed an extrinsic (see [`decode_signed_details`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtrinsicSigner {
+    /// The extrinsic isn't signed (for instance, it's an inherent like the timestamp).
+    Unsigned,
+    /// The extrinsic is signed, and we decoded the details.
+    Signed(SignedDetails),
+    /// The extrinsic is signed, but not in a way we know how to decode; for instance, it's
+    /// a different extrinsic version, or the signer isn't a `MultiAddress::Id`. The reason
+    /// says which.
+    Undecodable(String),
+}
+
+impl ExtrinsicSigner {
+    /// The signed details, if we have them.
+    pub fn signed(&self) -> Option<&SignedDetails> {
+        match self {
+            ExtrinsicSigner::Signed(details) => Some(details),
+            _ => None,
+        }
+    }
+}
+
+/// Decode the signer, nonce and tip from an extrinsic (including its length prefix, as
+/// [`encode_extrinsic`] produces and "chain_getBlock" returns).
+///
+/// This undoes [`encode_extrinsic`], and so assumes the same layout; the signer is a
+/// `MultiAddress::Id`, and the signed extra begins with the era, nonce and tip. Blocks can
+/// contain extrinsics which are signed some other way, so rather than failing, we hand back
+/// [`ExtrinsicSigner::Undecodable`] for those and leave it to the caller to skip them.
+pub fn decode_signed_details(extrinsic: &[u8]) -> ExtrinsicSigner {
+    match try_decode_signed_details(extrinsic) {
+        Ok(Some(details)) => ExtrinsicSigner::Signed(details),
+        Ok(None) => ExtrinsicSigner::Unsigned,
+        Err(e) => ExtrinsicSigner::Undecodable(e.to_string()),
+    }
+}
+
+fn try_decode_signed_details(mut extrinsic: &[u8]) -> anyhow::Result<Option<SignedDetails>> {
+    let data = &mut extrinsic;
+    let _len = Compact::<u32>::decode(data)?;
+
+    let version = u8::decode(data)?;
+    if version & 0b1000_0000 == 0 {
+        return Ok(None);
+    }
+    if version & 0b0111_1111 != 4 {
+        anyhow::bail!("unsupported extrinsic version {}", version & 0b0111_1111);
+    }
+
+    let signer = match MultiAddress::<AccountId32, u32>::decode(data)? {
+        MultiAddress::Id(id) => id,
+        _ => anyhow::bail!("only extrinsics signed by a MultiAddress::Id are supported"),
+    };
+    let _signature = MultiSignature::decode(data)?;
+    let _era = Era::decode(data)?;
+    let nonce = Compact::<u32>::decode(data)?.0;
+    let tip = Compact::<u128>::decode(data)?.0;
+
+    Ok(Some(SignedDetails { signer, nonce, tip }))
+}
+
+/// Find the index of an extrinsic (given as the hex string we submitted) within some block.
+/// Events emitted by the extrinsic are tagged with this index (see [`crate::Phase`]).
+pub async fn find_extrinsic_index(
+    url: &(impl RpcTransport + ?Sized),
+    block_hash: &str,
+    extrinsic_hex: &str,
+) -> anyhow::Result<Option<u32>> {
+    let block = rpc(url, "chain_getBlock", [block_hash]).await?;
+    let extrinsics = block["block"]["extrinsics"]
+        .as_array()
+        .ok_or_else(|| anyhow::anyhow!("block {} has no extrinsics", block_hash))?;
+
+    Ok(extrinsics
+        .iter()
+        .position(|ext| ext.as_str() == Some(extrinsic_hex))
+        .map(|idx| idx as u32))
+}
+
+/// Where a submitted extrinsic ended up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InBlock {
+    pub block_hash: String,
+    pub extrinsic_index: u32,
+}
+
+/// Sign a call (using the next nonce for the signer), submit it, and wait until it's been
+/// included in a block. This bundles up the steps from example 05 with the watching from
+/// example 09, for when we just want to get a call into a block and look at what happened.
+pub async fn sign_and_watch(
+    client: &Client,
+    url: &(impl RpcTransport + ?Sized),
+    nonces: &NonceManager<impl RpcTransport>,
+    signer: &sr25519::Pair,
+    call: &EncodedCall,
+) -> anyhow::Result<InBlock> {
+    let runtime_version = get_runtime_version(url).await?;
+    let genesis_hash = get_genesis_hash(url).await?;
+    let account: AccountId32 = signer.public().into();
+    let nonce = nonces.next(&account).await?;
+
+    let payload = signed_extrinsic(signer, call, nonce, 0, &runtime_version, genesis_hash);
+    let payload_hex = format!("0x{}", hex::encode(&payload));
+    let block_hash = match submit_and_watch(client, &payload_hex).await {
+        Ok(block_hash) => block_hash,
+        Err(e) => {
+            // The transaction never made it into a block, so give the nonce back. Otherwise
+            // every later transaction from this account would be stuck waiting on the gap:
+            nonces.release(&account, nonce).await;
+            return Err(e);
+        }
+    };
+
+    // The nonce has been used up by now, so failing to find the extrinsic in its block
+    // doesn't give it back:
+    let extrinsic_index = find_extrinsic_index(url, &block_hash, &payload_hex)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("extrinsic not found in block {}", block_hash))?;
+
+    Ok(InBlock {
+        block_hash,
+        extrinsic_index,
+    })
+}
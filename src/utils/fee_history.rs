@@ -0,0 +1,140 @@
+use parity_scale_codec::Decode;
+
+use crate::{
+    decode_signed_details, get_storage, query_fees, rpc, storage_prefix_key, ExtrinsicSigner,
+    InclusionFee, RpcTransport,
+};
+
+/// `TransactionPayment.NextFeeMultiplier` is a `FixedU128`; a u128 with 18 decimal places.
+/// This is the value of a multiplier of 1 (which is also what it defaults to).
+pub const FEE_MULTIPLIER_ONE: u128 = 1_000_000_000_000_000_000;
+
+/// The fees paid by a single signed extrinsic in a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtrinsicFees {
+    /// The index of the extrinsic in the block.
+    pub index: u32,
+    pub base_fee: u128,
+    pub len_fee: u128,
+    pub adjusted_weight_fee: u128,
+    pub tip: u128,
+}
+
+impl ExtrinsicFees {
+    /// The fee excluding the tip.
+    pub fn partial_fee(&self) -> u128 {
+        self.base_fee + self.len_fee + self.adjusted_weight_fee
+    }
+}
+
+/// The fees paid by every signed extrinsic in a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockFees {
+    pub number: u64,
+    pub hash: String,
+    pub extrinsics: Vec<ExtrinsicFees>,
+    /// How many signed extrinsics were left out of `extrinsics` because we couldn't decode them
+    /// (see [`crate::ExtrinsicSigner::Undecodable`]).
+    pub skipped_extrinsics: usize,
+    /// The fee multiplier that will apply to the next block, as a `FixedU128`
+    /// (see [`FEE_MULTIPLIER_ONE`]). This goes up when blocks are busy, and down when they're not.
+    pub next_fee_multiplier: u128,
+}
+
+impl BlockFees {
+    /// The median fee (excluding tips) paid by extrinsics in this block, if there were any.
+    pub fn median_fee(&self) -> Option<u128> {
+        let mut fees: Vec<u128> = self.extrinsics.iter().map(|e| e.partial_fee()).collect();
+        fees.sort_unstable();
+        match fees.len() {
+            0 => None,
+            n if n % 2 == 1 => Some(fees[n / 2]),
+            n => Some((fees[n / 2 - 1] + fees[n / 2]) / 2),
+        }
+    }
+
+    /// The total of the fees (excluding tips) paid in this block.
+    pub fn total_fees(&self) -> u128 {
+        self.extrinsics.iter().map(|e| e.partial_fee()).sum()
+    }
+
+    /// The total of the tips paid in this block.
+    pub fn total_tips(&self) -> u128 {
+        self.extrinsics.iter().map(|e| e.tip).sum()
+    }
+}
+
+/// Work out the fees paid by each signed extrinsic in some block, using the same fee breakdown
+/// as example 07. Fees are calculated against the state of the parent block, since that's the
+/// state that each extrinsic was applied on top of (and so has the fee multiplier that applied).
+///
+/// Like example 07, these are the fees calculated before dispatch; see [`crate::actual_fee_paid`]
+/// for what was paid once any weight was refunded.
+pub async fn block_fees(
+    url: &(impl RpcTransport + ?Sized),
+    block_number: u64,
+) -> anyhow::Result<BlockFees> {
+    let hash = rpc(url, "chain_getBlockHash", [block_number])
+        .await?
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("no block hash found for block {}", block_number))?
+        .to_string();
+
+    let block = rpc(url, "chain_getBlock", [&hash]).await?;
+    let parent_hash = block["block"]["header"]["parentHash"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("block {} has no parent hash", hash))?;
+    let extrinsics_hex = block["block"]["extrinsics"]
+        .as_array()
+        .ok_or_else(|| anyhow::anyhow!("block {} has no extrinsics", hash))?;
+
+    let mut extrinsics = Vec::new();
+    let mut skipped_extrinsics = 0;
+    for (index, extrinsic_hex) in extrinsics_hex.iter().enumerate() {
+        let extrinsic = hex::decode(
+            extrinsic_hex
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("extrinsic should be a hex string"))?
+                .trim_start_matches("0x"),
+        )?;
+
+        // Unsigned extrinsics (like the timestamp inherent) don't pay fees, and we can't get
+        // the tip from extrinsics signed in a format we don't understand:
+        let tip = match decode_signed_details(&extrinsic) {
+            ExtrinsicSigner::Signed(details) => details.tip,
+            ExtrinsicSigner::Unsigned => continue,
+            ExtrinsicSigner::Undecodable(_) => {
+                skipped_extrinsics += 1;
+                continue;
+            }
+        };
+
+        let estimate = query_fees(url, &extrinsic, Some(parent_hash)).await?;
+        let inclusion_fee = estimate.inclusion_fee.unwrap_or(InclusionFee {
+            base_fee: 0,
+            len_fee: 0,
+            adjusted_weight_fee: 0,
+        });
+        extrinsics.push(ExtrinsicFees {
+            index: index as u32,
+            base_fee: inclusion_fee.base_fee,
+            len_fee: inclusion_fee.len_fee,
+            adjusted_weight_fee: inclusion_fee.adjusted_weight_fee,
+            tip,
+        });
+    }
+
+    let multiplier_key = storage_prefix_key("TransactionPayment", "NextFeeMultiplier");
+    let next_fee_multiplier = match get_storage(url, &multiplier_key, Some(&hash)).await? {
+        Some(bytes) => u128::decode(&mut &*bytes)?,
+        None => FEE_MULTIPLIER_ONE,
+    };
+
+    Ok(BlockFees {
+        number: block_number,
+        hash,
+        extrinsics,
+        skipped_extrinsics,
+        next_fee_multiplier,
+    })
+}
@@ -0,0 +1,367 @@
+use frame_metadata::RuntimeMetadataV14;
+use parity_scale_codec::{Compact, Decode, Encode};
+use sp_runtime::AccountId32;
+
+use crate::{
+    decode::u128_from_json, decode_signed_details, dummy_signed_extrinsic, get_events, rpc,
+    ExtrinsicSigner, RpcTransport, Weight,
+};
+
+/// The fees that an extrinsic is expected to pay, as calculated by the
+/// TransactionPayment pallet before the extrinsic is dispatched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeeEstimate {
+    /// The weight of the extrinsic, before any refund after dispatching.
+    pub weight: Weight,
+    /// The dispatch class of the extrinsic ("Normal", "Operational" or "Mandatory").
+    pub class: String,
+    /// The fee, excluding any tip. This is the sum of the inclusion fee parts.
+    pub partial_fee: u128,
+    /// A breakdown of the partial fee. This is `None` for extrinsics which pay no fee.
+    pub inclusion_fee: Option<InclusionFee>,
+}
+
+/// The breakdown of the fee paid to include an extrinsic in a block (see example 07).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InclusionFee {
+    /// A fixed fee paid by every extrinsic.
+    pub base_fee: u128,
+    /// A fee based on the encoded length of the extrinsic.
+    pub len_fee: u128,
+    /// A fee based on the weight of the extrinsic, adjusted according to how busy the chain is.
+    pub adjusted_weight_fee: u128,
+}
+
+/// Estimate the fees for a call made by `from`, before anything is signed. We put the call in an
+/// extrinsic with a dummy signature (see [`dummy_signed_extrinsic`]), using the account's next
+/// nonce and the tip we intend to pay, so that it's the same length as the real one will be.
+pub async fn estimate_fees<C: Encode>(
+    url: &(impl RpcTransport + ?Sized),
+    from: &AccountId32,
+    call: C,
+    tip: u128,
+) -> anyhow::Result<FeeEstimate> {
+    let nonce: u32 = serde_json::from_value(rpc(url, "system_accountNextIndex", (from,)).await?)?;
+    let extrinsic = dummy_signed_extrinsic(from, call, nonce, tip);
+    query_fees(url, &extrinsic, None).await
+}
+
+/// Ask the runtime what fees some extrinsic would pay, at the given block (or the latest block).
+///
+/// Example 07 does this via the "payment_queryInfo" and "payment_queryFeeDetails" RPC methods,
+/// which hand back JSON. Those RPC methods just call into the `TransactionPaymentApi` runtime
+/// API, which we can also call ourselves via "state_call", getting back SCALE encoded results.
+pub async fn query_fees(
+    url: &(impl RpcTransport + ?Sized),
+    extrinsic: &[u8],
+    at: Option<&str>,
+) -> anyhow::Result<FeeEstimate> {
+    // Both runtime API functions take the extrinsic and its encoded length:
+    let args = format!(
+        "0x{}{}",
+        hex::encode(extrinsic),
+        hex::encode((extrinsic.len() as u32).encode())
+    );
+
+    let info_bytes = state_call(url, "TransactionPaymentApi_query_info", &args, at).await?;
+    let details_bytes =
+        state_call(url, "TransactionPaymentApi_query_fee_details", &args, at).await?;
+
+    let (weight, class, partial_fee) = decode_dispatch_info(&info_bytes)?;
+
+    // `FeeDetails` is an `Option<InclusionFee>` followed by the tip (which is always 0 here,
+    // since the tip isn't part of the estimate):
+    let inclusion_fee = Option::<(u128, u128, u128)>::decode(&mut &*details_bytes)?.map(
+        |(base_fee, len_fee, adjusted_weight_fee)| InclusionFee {
+            base_fee,
+            len_fee,
+            adjusted_weight_fee,
+        },
+    );
+
+    Ok(FeeEstimate {
+        weight,
+        class,
+        partial_fee,
+        inclusion_fee,
+    })
+}
+
+/// Decode a `RuntimeDispatchInfo`, which is `{ weight, class, partial_fee }`. Depending on the
+/// runtime, the weight is either a `u64` or a `{ ref_time, proof_size }` struct of compact
+/// encoded numbers. The class (1 byte) and partial fee (a 16 byte u128) come after it, so we
+/// decode those from the end and whatever is left is the weight. A `u64` always takes 8 bytes,
+/// but two compact numbers can take as few as 2 (for an ordinary transfer they take about 6).
+fn decode_dispatch_info(bytes: &[u8]) -> anyhow::Result<(Weight, String, u128)> {
+    if bytes.len() < 17 + 2 {
+        anyhow::bail!("RuntimeDispatchInfo is too short ({} bytes)", bytes.len());
+    }
+    let (weight_bytes, rest) = bytes.split_at(bytes.len() - 17);
+
+    // Two compact numbers can also add up to 8 bytes, so we only treat the weight as a `u64`
+    // if it doesn't decode as exactly two compact numbers:
+    let mut compact_bytes = weight_bytes;
+    let weight = match <(Compact<u64>, Compact<u64>)>::decode(&mut compact_bytes) {
+        Ok((ref_time, proof_size)) if compact_bytes.is_empty() => {
+            Weight::new(ref_time.0, proof_size.0)
+        }
+        _ if weight_bytes.len() == 8 => Weight::new(u64::decode(&mut &*weight_bytes)?, 0),
+        _ => anyhow::bail!("could not decode the weight in RuntimeDispatchInfo"),
+    };
+
+    let class = match rest[0] {
+        0 => "Normal",
+        1 => "Operational",
+        2 => "Mandatory",
+        n => anyhow::bail!("invalid dispatch class {}", n),
+    };
+    let partial_fee = u128::decode(&mut &rest[1..])?;
+
+    Ok((weight, class.to_string(), partial_fee))
+}
+
+/// Call a runtime API function via "state_call", handing back the SCALE encoded result.
+async fn state_call(
+    url: &(impl RpcTransport + ?Sized),
+    function: &str,
+    args_hex: &str,
+    at: Option<&str>,
+) -> anyhow::Result<Vec<u8>> {
+    let result = rpc(url, "state_call", (function, args_hex, at)).await?;
+    let result_hex = result
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("{} did not return a hex string", function))?;
+    Ok(hex::decode(result_hex.trim_start_matches("0x"))?)
+}
+
+/// The (unadjusted) fee for each dimension of a weight, as calculated by the runtime.
+///
+/// Runtimes using two dimensional weights convert each dimension to a fee separately, and then
+/// combine them; Polkadot, for instance, has historically charged for ref time only, while other
+/// runtimes charge for whichever of the two is more expensive. The `total` is the fee for the
+/// whole weight, however the runtime chooses to combine them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WeightFees {
+    pub ref_time_fee: u128,
+    pub proof_size_fee: u128,
+    pub total: u128,
+}
+
+/// Ask the runtime to convert a weight into a fee using the `TransactionPaymentApi_query_weight_to_fee`
+/// runtime API. This ignores the fee multiplier (which the "adjusted" weight fee takes into account),
+/// and is only available on runtimes which use two dimensional weights.
+pub async fn weight_to_fee(
+    url: &(impl RpcTransport + ?Sized),
+    weight: Weight,
+    at: Option<&str>,
+) -> anyhow::Result<u128> {
+    // The API takes a `Weight { #[codec(compact)] ref_time, #[codec(compact)] proof_size }`:
+    let args = (Compact(weight.ref_time), Compact(weight.proof_size)).encode();
+    let args_hex = format!("0x{}", hex::encode(args));
+    let result = state_call(
+        url,
+        "TransactionPaymentApi_query_weight_to_fee",
+        &args_hex,
+        at,
+    )
+    .await?;
+    Ok(u128::decode(&mut &*result)?)
+}
+
+/// Break down the fee for a weight into the fee for each of its dimensions (see [`weight_to_fee`]).
+pub async fn weight_fees(
+    url: &(impl RpcTransport + ?Sized),
+    weight: Weight,
+    at: Option<&str>,
+) -> anyhow::Result<WeightFees> {
+    Ok(WeightFees {
+        ref_time_fee: weight_to_fee(url, Weight::new(weight.ref_time, 0), at).await?,
+        proof_size_fee: weight_to_fee(url, Weight::new(0, weight.proof_size), at).await?,
+        total: weight_to_fee(url, weight, at).await?,
+    })
+}
+
+/// What an extrinsic in some block actually paid, compared with what it was estimated to pay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActualFee {
+    /// The fees that the runtime expected the extrinsic to pay, at the block before it.
+    pub estimate: FeeEstimate,
+    /// The weight that the extrinsic actually used, from the `System.ExtrinsicSuccess`
+    /// (or `System.ExtrinsicFailed`) event.
+    pub actual_weight: Weight,
+    /// Whether the extrinsic paid fees at all. Calls can decide to waive them once dispatched.
+    pub pays_fee: bool,
+    /// The fee actually paid, excluding the tip.
+    pub partial_fee: u128,
+    /// The tip paid to the block author.
+    pub tip: u128,
+    /// Whether the fee was taken from a `TransactionPayment.TransactionFeePaid` event, or
+    /// calculated from the actual weight (for runtimes which don't emit that event).
+    pub from_event: bool,
+}
+
+impl ActualFee {
+    /// The total paid, including the tip.
+    pub fn total(&self) -> u128 {
+        self.partial_fee + self.tip
+    }
+
+    /// How much more (or, if negative, less) was paid than estimated, ignoring the tip.
+    pub fn difference_from_estimate(&self) -> i128 {
+        self.partial_fee as i128 - self.estimate.partial_fee as i128
+    }
+}
+
+/// Work out what fee the extrinsic at some index in a block actually paid. This is the
+/// calculation described at the bottom of example 07.
+///
+/// The fee estimated before dispatch assumes the worst case weight of the call. Once dispatched,
+/// the actual weight used (and whether fees are paid at all) is reported in the
+/// `System.ExtrinsicSuccess` event, and any overpayment is refunded. Newer runtimes tell us the
+/// final fee and tip in a `TransactionPayment.TransactionFeePaid` event; otherwise, we scale the
+/// weight part of the estimate by how much of the estimated weight was actually used.
+pub async fn actual_fee_paid(
+    url: &(impl RpcTransport + ?Sized),
+    metadata: &RuntimeMetadataV14,
+    block_hash: &str,
+    extrinsic_index: u32,
+) -> anyhow::Result<ActualFee> {
+    let block = rpc(url, "chain_getBlock", [block_hash]).await?;
+    let extrinsic_hex = block["block"]["extrinsics"][extrinsic_index as usize]
+        .as_str()
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "block {} has no extrinsic at index {}",
+                block_hash,
+                extrinsic_index
+            )
+        })?;
+    let extrinsic = hex::decode(extrinsic_hex.trim_start_matches("0x"))?;
+    let parent_hash = block["block"]["header"]["parentHash"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("block {} has no parent hash", block_hash))?;
+
+    // Estimate the fee against the state that the extrinsic was applied on top of:
+    let estimate = query_fees(url, &extrinsic, Some(parent_hash)).await?;
+
+    let events = get_events(url, metadata, Some(block_hash)).await?;
+    let events: Vec<_> = events
+        .iter()
+        .filter(|e| e.is_from_extrinsic(extrinsic_index))
+        .collect();
+
+    let dispatch_info = events
+        .iter()
+        .find_map(|e| {
+            if e.is("System", "ExtrinsicSuccess") {
+                e.field("dispatch_info", 0)
+            } else if e.is("System", "ExtrinsicFailed") {
+                e.field("dispatch_info", 1)
+            } else {
+                None
+            }
+        })
+        .ok_or_else(|| anyhow::anyhow!("no ExtrinsicSuccess or ExtrinsicFailed event found"))?;
+    let actual_weight = Weight::from_json(&dispatch_info["weight"])?;
+    let pays_fee = dispatch_info["pays_fee"] != "No";
+
+    if let Some(event) = events
+        .iter()
+        .find(|e| e.is("TransactionPayment", "TransactionFeePaid"))
+    {
+        let field = |name, position| {
+            event
+                .field(name, position)
+                .ok_or_else(|| anyhow::anyhow!("TransactionFeePaid has no '{}' field", name))
+        };
+        // The "actual_fee" in this event includes the tip:
+        let total = u128_from_json(field("actual_fee", 1)?)?;
+        let tip = u128_from_json(field("tip", 2)?)?;
+        return Ok(ActualFee {
+            estimate,
+            actual_weight,
+            pays_fee,
+            partial_fee: total.saturating_sub(tip),
+            tip,
+            from_event: true,
+        });
+    }
+
+    let tip = match decode_signed_details(&extrinsic) {
+        ExtrinsicSigner::Signed(details) => details.tip,
+        ExtrinsicSigner::Unsigned => anyhow::bail!("extrinsic {} is not signed", extrinsic_index),
+        ExtrinsicSigner::Undecodable(reason) => {
+            anyhow::bail!("can't decode extrinsic {}: {}", extrinsic_index, reason)
+        }
+    };
+    let partial_fee = match (&estimate.inclusion_fee, pays_fee) {
+        (Some(fee), true) => {
+            // Scale the weight fee by how much cheaper the actual weight is than the estimated
+            // one. If the runtime can tell us what each weight costs, we use that (since it
+            // knows how the two dimensions are combined); otherwise we go by ref time alone.
+            let (actual, estimated) = match (
+                weight_to_fee(url, actual_weight, Some(parent_hash)).await,
+                weight_to_fee(url, estimate.weight, Some(parent_hash)).await,
+            ) {
+                (Ok(actual), Ok(estimated)) => (actual, estimated),
+                _ => (
+                    actual_weight.ref_time as u128,
+                    estimate.weight.ref_time as u128,
+                ),
+            };
+            let weight_fee = (fee.adjusted_weight_fee * actual)
+                .checked_div(estimated)
+                .unwrap_or(fee.adjusted_weight_fee);
+            fee.base_fee + fee.len_fee + weight_fee
+        }
+        _ => 0,
+    };
+
+    Ok(ActualFee {
+        estimate,
+        actual_weight,
+        pays_fee,
+        partial_fee,
+        tip,
+        from_event: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_legacy_dispatch_info() {
+        // A transfer on a runtime with `u64` weights:
+        let bytes = hex::decode("80fdad0b0000000000001cd5a1030000000000000000000000").unwrap();
+        let (weight, class, partial_fee) = decode_dispatch_info(&bytes).unwrap();
+        assert_eq!(weight, Weight::new(195_952_000, 0));
+        assert_eq!(class, "Normal");
+        assert_eq!(partial_fee, 15_600_000_000);
+    }
+
+    #[test]
+    fn decodes_two_dimensional_dispatch_info() {
+        // A transfer on a runtime with `{ ref_time, proof_size }` weights, which is only
+        // 23 bytes long:
+        let bytes = hex::decode("62bfc72225380041e44f09000000000000000000000000").unwrap();
+        let (weight, class, partial_fee) = decode_dispatch_info(&bytes).unwrap();
+        assert_eq!(weight, Weight::new(145_879_000, 3593));
+        assert_eq!(class, "Normal");
+        assert_eq!(partial_fee, 156_230_721);
+
+        // The smallest possible weight takes just 2 bytes:
+        let bytes = hex::decode("00000100000000000000000000000000000000").unwrap();
+        let (weight, class, partial_fee) = decode_dispatch_info(&bytes).unwrap();
+        assert_eq!(weight, Weight::new(0, 0));
+        assert_eq!(class, "Operational");
+        assert_eq!(partial_fee, 0);
+    }
+
+    #[test]
+    fn rejects_truncated_dispatch_info() {
+        let bytes = hex::decode("000000000000000000000000000000000000").unwrap();
+        assert!(decode_dispatch_info(&bytes).is_err());
+    }
+}
@@ -1,5 +1,58 @@
+mod account;
+mod balances;
+mod batch;
+mod codegen;
+mod constants;
+mod decode;
+mod dry_run;
+mod encode;
+mod events;
+mod extrinsic;
+mod fee_history;
+mod fees;
+mod metadata;
+mod metadata_cache;
+mod metadata_diff;
+mod metadata_docs;
+mod mock_node;
+mod multisig;
+mod nonce;
+pub mod polkadot;
+mod proxy;
+mod recorder;
 mod rpc_to_localhost;
+mod ss58;
+mod storage;
+mod tip_advisor;
+mod token;
+mod weight;
 mod ws_client;
 
+pub use account::*;
+pub use balances::*;
+pub use batch::*;
+pub use codegen::*;
+pub use constants::*;
+pub use decode::*;
+pub use dry_run::*;
+pub use encode::*;
+pub use events::*;
+pub use extrinsic::*;
+pub use fee_history::*;
+pub use fees::*;
+pub use metadata::*;
+pub use metadata_cache::*;
+pub use metadata_diff::*;
+pub use metadata_docs::*;
+pub use mock_node::*;
+pub use multisig::*;
+pub use nonce::*;
+pub use proxy::*;
+pub use recorder::*;
 pub use rpc_to_localhost::*;
+pub use ss58::*;
+pub use storage::*;
+pub use tip_advisor::*;
+pub use token::*;
+pub use weight::*;
 pub use ws_client::*;
@@ -0,0 +1,219 @@
+use frame_metadata::{
+    PalletMetadata, RuntimeMetadata, RuntimeMetadataPrefixed, RuntimeMetadataV14,
+    StorageEntryMetadata,
+};
+use parity_scale_codec::Decode;
+use scale_info::{form::PortableForm, PortableRegistry, TypeDef, TypeDefPrimitive, Variant};
+use std::path::Path;
+
+use crate::{rpc, RpcTransport};
+
+/// Fetch and decode the metadata from the node (see example 03).
+pub async fn get_metadata(
+    url: &(impl RpcTransport + ?Sized),
+) -> anyhow::Result<RuntimeMetadataV14> {
+    get_metadata_at(url, None).await
+}
+
+/// Fetch and decode the metadata as of some block (or the latest block). Runtime upgrades
+/// change the metadata, so to decode state from an older block, we need its metadata.
+pub async fn get_metadata_at(
+    url: &(impl RpcTransport + ?Sized),
+    at: Option<&str>,
+) -> anyhow::Result<RuntimeMetadataV14> {
+    let metadata_json = rpc(url, "state_getMetadata", (at,)).await?;
+    let metadata_hex = metadata_json
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("state_getMetadata did not return a string"))?;
+    let metadata_bytes = hex::decode(metadata_hex.trim_start_matches("0x"))?;
+    decode_metadata(&metadata_bytes)
+}
+
+/// Decode SCALE encoded metadata bytes. Only V14 metadata is supported, since
+/// that's the first version which describes the types in use.
+pub fn decode_metadata(mut bytes: &[u8]) -> anyhow::Result<RuntimeMetadataV14> {
+    let prefixed = RuntimeMetadataPrefixed::decode(&mut bytes)?;
+    match prefixed.1 {
+        RuntimeMetadata::V14(metadata) => Ok(metadata),
+        _ => anyhow::bail!("only V14 metadata is supported"),
+    }
+}
+
+/// Load metadata that was saved to a file, either as the hex string that "state_getMetadata"
+/// hands back (with or without JSON quotes around it) or as raw SCALE encoded bytes.
+pub fn load_metadata(path: impl AsRef<Path>) -> anyhow::Result<RuntimeMetadataV14> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path)
+        .map_err(|e| anyhow::anyhow!("cannot read metadata from {}: {}", path.display(), e))?;
+
+    let text = std::str::from_utf8(&bytes).unwrap_or_default().trim();
+    let text = text.trim_matches('"');
+    let bytes = match text.strip_prefix("0x") {
+        Some(hex_str) => hex::decode(hex_str)?,
+        None => bytes,
+    };
+    decode_metadata(&bytes)
+        .map_err(|e| anyhow::anyhow!("cannot decode metadata from {}: {}", path.display(), e))
+}
+
+/// Find a pallet in the metadata by name.
+pub fn pallet<'a>(
+    metadata: &'a RuntimeMetadataV14,
+    pallet_name: &str,
+) -> anyhow::Result<&'a PalletMetadata<PortableForm>> {
+    metadata
+        .pallets
+        .iter()
+        .find(|p| p.name == pallet_name)
+        .ok_or_else(|| anyhow::anyhow!("pallet '{}' not found in the metadata", pallet_name))
+}
+
+/// Look up the pallet index and call index for some call. In example 05 we hard coded
+/// these (5 and 0 for `Balances.transfer`), but they can differ between chains and
+/// between runtime versions, so it's much safer to find them in the metadata.
+pub fn call_index(
+    metadata: &RuntimeMetadataV14,
+    pallet_name: &str,
+    call_name: &str,
+) -> anyhow::Result<(u8, u8)> {
+    let pallet = pallet(metadata, pallet_name)?;
+    let calls = pallet
+        .calls
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("pallet '{}' has no calls", pallet_name))?;
+    let call = variant_by_name(&metadata.types, calls.ty.id(), call_name).ok_or_else(|| {
+        anyhow::anyhow!("call '{}' not found in pallet '{}'", call_name, pallet_name)
+    })?;
+    Ok((pallet.index, call.index()))
+}
+
+/// Find a storage entry in the metadata, given the pallet name and the entry name.
+pub fn storage_entry<'a>(
+    metadata: &'a RuntimeMetadataV14,
+    pallet_name: &str,
+    entry_name: &str,
+) -> anyhow::Result<&'a StorageEntryMetadata<PortableForm>> {
+    pallet(metadata, pallet_name)?
+        .storage
+        .as_ref()
+        .and_then(|storage| storage.entries.iter().find(|e| e.name == entry_name))
+        .ok_or_else(|| anyhow::anyhow!("storage entry '{}.{}' not found", pallet_name, entry_name))
+}
+
+/// The pallet calls, events and errors are each described by an enum type in the
+/// metadata; this finds a variant in such a type by its name.
+pub fn variant_by_name<'a>(
+    types: &'a PortableRegistry,
+    type_id: u32,
+    name: &str,
+) -> Option<&'a Variant<PortableForm>> {
+    variants(types, type_id)?.iter().find(|v| v.name() == name)
+}
+
+/// Find a variant in an enum type by its index.
+pub fn variant_by_index(
+    types: &PortableRegistry,
+    type_id: u32,
+    index: u8,
+) -> Option<&Variant<PortableForm>> {
+    variants(types, type_id)?
+        .iter()
+        .find(|v| v.index() == index)
+}
+
+/// The variants of an enum type, or `None` if the type isn't an enum.
+pub fn variants(types: &PortableRegistry, type_id: u32) -> Option<&[Variant<PortableForm>]> {
+    match types.resolve(type_id)?.type_def() {
+        TypeDef::Variant(v) => Some(v.variants()),
+        _ => None,
+    }
+}
+
+/// Find the ID of a type in the metadata by its full path, for instance
+/// `["sp_runtime", "DispatchError"]`.
+pub fn type_id_by_path(types: &PortableRegistry, path: &[&str]) -> Option<u32> {
+    types
+        .types()
+        .iter()
+        .find(|t| {
+            t.ty()
+                .path()
+                .segments()
+                .iter()
+                .map(|s| s.as_str())
+                .eq(path.iter().copied())
+        })
+        .map(|t| t.id())
+}
+
+/// A readable name for a type, like `Vec<u8>`, `MultiAddress<AccountId32, ()>` or
+/// `[u8; 32]`. Structs and enums are named by the last part of their path, which isn't
+/// always unique, but is what we'd see in the runtime's source code.
+pub fn type_name(types: &PortableRegistry, type_id: u32) -> String {
+    let ty = match types.resolve(type_id) {
+        Some(ty) => ty,
+        None => return format!("<unknown type {}>", type_id),
+    };
+
+    match ty.type_def() {
+        TypeDef::Composite(_) | TypeDef::Variant(_) if !ty.path().is_empty() => {
+            let name = ty.path().ident().unwrap_or_default();
+            let params: Vec<String> = ty
+                .type_params()
+                .iter()
+                .filter_map(|param| param.ty())
+                .map(|param| type_name(types, param.id()))
+                .collect();
+            if params.is_empty() {
+                name
+            } else {
+                format!("{}<{}>", name, params.join(", "))
+            }
+        }
+        TypeDef::Composite(composite) => {
+            let fields: Vec<String> = composite
+                .fields()
+                .iter()
+                .map(|field| type_name(types, field.ty().id()))
+                .collect();
+            format!("({})", fields.join(", "))
+        }
+        TypeDef::Variant(_) => "<anonymous enum>".to_string(),
+        TypeDef::Sequence(seq) => format!("Vec<{}>", type_name(types, seq.type_param().id())),
+        TypeDef::Array(arr) => format!(
+            "[{}; {}]",
+            type_name(types, arr.type_param().id()),
+            arr.len()
+        ),
+        TypeDef::Tuple(tuple) => {
+            let fields: Vec<String> = tuple
+                .fields()
+                .iter()
+                .map(|field| type_name(types, field.id()))
+                .collect();
+            format!("({})", fields.join(", "))
+        }
+        TypeDef::Primitive(prim) => match prim {
+            TypeDefPrimitive::Bool => "bool",
+            TypeDefPrimitive::Char => "char",
+            TypeDefPrimitive::Str => "String",
+            TypeDefPrimitive::U8 => "u8",
+            TypeDefPrimitive::U16 => "u16",
+            TypeDefPrimitive::U32 => "u32",
+            TypeDefPrimitive::U64 => "u64",
+            TypeDefPrimitive::U128 => "u128",
+            TypeDefPrimitive::U256 => "U256",
+            TypeDefPrimitive::I8 => "i8",
+            TypeDefPrimitive::I16 => "i16",
+            TypeDefPrimitive::I32 => "i32",
+            TypeDefPrimitive::I64 => "i64",
+            TypeDefPrimitive::I128 => "i128",
+            TypeDefPrimitive::I256 => "I256",
+        }
+        .to_string(),
+        TypeDef::Compact(compact) => {
+            format!("Compact<{}>", type_name(types, compact.type_param().id()))
+        }
+        TypeDef::BitSequence(_) => "BitVec".to_string(),
+    }
+}
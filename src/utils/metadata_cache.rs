@@ -0,0 +1,195 @@
+use frame_metadata::RuntimeMetadataV14;
+use jsonrpsee::{
+    async_client::Client,
+    core::client::{Subscription, SubscriptionClientT},
+    rpc_params,
+};
+use serde_json::Value;
+use sp_core::H256;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use tokio::task::JoinHandle;
+
+use crate::{decode_metadata, get_genesis_hash, rpc};
+
+/// The environment variable that says where [`MetadataCache::new`] should keep metadata.
+pub const METADATA_CACHE_DIR_VAR: &str = "POLKADOT_METADATA_CACHE";
+
+/// The metadata is several megabytes, but only changes when the runtime is upgraded, and
+/// every runtime upgrade bumps the runtime's `spec_version`. So rather than fetching it with
+/// "state_getMetadata" every time we need it, we can keep it on disk, keyed by the chain's
+/// genesis hash and the `spec_version`, and just ask the node which `spec_version` it's on
+/// (which is a much smaller response).
+///
+/// Decoded metadata is also kept in memory, for tools which ask for it more than once. If
+/// [`MetadataCache::watch_upgrades`] is used, the node tells us about runtime upgrades as they
+/// happen, and we don't need to ask for the `spec_version` at all.
+pub struct MetadataCache {
+    url: String,
+    dir: PathBuf,
+    state: Arc<Mutex<CacheState>>,
+    watcher: Mutex<Option<JoinHandle<()>>>,
+}
+
+#[derive(Default)]
+struct CacheState {
+    genesis_hash: Option<H256>,
+    /// The latest `spec_version`, when we're watching for runtime upgrades.
+    spec_version: Option<u32>,
+    loaded: HashMap<u32, Arc<RuntimeMetadataV14>>,
+}
+
+impl MetadataCache {
+    /// A cache for the chain that the node at `url` is running, which keeps metadata in the
+    /// directory named by the `POLKADOT_METADATA_CACHE` environment variable, or else in a
+    /// "polkadot-metadata" directory in the system's temporary directory.
+    pub fn new(url: impl Into<String>) -> Self {
+        let dir = match std::env::var_os(METADATA_CACHE_DIR_VAR) {
+            Some(dir) => PathBuf::from(dir),
+            None => std::env::temp_dir().join("polkadot-metadata"),
+        };
+        MetadataCache::with_dir(url, dir)
+    }
+
+    /// Like [`MetadataCache::new`], but keeping metadata in the directory given.
+    pub fn with_dir(url: impl Into<String>, dir: impl AsRef<Path>) -> Self {
+        MetadataCache {
+            url: url.into(),
+            dir: dir.as_ref().to_path_buf(),
+            state: Arc::new(Mutex::new(CacheState::default())),
+            watcher: Mutex::new(None),
+        }
+    }
+
+    /// The metadata for the latest block.
+    pub async fn metadata(&self) -> anyhow::Result<Arc<RuntimeMetadataV14>> {
+        // If we're watching for upgrades, we already know which runtime is the newest (and
+        // the latest block's metadata is for that runtime):
+        let watched = self.state.lock().unwrap().spec_version;
+        if let Some(spec_version) = watched {
+            return self.metadata_for(spec_version, None).await;
+        }
+
+        // Otherwise, we pin down the latest block first, and then ask for the runtime version
+        // and metadata as of that block, so that they match even if the runtime is upgraded
+        // in between:
+        let block_hash = rpc(&self.url, "chain_getBlockHash", ()).await?;
+        let block_hash = block_hash
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("chain_getBlockHash did not return a string"))?;
+        self.metadata_at(block_hash).await
+    }
+
+    /// The metadata as of some block. Like [`crate::get_metadata_at`], this is what we need to
+    /// decode state from before a runtime upgrade.
+    pub async fn metadata_at(&self, at: &str) -> anyhow::Result<Arc<RuntimeMetadataV14>> {
+        let runtime_version = rpc(&self.url, "state_getRuntimeVersion", [at]).await?;
+        let spec_version = runtime_version["specVersion"]
+            .as_u64()
+            .ok_or_else(|| anyhow::anyhow!("no specVersion in {}", runtime_version))?;
+        self.metadata_for(spec_version as u32, Some(at)).await
+    }
+
+    /// Subscribe to "state_subscribeRuntimeVersion", to find out about runtime upgrades as they
+    /// happen. From then on, [`MetadataCache::metadata`] hands back the metadata for the newest
+    /// runtime without checking which runtime the node is on first.
+    pub async fn watch_upgrades(&self, client: &Client) -> anyhow::Result<()> {
+        let mut sub: Subscription<Value> = client
+            .subscribe(
+                "state_subscribeRuntimeVersion",
+                rpc_params![],
+                "state_unsubscribeRuntimeVersion",
+            )
+            .await?;
+
+        let state = self.state.clone();
+        let watcher = tokio::spawn(async move {
+            while let Some(Ok(runtime_version)) = sub.next().await {
+                // The first notification is the current version, and then one comes
+                // with each upgrade:
+                if let Some(spec_version) = runtime_version["specVersion"].as_u64() {
+                    state.lock().unwrap().spec_version = Some(spec_version as u32);
+                }
+            }
+            // The subscription has ended, so we can no longer trust what we were told:
+            state.lock().unwrap().spec_version = None;
+        });
+
+        if let Some(old_watcher) = self.watcher.lock().unwrap().replace(watcher) {
+            old_watcher.abort();
+        }
+        Ok(())
+    }
+
+    /// Hand back the metadata for some `spec_version` from memory, or from disk, or else fetch
+    /// it (as of the block `at`, which should be running that `spec_version`) and save it.
+    async fn metadata_for(
+        &self,
+        spec_version: u32,
+        at: Option<&str>,
+    ) -> anyhow::Result<Arc<RuntimeMetadataV14>> {
+        if let Some(metadata) = self.state.lock().unwrap().loaded.get(&spec_version) {
+            return Ok(metadata.clone());
+        }
+
+        let path = self.path_for(spec_version).await?;
+        let metadata = match std::fs::read(&path)
+            .ok()
+            .and_then(|bytes| decode_metadata(&bytes).ok())
+        {
+            Some(metadata) => metadata,
+            None => {
+                let metadata_json = rpc(&self.url, "state_getMetadata", (at,)).await?;
+                let metadata_hex = metadata_json
+                    .as_str()
+                    .ok_or_else(|| anyhow::anyhow!("state_getMetadata did not return a string"))?;
+                let metadata_bytes = hex::decode(metadata_hex.trim_start_matches("0x"))?;
+                let metadata = decode_metadata(&metadata_bytes)?;
+
+                // Failing to save to the cache shouldn't stop us from using the metadata, so
+                // errors are ignored here (and we'll just fetch it again next time). Writing to
+                // a temporary file first means that nobody reads a half written file.
+                let tmp_path = path.with_extension("tmp");
+                let _ = std::fs::create_dir_all(&self.dir)
+                    .and_then(|_| std::fs::write(&tmp_path, &metadata_bytes))
+                    .and_then(|_| std::fs::rename(&tmp_path, &path));
+                metadata
+            }
+        };
+
+        let metadata = Arc::new(metadata);
+        self.state
+            .lock()
+            .unwrap()
+            .loaded
+            .insert(spec_version, metadata.clone());
+        Ok(metadata)
+    }
+
+    /// Where the metadata for some `spec_version` of this chain is kept on disk.
+    async fn path_for(&self, spec_version: u32) -> anyhow::Result<PathBuf> {
+        let known_genesis_hash = self.state.lock().unwrap().genesis_hash;
+        let genesis_hash = match known_genesis_hash {
+            Some(genesis_hash) => genesis_hash,
+            None => {
+                let genesis_hash = get_genesis_hash(&self.url).await?;
+                self.state.lock().unwrap().genesis_hash = Some(genesis_hash);
+                genesis_hash
+            }
+        };
+        Ok(self
+            .dir
+            .join(format!("{:x}-{}.scale", genesis_hash, spec_version)))
+    }
+}
+
+impl Drop for MetadataCache {
+    fn drop(&mut self) {
+        if let Some(watcher) = self.watcher.get_mut().unwrap().take() {
+            watcher.abort();
+        }
+    }
+}
@@ -0,0 +1,515 @@
+use frame_metadata::{
+    PalletMetadata, RuntimeMetadataV14, StorageEntryMetadata, StorageEntryType, StorageHasher,
+};
+use scale_info::{form::PortableForm, Field, PortableRegistry, TypeDef, Variant};
+use std::collections::HashSet;
+
+use crate::{type_name, variants};
+
+/// Something that differs between two versions of the metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataChange {
+    /// The pallet that changed, or `None` for changes to the extrinsic format.
+    pub pallet: Option<String>,
+    pub description: String,
+    /// Whether this breaks code which hardcodes indices or encodings, like the pallet and
+    /// call index and the argument encoding that example 05 uses to build a transfer.
+    pub breaking: bool,
+}
+
+/// Compare two versions of the metadata (say, from before and after a runtime upgrade),
+/// listing what was added, removed or changed. Types are compared by their shape (the
+/// fields, variants and primitives that make them up) rather than by their IDs, which
+/// differ between versions of the metadata even when nothing has changed.
+pub fn diff_metadata(old: &RuntimeMetadataV14, new: &RuntimeMetadataV14) -> Vec<MetadataChange> {
+    let mut diff = Diff {
+        old_types: &old.types,
+        new_types: &new.types,
+        changes: Vec::new(),
+        known_same: HashSet::new(),
+    };
+
+    for old_pallet in &old.pallets {
+        match new.pallets.iter().find(|p| p.name == old_pallet.name) {
+            Some(new_pallet) => diff.pallet(old_pallet, new_pallet),
+            None => {
+                // A pallet at the same index with a name that's new is most likely a rename:
+                let renamed_to = new.pallets.iter().find(|p| {
+                    p.index == old_pallet.index && !old.pallets.iter().any(|o| o.name == p.name)
+                });
+                match renamed_to {
+                    Some(new_pallet) => {
+                        diff.change(
+                            Some(&old_pallet.name),
+                            format!("pallet renamed to {}", new_pallet.name),
+                            false,
+                        );
+                        diff.pallet(old_pallet, new_pallet);
+                    }
+                    None => diff.change(
+                        Some(&old_pallet.name),
+                        format!("pallet removed (was at index {})", old_pallet.index),
+                        true,
+                    ),
+                }
+            }
+        }
+    }
+    for new_pallet in &new.pallets {
+        let existed = old.pallets.iter().any(|p| {
+            p.name == new_pallet.name
+                || (p.index == new_pallet.index && !new.pallets.iter().any(|n| n.name == p.name))
+        });
+        if !existed {
+            diff.change(
+                Some(&new_pallet.name),
+                format!("pallet added at index {}", new_pallet.index),
+                false,
+            );
+        }
+    }
+
+    diff.extrinsic(old, new);
+    diff.changes
+}
+
+struct Diff<'a> {
+    old_types: &'a PortableRegistry,
+    new_types: &'a PortableRegistry,
+    changes: Vec<MetadataChange>,
+    /// Pairs of old and new type IDs that we've already found to be the same. The same
+    /// types (like `AccountId32`) come up over and over, so this saves a lot of work.
+    known_same: HashSet<(u32, u32)>,
+}
+
+impl<'a> Diff<'a> {
+    fn change(&mut self, pallet: Option<&str>, description: String, breaking: bool) {
+        self.changes.push(MetadataChange {
+            pallet: pallet.map(|p| p.to_string()),
+            description,
+            breaking,
+        });
+    }
+
+    fn pallet(&mut self, old: &PalletMetadata<PortableForm>, new: &PalletMetadata<PortableForm>) {
+        let name = new.name.as_str();
+        if old.index != new.index {
+            self.change(
+                Some(name),
+                format!("pallet index changed from {} to {}", old.index, new.index),
+                true,
+            );
+        }
+
+        let old_calls = old.calls.as_ref().map(|c| c.ty.id());
+        let new_calls = new.calls.as_ref().map(|c| c.ty.id());
+        self.variants(name, "call", old_calls, new_calls);
+        let old_events = old.event.as_ref().map(|e| e.ty.id());
+        let new_events = new.event.as_ref().map(|e| e.ty.id());
+        self.variants(name, "event", old_events, new_events);
+        let old_errors = old.error.as_ref().map(|e| e.ty.id());
+        let new_errors = new.error.as_ref().map(|e| e.ty.id());
+        self.variants(name, "error", old_errors, new_errors);
+
+        let old_prefix = old.storage.as_ref().map(|s| s.prefix.as_str());
+        let new_prefix = new.storage.as_ref().map(|s| s.prefix.as_str());
+        if let (Some(old_prefix), Some(new_prefix)) = (old_prefix, new_prefix) {
+            if old_prefix != new_prefix {
+                self.change(
+                    Some(name),
+                    format!(
+                        "storage prefix changed from {} to {}",
+                        old_prefix, new_prefix
+                    ),
+                    true,
+                );
+            }
+        }
+        let no_entries = Vec::new();
+        let old_entries = old.storage.as_ref().map_or(&no_entries, |s| &s.entries);
+        let new_entries = new.storage.as_ref().map_or(&no_entries, |s| &s.entries);
+        for old_entry in old_entries {
+            match new_entries.iter().find(|e| e.name == old_entry.name) {
+                Some(new_entry) => self.storage_entry(name, old_entry, new_entry),
+                None => self.change(
+                    Some(name),
+                    format!("storage {} removed", old_entry.name),
+                    true,
+                ),
+            }
+        }
+        for new_entry in new_entries {
+            if !old_entries.iter().any(|e| e.name == new_entry.name) {
+                self.change(
+                    Some(name),
+                    format!("storage {} added", new_entry.name),
+                    false,
+                );
+            }
+        }
+
+        for old_constant in &old.constants {
+            match new.constants.iter().find(|c| c.name == old_constant.name) {
+                Some(new_constant) => {
+                    if !self.same_type(old_constant.ty.id(), new_constant.ty.id()) {
+                        self.change(
+                            Some(name),
+                            format!(
+                                "constant {} changed type from {} to {}",
+                                old_constant.name,
+                                type_name(self.old_types, old_constant.ty.id()),
+                                type_name(self.new_types, new_constant.ty.id())
+                            ),
+                            true,
+                        );
+                    }
+                }
+                None => self.change(
+                    Some(name),
+                    format!("constant {} removed", old_constant.name),
+                    true,
+                ),
+            }
+        }
+        for new_constant in &new.constants {
+            if !old.constants.iter().any(|c| c.name == new_constant.name) {
+                self.change(
+                    Some(name),
+                    format!("constant {} added", new_constant.name),
+                    false,
+                );
+            }
+        }
+    }
+
+    /// Compare the calls, events or errors of a pallet, which are the variants of an enum.
+    /// Moving one to another index breaks anything which hardcodes that index, and so does
+    /// changing the arguments, since they're encoded one after the other.
+    fn variants(&mut self, pallet: &str, kind: &str, old_ty: Option<u32>, new_ty: Option<u32>) {
+        let old_variants = old_ty
+            .and_then(|ty| variants(self.old_types, ty))
+            .unwrap_or_default();
+        let new_variants = new_ty
+            .and_then(|ty| variants(self.new_types, ty))
+            .unwrap_or_default();
+
+        for old_variant in old_variants {
+            let new_variant = match new_variants.iter().find(|v| v.name() == old_variant.name()) {
+                Some(new_variant) => new_variant,
+                None => {
+                    self.change(
+                        Some(pallet),
+                        format!(
+                            "{} {} removed (was at index {})",
+                            kind,
+                            old_variant.name(),
+                            old_variant.index()
+                        ),
+                        true,
+                    );
+                    continue;
+                }
+            };
+            if old_variant.index() != new_variant.index() {
+                self.change(
+                    Some(pallet),
+                    format!(
+                        "{} {} index changed from {} to {}",
+                        kind,
+                        new_variant.name(),
+                        old_variant.index(),
+                        new_variant.index()
+                    ),
+                    true,
+                );
+            }
+            self.fields(pallet, kind, old_variant, new_variant);
+        }
+        for new_variant in new_variants {
+            if !old_variants.iter().any(|v| v.name() == new_variant.name()) {
+                // Taking the index of something that was removed is covered by the removal:
+                self.change(
+                    Some(pallet),
+                    format!(
+                        "{} {} added at index {}",
+                        kind,
+                        new_variant.name(),
+                        new_variant.index()
+                    ),
+                    false,
+                );
+            }
+        }
+    }
+
+    fn fields(
+        &mut self,
+        pallet: &str,
+        kind: &str,
+        old: &Variant<PortableForm>,
+        new: &Variant<PortableForm>,
+    ) {
+        let describe = |types: &PortableRegistry, fields: &[Field<PortableForm>]| {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|field| match field.name() {
+                    Some(name) => format!("{}: {}", name, type_name(types, field.ty().id())),
+                    None => type_name(types, field.ty().id()),
+                })
+                .collect();
+            format!("({})", fields.join(", "))
+        };
+        let same_types = old.fields().len() == new.fields().len()
+            && old
+                .fields()
+                .iter()
+                .zip(new.fields())
+                .all(|(o, n)| self.same_type(o.ty().id(), n.ty().id()));
+        let same_names = old
+            .fields()
+            .iter()
+            .map(|f| f.name())
+            .eq(new.fields().iter().map(|f| f.name()));
+
+        if !same_types || !same_names {
+            self.change(
+                Some(pallet),
+                format!(
+                    "{} {} {} changed from {} to {}",
+                    kind,
+                    new.name(),
+                    if same_types {
+                        "argument names"
+                    } else {
+                        "arguments"
+                    },
+                    describe(self.old_types, old.fields()),
+                    describe(self.new_types, new.fields())
+                ),
+                !same_types,
+            );
+        }
+    }
+
+    fn storage_entry(
+        &mut self,
+        pallet: &str,
+        old: &StorageEntryMetadata<PortableForm>,
+        new: &StorageEntryMetadata<PortableForm>,
+    ) {
+        if old.modifier != new.modifier {
+            self.change(
+                Some(pallet),
+                format!(
+                    "storage {} changed from {:?} to {:?}",
+                    new.name, old.modifier, new.modifier
+                ),
+                false,
+            );
+        }
+
+        let describe = |types: &PortableRegistry, ty: &StorageEntryType<PortableForm>| match ty {
+            StorageEntryType::Plain(value) => type_name(types, value.id()),
+            StorageEntryType::Map {
+                hashers,
+                key,
+                value,
+            } => format!(
+                "map {} => {} hashed with {}",
+                type_name(types, key.id()),
+                type_name(types, value.id()),
+                hasher_names(hashers)
+            ),
+        };
+        let unchanged = match (&old.ty, &new.ty) {
+            (StorageEntryType::Plain(old_value), StorageEntryType::Plain(new_value)) => {
+                self.same_type(old_value.id(), new_value.id())
+            }
+            (
+                StorageEntryType::Map {
+                    hashers: old_hashers,
+                    key: old_key,
+                    value: old_value,
+                },
+                StorageEntryType::Map {
+                    hashers: new_hashers,
+                    key: new_key,
+                    value: new_value,
+                },
+            ) => {
+                hasher_names(old_hashers) == hasher_names(new_hashers)
+                    && self.same_type(old_key.id(), new_key.id())
+                    && self.same_type(old_value.id(), new_value.id())
+            }
+            _ => false,
+        };
+        if !unchanged {
+            self.change(
+                Some(pallet),
+                format!(
+                    "storage {} changed from {} to {}",
+                    new.name,
+                    describe(self.old_types, &old.ty),
+                    describe(self.new_types, &new.ty)
+                ),
+                true,
+            );
+        }
+    }
+
+    /// The extrinsic version and signed extensions decide how every extrinsic is encoded,
+    /// so any change to them is breaking.
+    fn extrinsic(&mut self, old: &RuntimeMetadataV14, new: &RuntimeMetadataV14) {
+        if old.extrinsic.version != new.extrinsic.version {
+            self.change(
+                None,
+                format!(
+                    "extrinsic version changed from {} to {}",
+                    old.extrinsic.version, new.extrinsic.version
+                ),
+                true,
+            );
+        }
+
+        let names = |metadata: &RuntimeMetadataV14| {
+            metadata
+                .extrinsic
+                .signed_extensions
+                .iter()
+                .map(|e| e.identifier.clone())
+                .collect::<Vec<_>>()
+        };
+        let (old_names, new_names) = (names(old), names(new));
+        if old_names != new_names {
+            self.change(
+                None,
+                format!(
+                    "signed extensions changed from [{}] to [{}]",
+                    old_names.join(", "),
+                    new_names.join(", ")
+                ),
+                true,
+            );
+            return;
+        }
+
+        let extensions = old
+            .extrinsic
+            .signed_extensions
+            .iter()
+            .zip(&new.extrinsic.signed_extensions);
+        for (old_ext, new_ext) in extensions {
+            let unchanged = self.same_type(old_ext.ty.id(), new_ext.ty.id())
+                && self.same_type(
+                    old_ext.additional_signed.id(),
+                    new_ext.additional_signed.id(),
+                );
+            if !unchanged {
+                self.change(
+                    None,
+                    format!("signed extension {} changed type", new_ext.identifier),
+                    true,
+                );
+            }
+        }
+    }
+
+    fn same_type(&mut self, old_id: u32, new_id: u32) -> bool {
+        let mut visited = HashSet::new();
+        let is_same = same_shape(
+            (self.old_types, old_id),
+            (self.new_types, new_id),
+            &self.known_same,
+            &mut visited,
+        );
+        // Everything that we looked at on the way is the same too:
+        if is_same {
+            self.known_same.extend(visited);
+        }
+        is_same
+    }
+}
+
+/// Whether two types (from different registries) are encoded in the same way. Types can
+/// refer to themselves (like `Call`, which can contain calls), so pairs of types that we've
+/// already visited are assumed to be the same; if they turn out not to be, that's found
+/// where they were first visited, and the whole comparison is false anyway.
+fn same_shape(
+    (old_types, old_id): (&PortableRegistry, u32),
+    (new_types, new_id): (&PortableRegistry, u32),
+    known_same: &HashSet<(u32, u32)>,
+    visited: &mut HashSet<(u32, u32)>,
+) -> bool {
+    if known_same.contains(&(old_id, new_id)) || !visited.insert((old_id, new_id)) {
+        return true;
+    }
+    let (old_ty, new_ty) = match (old_types.resolve(old_id), new_types.resolve(new_id)) {
+        (Some(old_ty), Some(new_ty)) => (old_ty, new_ty),
+        _ => return false,
+    };
+    let mut same = |old_id: u32, new_id: u32| {
+        same_shape(
+            (old_types, old_id),
+            (new_types, new_id),
+            known_same,
+            visited,
+        )
+    };
+
+    match (old_ty.type_def(), new_ty.type_def()) {
+        (TypeDef::Composite(old), TypeDef::Composite(new)) => {
+            same_fields(old.fields(), new.fields(), &mut same)
+        }
+        (TypeDef::Variant(old), TypeDef::Variant(new)) => {
+            old.variants().len() == new.variants().len()
+                && old.variants().iter().zip(new.variants()).all(|(o, n)| {
+                    o.name() == n.name()
+                        && o.index() == n.index()
+                        && same_fields(o.fields(), n.fields(), &mut same)
+                })
+        }
+        (TypeDef::Sequence(old), TypeDef::Sequence(new)) => {
+            same(old.type_param().id(), new.type_param().id())
+        }
+        (TypeDef::Array(old), TypeDef::Array(new)) => {
+            old.len() == new.len() && same(old.type_param().id(), new.type_param().id())
+        }
+        (TypeDef::Tuple(old), TypeDef::Tuple(new)) => {
+            old.fields().len() == new.fields().len()
+                && old
+                    .fields()
+                    .iter()
+                    .zip(new.fields())
+                    .all(|(o, n)| same(o.id(), n.id()))
+        }
+        (TypeDef::Primitive(old), TypeDef::Primitive(new)) => old == new,
+        (TypeDef::Compact(old), TypeDef::Compact(new)) => {
+            same(old.type_param().id(), new.type_param().id())
+        }
+        (TypeDef::BitSequence(old), TypeDef::BitSequence(new)) => {
+            same(old.bit_store_type().id(), new.bit_store_type().id())
+                && same(old.bit_order_type().id(), new.bit_order_type().id())
+        }
+        _ => false,
+    }
+}
+
+fn same_fields(
+    old: &[Field<PortableForm>],
+    new: &[Field<PortableForm>],
+    same: &mut impl FnMut(u32, u32) -> bool,
+) -> bool {
+    old.len() == new.len()
+        && old
+            .iter()
+            .zip(new)
+            .all(|(o, n)| o.name() == n.name() && same(o.ty().id(), n.ty().id()))
+}
+
+fn hasher_names(hashers: &[StorageHasher]) -> String {
+    hashers
+        .iter()
+        .map(|h| format!("{:?}", h))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
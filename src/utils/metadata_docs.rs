@@ -0,0 +1,378 @@
+use frame_metadata::{
+    PalletMetadata, RuntimeMetadataV14, StorageEntryMetadata, StorageEntryModifier,
+    StorageEntryType,
+};
+use scale_info::{form::PortableForm, Field, PortableRegistry, Variant};
+
+use crate::{decode_value, storage_key_types, storage_prefix_key, type_name, variants};
+
+/// The formats that [`metadata_docs`] can render the metadata's docs in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocsFormat {
+    Markdown,
+    /// A standalone HTML page, which needs nothing else to view it in a browser.
+    Html,
+}
+
+/// Render the docs in the metadata, for people who'd like to know what a chain supports without
+/// reading the runtime's source code. Each pallet gets a section listing its calls (with their
+/// signatures and call indices), events, errors, storage entries and constants, along with the
+/// doc comments that the runtime has on each of them. Types are named as in [`type_name`].
+pub fn metadata_docs(metadata: &RuntimeMetadataV14, format: DocsFormat) -> String {
+    let mut blocks = vec![Block::Heading(1, "Runtime metadata".to_string())];
+    let signed_extensions: Vec<String> = metadata
+        .extrinsic
+        .signed_extensions
+        .iter()
+        .map(|e| format!("`{}`", e.identifier))
+        .collect();
+    blocks.push(Block::Text(match signed_extensions.len() {
+        0 => format!(
+            "Extrinsic version `{}`, with no signed extensions.",
+            metadata.extrinsic.version
+        ),
+        _ => format!(
+            "Extrinsic version `{}`, with the signed extensions {}.",
+            metadata.extrinsic.version,
+            signed_extensions.join(", ")
+        ),
+    }));
+    blocks.push(Block::Contents(
+        metadata
+            .pallets
+            .iter()
+            .map(|p| (p.name.clone(), format!("{} (index {})", p.name, p.index)))
+            .collect(),
+    ));
+
+    for pallet in &metadata.pallets {
+        pallet_blocks(metadata, pallet, &mut blocks);
+    }
+
+    match format {
+        DocsFormat::Markdown => render_markdown(&blocks),
+        DocsFormat::Html => render_html(&blocks),
+    }
+}
+
+/// The docs are first laid out as blocks, which are then rendered in the format asked for.
+/// Text can contain `inline code` in either format, but is otherwise plain.
+enum Block {
+    Heading(usize, String),
+    /// Links to each pallet's section, as the pallet name and the text to show.
+    Contents(Vec<(String, String)>),
+    Signature(String),
+    Text(String),
+    /// Doc comments from the runtime, one string per line (which are Markdown).
+    Docs(Vec<String>),
+}
+
+fn pallet_blocks(
+    metadata: &RuntimeMetadataV14,
+    pallet: &PalletMetadata<PortableForm>,
+    blocks: &mut Vec<Block>,
+) {
+    let types = &metadata.types;
+    blocks.push(Block::Heading(2, pallet.name.clone()));
+    blocks.push(Block::Text(format!("Pallet index `{}`.", pallet.index)));
+
+    let kinds = [
+        ("Calls", "Call", pallet.calls.as_ref().map(|c| c.ty.id())),
+        ("Events", "Event", pallet.event.as_ref().map(|e| e.ty.id())),
+        ("Errors", "Error", pallet.error.as_ref().map(|e| e.ty.id())),
+    ];
+    for &(heading, kind, ty) in &kinds {
+        let variants = match ty.and_then(|ty| variants(types, ty)) {
+            Some(variants) if !variants.is_empty() => variants,
+            _ => continue,
+        };
+        blocks.push(Block::Heading(3, heading.to_string()));
+        for variant in variants {
+            variant_blocks(types, pallet.index, kind, variant, blocks);
+        }
+    }
+
+    if let Some(storage) = &pallet.storage {
+        if !storage.entries.is_empty() {
+            blocks.push(Block::Heading(3, "Storage".to_string()));
+        }
+        for entry in &storage.entries {
+            storage_blocks(metadata, &storage.prefix, entry, blocks);
+        }
+    }
+
+    if !pallet.constants.is_empty() {
+        blocks.push(Block::Heading(3, "Constants".to_string()));
+    }
+    for constant in &pallet.constants {
+        blocks.push(Block::Heading(4, constant.name.clone()));
+        blocks.push(Block::Signature(format!(
+            "{}: {}",
+            constant.name,
+            type_name(types, constant.ty.id())
+        )));
+        blocks.push(Block::Text(format!(
+            "Value: `{}`",
+            decoded_or_hex(types, constant.ty.id(), &constant.value)
+        )));
+        blocks.push(Block::Docs(constant.docs.clone()));
+    }
+}
+
+/// A call, event or error, with a signature like `transfer(dest: AccountId32, value: u128)`,
+/// and the two bytes that it's encoded as starting with (see example 05).
+fn variant_blocks(
+    types: &PortableRegistry,
+    pallet_index: u8,
+    kind: &str,
+    variant: &Variant<PortableForm>,
+    blocks: &mut Vec<Block>,
+) {
+    blocks.push(Block::Heading(4, variant.name().clone()));
+    let signature = if variant.fields().is_empty() {
+        variant.name().clone()
+    } else {
+        format!("{}({})", variant.name(), fields(types, variant.fields()))
+    };
+    blocks.push(Block::Signature(signature));
+    blocks.push(Block::Text(format!(
+        "{} index `{}` (encoded starting with `0x{:02x}{:02x}`).",
+        kind,
+        variant.index(),
+        pallet_index,
+        variant.index()
+    )));
+    blocks.push(Block::Docs(variant.docs().to_vec()));
+}
+
+fn fields(types: &PortableRegistry, fields: &[Field<PortableForm>]) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            let ty = type_name(types, field.ty().id());
+            match field.name() {
+                Some(name) => format!("{}: {}", name, ty),
+                None => ty,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A storage entry, with a signature like
+/// `Account: map Blake2_128Concat(AccountId32) => AccountInfo<u32, AccountData<u128>>`.
+fn storage_blocks(
+    metadata: &RuntimeMetadataV14,
+    prefix: &str,
+    entry: &StorageEntryMetadata<PortableForm>,
+    blocks: &mut Vec<Block>,
+) {
+    let types = &metadata.types;
+    blocks.push(Block::Heading(4, entry.name.clone()));
+    let (signature, value_ty) = match &entry.ty {
+        StorageEntryType::Plain(value) => (
+            format!("{}: {}", entry.name, type_name(types, value.id())),
+            value.id(),
+        ),
+        StorageEntryType::Map {
+            hashers,
+            key,
+            value,
+        } => {
+            let key_types = storage_key_types(metadata, entry).unwrap_or_else(|_| vec![key.id()]);
+            let keys: Vec<String> = hashers
+                .iter()
+                .zip(key_types)
+                .map(|(hasher, key_ty)| format!("{:?}({})", hasher, type_name(types, key_ty)))
+                .collect();
+            let signature = format!(
+                "{}: map {} => {}",
+                entry.name,
+                keys.join(", "),
+                type_name(types, value.id())
+            );
+            (signature, value.id())
+        }
+    };
+    blocks.push(Block::Signature(signature));
+
+    let mut text = format!(
+        "Storage key prefix `0x{}`. ",
+        hex::encode(storage_prefix_key(prefix, &entry.name))
+    );
+    match entry.modifier {
+        StorageEntryModifier::Default => text.push_str(&format!(
+            "When nothing is stored, the value is `{}`.",
+            decoded_or_hex(types, value_ty, &entry.default)
+        )),
+        StorageEntryModifier::Optional => {
+            text.push_str("There is no default value when nothing is stored.")
+        }
+    }
+    blocks.push(Block::Text(text));
+    blocks.push(Block::Docs(entry.docs.clone()));
+}
+
+/// A value as the JSON that [`decode_value`] hands back, or as hex if it can't be decoded.
+fn decoded_or_hex(types: &PortableRegistry, type_id: u32, bytes: &[u8]) -> String {
+    match decode_value(types, type_id, &mut &*bytes) {
+        Ok(value) => value.to_string(),
+        Err(_) => format!("0x{}", hex::encode(bytes)),
+    }
+}
+
+/// The lines of some doc comments, without the space that follows each `///`.
+fn doc_lines(docs: &[String]) -> impl Iterator<Item = &str> {
+    docs.iter()
+        .flat_map(|doc| doc.split('\n'))
+        .map(|line| line.strip_prefix(' ').unwrap_or(line))
+}
+
+fn render_markdown(blocks: &[Block]) -> String {
+    let mut out = String::new();
+    for block in blocks {
+        match block {
+            Block::Heading(level, text) => {
+                out.push_str(&format!("{} {}\n\n", "#".repeat(*level), text))
+            }
+            Block::Contents(pallets) => {
+                for (name, text) in pallets {
+                    out.push_str(&format!("- [{}](#{})\n", text, name.to_lowercase()));
+                }
+                out.push('\n');
+            }
+            Block::Signature(signature) => {
+                out.push_str(&format!("```text\n{}\n```\n\n", signature))
+            }
+            Block::Text(text) => out.push_str(&format!("{}\n\n", text)),
+            Block::Docs(docs) if docs.is_empty() => {}
+            Block::Docs(docs) => {
+                // The docs are Markdown already, but headings in them (like "# <weight>" in
+                // older runtimes) would get mixed up with ours, and things in angle brackets
+                // would be taken for HTML tags, so those are escaped outside of code.
+                let mut in_code_block = false;
+                for line in doc_lines(docs) {
+                    if line.trim_start().starts_with("```") {
+                        in_code_block = !in_code_block;
+                        out.push_str(line);
+                    } else if in_code_block {
+                        out.push_str(line);
+                    } else {
+                        if line.starts_with('#') {
+                            out.push('\\');
+                        }
+                        out.push_str(&outside_inline_code(line, |text| text.replace('<', "&lt;")));
+                    }
+                    out.push('\n');
+                }
+                out.push('\n');
+            }
+        }
+    }
+    out
+}
+
+fn render_html(blocks: &[Block]) -> String {
+    let mut out = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Runtime metadata</title>\n<style>\n\
+         body { font-family: sans-serif; max-width: 60em; margin: auto; padding: 1em; }\n\
+         pre { background: #f4f4f4; padding: 0.5em; overflow-x: auto; }\n\
+         h4 { margin-bottom: 0.3em; }\n\
+         </style>\n</head>\n<body>\n",
+    );
+    for block in blocks {
+        match block {
+            Block::Heading(2, text) => out.push_str(&format!(
+                "<h2 id=\"{}\">{}</h2>\n",
+                text.to_lowercase(),
+                escape_html(text)
+            )),
+            Block::Heading(level, text) => {
+                out.push_str(&format!("<h{0}>{1}</h{0}>\n", level, escape_html(text)))
+            }
+            Block::Contents(pallets) => {
+                out.push_str("<ul>\n");
+                for (name, text) in pallets {
+                    out.push_str(&format!(
+                        "<li><a href=\"#{}\">{}</a></li>\n",
+                        name.to_lowercase(),
+                        escape_html(text)
+                    ));
+                }
+                out.push_str("</ul>\n");
+            }
+            Block::Signature(signature) => out.push_str(&format!(
+                "<pre><code>{}</code></pre>\n",
+                escape_html(signature)
+            )),
+            Block::Text(text) => out.push_str(&format!("<p>{}</p>\n", inline_html(text))),
+            Block::Docs(docs) => {
+                // Just enough Markdown for doc comments: paragraphs, code blocks and inline code.
+                let mut in_code_block = false;
+                let mut paragraph = Vec::new();
+                for line in doc_lines(docs) {
+                    if line.trim_start().starts_with("```") {
+                        end_paragraph(&mut paragraph, &mut out);
+                        in_code_block = !in_code_block;
+                        out.push_str(if in_code_block {
+                            "<pre><code>"
+                        } else {
+                            "</code></pre>\n"
+                        });
+                    } else if in_code_block {
+                        out.push_str(&format!("{}\n", escape_html(line)));
+                    } else if line.trim().is_empty() {
+                        end_paragraph(&mut paragraph, &mut out);
+                    } else {
+                        paragraph.push(line);
+                    }
+                }
+                if in_code_block {
+                    out.push_str("</code></pre>\n");
+                }
+                end_paragraph(&mut paragraph, &mut out);
+            }
+        }
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn end_paragraph(paragraph: &mut Vec<&str>, out: &mut String) {
+    if !paragraph.is_empty() {
+        out.push_str(&format!("<p>{}</p>\n", inline_html(&paragraph.join("\n"))));
+        paragraph.clear();
+    }
+}
+
+/// Escape some text for HTML, turning `inline code` into `<code>` elements.
+fn inline_html(text: &str) -> String {
+    let mut html = String::new();
+    for (index, part) in text.split('`').enumerate() {
+        match index % 2 {
+            0 => html.push_str(&escape_html(part)),
+            _ => html.push_str(&format!("<code>{}</code>", escape_html(part))),
+        }
+    }
+    html
+}
+
+/// Apply `f` to the parts of a line of Markdown which aren't `inline code`.
+fn outside_inline_code(line: &str, f: impl Fn(&str) -> String) -> String {
+    line.split('`')
+        .enumerate()
+        .map(|(index, part)| match index % 2 {
+            0 => f(part),
+            _ => part.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("`")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,391 @@
+use frame_metadata::RuntimeMetadataPrefixed;
+use hyper::{
+    header::{HeaderValue, CONTENT_TYPE},
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server,
+};
+use parity_scale_codec::Encode;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use soketto::handshake;
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+
+use crate::load_metadata;
+
+/// A canned response to some RPC call. Fixture files hold a JSON array of these, like:
+///
+/// ```json
+/// [
+///     { "method": "chain_getBlockHash", "params": [0], "result": "0x91b1..." },
+///     { "method": "state_getMetadata", "result": "0x6d657461..." },
+///     { "method": "author_submitExtrinsic", "error": { "code": 1010, "message": "Invalid Transaction" } },
+///     {
+///         "method": "author_submitAndWatchExtrinsic",
+///         "notification_method": "author_extrinsicUpdate",
+///         "notifications": ["ready", { "inBlock": "0x1234..." }]
+///     }
+/// ]
+/// ```
+///
+/// Recordings made with [`crate::Recorder`] are in the same format.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fixture {
+    pub method: String,
+    /// Only answer calls made with these params. If they're an array, calls whose params
+    /// start with the same items match too, so `["0x26aa.."]` answers a storage query for that
+    /// key at any block. If not given, answer any call to the method. The first matching
+    /// fixture is used, so put more specific ones first.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+    /// The "result" to hand back.
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub result: Value,
+    /// If given, hand back this "error" instead of a result.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<Value>,
+    /// If given, treat the call as a subscription (which only works over WS); we hand back
+    /// a new subscription ID, and then send each of these results as a notification.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notifications: Vec<Value>,
+    /// The method name used in notifications (defaults to the name of the method called).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notification_method: Option<String>,
+}
+
+/// Calls without params are treated the same as calls with empty params.
+pub(crate) fn normalize_params(params: Option<&Value>) -> Value {
+    match params {
+        None | Some(Value::Null) => json!([]),
+        Some(params) => params.clone(),
+    }
+}
+
+/// Do the params given in a fixture match the params of a call? Arrays match if the call's
+/// params start with the fixture's; anything else has to be equal.
+fn params_match(fixture_params: &Value, params: &Value) -> bool {
+    match (fixture_params, params) {
+        (Value::Array(expected), Value::Array(actual)) => {
+            expected.len() <= actual.len() && expected.iter().zip(actual).all(|(e, a)| e == a)
+        }
+        _ => fixture_params == params,
+    }
+}
+
+/// Load fixtures from a JSON file (see [`Fixture`] for the format).
+pub fn load_fixtures(path: impl AsRef<Path>) -> anyhow::Result<Vec<Fixture>> {
+    let path = path.as_ref();
+    let file = std::fs::File::open(path)
+        .map_err(|e| anyhow::anyhow!("can't open fixtures '{}': {}", path.display(), e))?;
+    serde_json::from_reader(std::io::BufReader::new(file))
+        .map_err(|e| anyhow::anyhow!("invalid fixtures in '{}': {}", path.display(), e))
+}
+
+/// A fixture answering "state_getMetadata" with some metadata saved to a file (see
+/// [`crate::load_metadata`] for the formats accepted), so that it needn't be pasted into a
+/// fixtures file as one enormous hex string.
+pub fn metadata_fixture(path: impl AsRef<Path>) -> anyhow::Result<Fixture> {
+    let metadata = load_metadata(path)?;
+    let bytes = RuntimeMetadataPrefixed::from(metadata).encode();
+    Ok(Fixture {
+        method: "state_getMetadata".to_string(),
+        params: None,
+        result: json!(format!("0x{}", hex::encode(bytes))),
+        error: None,
+        notifications: Vec::new(),
+        notification_method: None,
+    })
+}
+
+/// A pretend node, serving canned responses over HTTP and WS, so that the examples and utils
+/// can be run without a real node. Handing [`MockNode::http_url`] and [`MockNode::ws_url`]
+/// to them in place of [`crate::LOCALHOST_RPC_URL`] and [`crate::LOCALHOST_WS_URL`] (or, for
+/// the examples, setting `POLKADOT_RPC_URL` and `POLKADOT_WS_URL`) is all it takes. The
+/// servers stop when this is dropped.
+pub struct MockNode {
+    pub http_url: String,
+    pub ws_url: String,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl MockNode {
+    /// Start a mock node listening on any free local ports.
+    pub async fn start(fixtures: Vec<Fixture>) -> anyhow::Result<MockNode> {
+        MockNode::start_on(fixtures, any_port(), any_port()).await
+    }
+
+    /// Start a mock node listening on the given addresses.
+    pub async fn start_on(
+        fixtures: Vec<Fixture>,
+        http_addr: SocketAddr,
+        ws_addr: SocketAddr,
+    ) -> anyhow::Result<MockNode> {
+        MockNode::serve(Responder::new(fixtures, false), http_addr, ws_addr).await
+    }
+
+    /// Start a mock node replaying a recording (see [`crate::Recorder`]) on any free local
+    /// ports. Rather than always using the first fixture which matches a call, each matching
+    /// fixture is used in turn, so repeating a call (for instance, asking for the latest
+    /// block) gets the same series of responses as when it was recorded. Once they've all
+    /// been used, the last one is used again.
+    pub async fn replay(recording: Vec<Fixture>) -> anyhow::Result<MockNode> {
+        MockNode::replay_on(recording, any_port(), any_port()).await
+    }
+
+    /// Start a mock node replaying a recording on the given addresses.
+    pub async fn replay_on(
+        recording: Vec<Fixture>,
+        http_addr: SocketAddr,
+        ws_addr: SocketAddr,
+    ) -> anyhow::Result<MockNode> {
+        MockNode::serve(Responder::new(recording, true), http_addr, ws_addr).await
+    }
+
+    async fn serve(
+        responder: Responder,
+        http_addr: SocketAddr,
+        ws_addr: SocketAddr,
+    ) -> anyhow::Result<MockNode> {
+        let responder = Arc::new(responder);
+
+        let http_responder = responder.clone();
+        let make_service = make_service_fn(move |_conn| {
+            let responder = http_responder.clone();
+            let service = service_fn(move |req| serve_http(responder.clone(), req));
+            async move { Ok::<_, Infallible>(service) }
+        });
+        let http_server = Server::try_bind(&http_addr)?.serve(make_service);
+        let http_addr = http_server.local_addr();
+        let http_task = tokio::spawn(async move {
+            if let Err(e) = http_server.await {
+                eprintln!("Mock node HTTP server failed: {}", e);
+            }
+        });
+
+        let ws_listener = TcpListener::bind(ws_addr).await?;
+        let ws_addr = ws_listener.local_addr()?;
+        let ws_task = tokio::spawn(async move {
+            while let Ok((socket, _)) = ws_listener.accept().await {
+                let responder = responder.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = serve_ws(responder, socket).await {
+                        eprintln!("Mock node WS connection failed: {}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(MockNode {
+            http_url: format!("http://{}", http_addr),
+            ws_url: format!("ws://{}", ws_addr),
+            tasks: vec![http_task, ws_task],
+        })
+    }
+}
+
+impl Drop for MockNode {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+fn any_port() -> SocketAddr {
+    ([127, 0, 0, 1], 0).into()
+}
+
+/// Works out what to say in response to each JSON-RPC request, given the fixtures.
+struct Responder {
+    fixtures: Vec<Fixture>,
+    next_subscription_id: AtomicU64,
+    /// When replaying, which fixtures have been used already.
+    used: Option<Mutex<Vec<bool>>>,
+}
+
+impl Responder {
+    fn new(fixtures: Vec<Fixture>, replay: bool) -> Responder {
+        let used = if replay {
+            Some(Mutex::new(vec![false; fixtures.len()]))
+        } else {
+            None
+        };
+        Responder {
+            fixtures,
+            next_subscription_id: AtomicU64::new(1),
+            used,
+        }
+    }
+
+    fn find_fixture(&self, method: &str, params: &Value) -> Option<&Fixture> {
+        let matches = |fixture: &Fixture| {
+            fixture.method == method
+                && match &fixture.params {
+                    Some(fixture_params) => params_match(fixture_params, params),
+                    None => true,
+                }
+        };
+        let used = match &self.used {
+            Some(used) => used,
+            None => return self.fixtures.iter().find(|f| matches(f)),
+        };
+
+        let mut used = used.lock().unwrap();
+        let next = (0..self.fixtures.len()).find(|&i| !used[i] && matches(&self.fixtures[i]));
+        match next {
+            Some(i) => {
+                used[i] = true;
+                Some(&self.fixtures[i])
+            }
+            None => self.fixtures.iter().rev().find(|f| matches(f)),
+        }
+    }
+
+    /// Respond to a request (or a batch of them), handing back the response along with any
+    /// subscription notifications to send after it.
+    fn respond(&self, request: &Value, allow_subscriptions: bool) -> (Value, Vec<Value>) {
+        match request {
+            Value::Array(calls) => {
+                let mut responses = Vec::new();
+                let mut notifications = Vec::new();
+                for call in calls {
+                    let (response, mut notifs) = self.respond_to_call(call, allow_subscriptions);
+                    responses.push(response);
+                    notifications.append(&mut notifs);
+                }
+                (Value::Array(responses), notifications)
+            }
+            call => self.respond_to_call(call, allow_subscriptions),
+        }
+    }
+
+    fn respond_to_call(&self, call: &Value, allow_subscriptions: bool) -> (Value, Vec<Value>) {
+        let id = call["id"].clone();
+        let method = match call["method"].as_str() {
+            Some(method) => method,
+            None => return (error_response(id, -32600, "Invalid request"), Vec::new()),
+        };
+        let params = normalize_params(call.get("params"));
+
+        match self.find_fixture(method, &params) {
+            Some(fixture) if !fixture.notifications.is_empty() => {
+                if !allow_subscriptions {
+                    let message = "Subscriptions are not available over HTTP";
+                    return (error_response(id, -32601, message), Vec::new());
+                }
+                let subscription = self
+                    .next_subscription_id
+                    .fetch_add(1, Ordering::Relaxed)
+                    .to_string();
+                let notification_method = fixture.notification_method.as_deref().unwrap_or(method);
+                let notifications = fixture
+                    .notifications
+                    .iter()
+                    .map(|result| {
+                        json!({
+                            "jsonrpc": "2.0",
+                            "method": notification_method,
+                            "params": { "subscription": subscription, "result": result }
+                        })
+                    })
+                    .collect();
+                (result_response(id, json!(subscription)), notifications)
+            }
+            Some(fixture) => match &fixture.error {
+                Some(error) => (
+                    json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+                    Vec::new(),
+                ),
+                None => (result_response(id, fixture.result.clone()), Vec::new()),
+            },
+            // Unless told otherwise, we support the methods we have fixtures for:
+            None if method == "rpc_methods" => {
+                let mut methods: Vec<&str> = self.fixtures.iter().map(|f| &*f.method).collect();
+                methods.push("rpc_methods");
+                methods.sort_unstable();
+                methods.dedup();
+                let result = json!({ "version": 1, "methods": methods });
+                (result_response(id, result), Vec::new())
+            }
+            // Unsubscribing always works, since our subscriptions end by themselves:
+            None if method.contains("unsubscribe") || method.contains("unwatch") => {
+                (result_response(id, json!(true)), Vec::new())
+            }
+            None => (error_response(id, -32601, "Method not found"), Vec::new()),
+        }
+    }
+}
+
+fn result_response(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn parse_error() -> Value {
+    error_response(Value::Null, -32700, "Parse error")
+}
+
+async fn serve_http(
+    responder: Arc<Responder>,
+    req: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let response = match hyper::body::to_bytes(req.into_body()).await {
+        Ok(body) => match serde_json::from_slice(&body) {
+            Ok(request) => responder.respond(&request, false).0,
+            Err(_) => parse_error(),
+        },
+        Err(_) => parse_error(),
+    };
+
+    let mut response = Response::new(Body::from(response.to_string()));
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    Ok(response)
+}
+
+async fn serve_ws(responder: Arc<Responder>, socket: TcpStream) -> anyhow::Result<()> {
+    let mut server = handshake::Server::new(socket.compat());
+    let key = server.receive_request().await?.key();
+    let accept = handshake::server::Response::Accept {
+        key,
+        protocol: None,
+    };
+    server.send_response(&accept).await?;
+    let (mut sender, mut receiver) = server.into_builder().finish();
+
+    let mut message = Vec::new();
+    loop {
+        message.clear();
+        match receiver.receive_data(&mut message).await {
+            Ok(_) => {}
+            Err(soketto::connection::Error::Closed) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        }
+
+        let (response, notifications) = match serde_json::from_slice(&message) {
+            Ok(request) => responder.respond(&request, true),
+            Err(_) => (parse_error(), Vec::new()),
+        };
+        sender.send_text(response.to_string()).await?;
+        for notification in notifications {
+            sender.send_text(notification.to_string()).await?;
+        }
+        sender.flush().await?;
+    }
+}
@@ -0,0 +1,306 @@
+use frame_metadata::{RuntimeMetadataV14, StorageEntryType};
+use parity_scale_codec::{Compact, Encode, Output};
+use sp_core::blake2_256;
+use sp_runtime::AccountId32;
+
+use crate::{
+    account_from_json, decode_value, get_storage, storage_entry, storage_map_key, storage_prefix,
+    u128_from_json, u32_from_json, EncodedCall, RpcTransport, Weight,
+};
+
+/// A point in the chain's history (a block number and extrinsic index in that block).
+/// The first approval of a multisig call is recorded at some timepoint, and subsequent
+/// approvals must provide it to prove that they are approving that same operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timepoint {
+    pub height: u32,
+    pub index: u32,
+}
+
+impl Encode for Timepoint {
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        (self.height, self.index).encode_to(dest)
+    }
+}
+
+/// A pending multisig operation, as stored in `Multisig.Multisigs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingMultisig {
+    /// When the first approval was made.
+    pub when: Timepoint,
+    /// The amount held from the depositor while the operation is pending.
+    pub deposit: u128,
+    /// The account which made the first approval, and paid the deposit.
+    pub depositor: AccountId32,
+    /// The signatories who have approved the operation so far.
+    pub approvals: Vec<AccountId32>,
+}
+
+/// Some signatories who, together, control a multisig account.
+///
+/// A multisig account doesn't have a private key; its account ID is derived from the
+/// signatories and the number of them (the threshold) who must approve an operation
+/// before it's dispatched. One signatory makes the first approval (paying a deposit while
+/// the operation is pending), and the others approve it after, the last approval causing
+/// the call to be dispatched from the multisig account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Multisig {
+    signatories: Vec<AccountId32>,
+    threshold: u16,
+}
+
+impl Multisig {
+    /// Describe a multisig account. The order of signatories doesn't matter.
+    pub fn new(mut signatories: Vec<AccountId32>, threshold: u16) -> anyhow::Result<Self> {
+        signatories.sort();
+        signatories.dedup();
+
+        if threshold < 2 {
+            anyhow::bail!(
+                "a multisig threshold must be at least 2 (use as_multi_threshold_1 otherwise)"
+            );
+        }
+        if threshold as usize > signatories.len() {
+            anyhow::bail!(
+                "threshold {} is larger than the number of signatories ({})",
+                threshold,
+                signatories.len()
+            );
+        }
+
+        Ok(Multisig {
+            signatories,
+            threshold,
+        })
+    }
+
+    /// The signatories, sorted (as the Multisig pallet expects them to be).
+    pub fn signatories(&self) -> &[AccountId32] {
+        &self.signatories
+    }
+
+    pub fn threshold(&self) -> u16 {
+        self.threshold
+    }
+
+    /// The account ID of the multisig account. This is taken from `multi_account_id` in
+    /// `substrate/frame/multisig/src/lib.rs`: a hash of some fixed bytes, the sorted
+    /// signatories and the threshold.
+    pub fn account_id(&self) -> AccountId32 {
+        let entropy = (b"modlpy/utilisuba", &self.signatories, self.threshold).encode();
+        AccountId32::from(blake2_256(&entropy))
+    }
+
+    /// Calls are made by one of the signatories, who passes the others as an argument.
+    pub fn other_signatories(&self, who: &AccountId32) -> anyhow::Result<Vec<AccountId32>> {
+        if !self.signatories.contains(who) {
+            anyhow::bail!("{} is not a signatory of this multisig", who);
+        }
+        Ok(self
+            .signatories
+            .iter()
+            .filter(|s| *s != who)
+            .cloned()
+            .collect())
+    }
+
+    /// Build a `Multisig.as_multi` call, to be signed and submitted by `who`. This carries the
+    /// whole call to be dispatched; if this is the final approval, the call is dispatched. The
+    /// timepoint must be `None` for the first approval and `Some` for subsequent ones.
+    pub fn as_multi(
+        &self,
+        metadata: &RuntimeMetadataV14,
+        who: &AccountId32,
+        timepoint: Option<Timepoint>,
+        call: &EncodedCall,
+        max_weight: Weight,
+    ) -> anyhow::Result<EncodedCall> {
+        let args = MultisigArgs {
+            threshold: self.threshold,
+            other_signatories: self.other_signatories(who)?,
+            timepoint,
+            call: Some(call),
+            call_hash: call_hash(call),
+            max_weight,
+        };
+        args.encode_call(metadata, "as_multi")
+    }
+
+    /// Build a `Multisig.approve_as_multi` call, to be signed and submitted by `who`. Only the
+    /// hash of the call is provided, so this can't be used for the final approval (the final
+    /// approver must use [`Multisig::as_multi`] so that the call can be dispatched).
+    pub fn approve_as_multi(
+        &self,
+        metadata: &RuntimeMetadataV14,
+        who: &AccountId32,
+        timepoint: Option<Timepoint>,
+        call_hash: [u8; 32],
+        max_weight: Weight,
+    ) -> anyhow::Result<EncodedCall> {
+        let args = MultisigArgs {
+            threshold: self.threshold,
+            other_signatories: self.other_signatories(who)?,
+            timepoint,
+            call: None,
+            call_hash,
+            max_weight,
+        };
+        args.encode_call(metadata, "approve_as_multi")
+    }
+
+    /// Look up the pending operation for some call hash in `Multisig.Multisigs`. `None` means
+    /// that nobody has approved the call yet.
+    pub async fn pending(
+        &self,
+        url: &(impl RpcTransport + ?Sized),
+        metadata: &RuntimeMetadataV14,
+        call_hash: [u8; 32],
+    ) -> anyhow::Result<Option<PendingMultisig>> {
+        // `Multisigs` is a double map from the multisig account and call hash
+        // to details about the pending operation:
+        let entry = storage_entry(metadata, "Multisig", "Multisigs")?;
+        let (hashers, value_ty) = match &entry.ty {
+            StorageEntryType::Map { hashers, value, .. } if hashers.len() == 2 => (hashers, value),
+            _ => anyhow::bail!("expected Multisig.Multisigs to be a double map"),
+        };
+
+        let account_id = self.account_id().encode();
+        let storage_key = storage_map_key(
+            storage_prefix(metadata, "Multisig")?,
+            "Multisigs",
+            &[(&hashers[0], &account_id), (&hashers[1], &call_hash)],
+        );
+
+        let bytes = match get_storage(url, &storage_key, None).await? {
+            Some(bytes) => bytes,
+            None => return Ok(None),
+        };
+
+        // Rather than relying on the exact type of balances and block numbers
+        // in this runtime, we decode the value using the metadata:
+        let value = decode_value(&metadata.types, value_ty.id(), &mut &*bytes)?;
+        let approvals = value["approvals"]
+            .as_array()
+            .ok_or_else(|| anyhow::anyhow!("approvals should be an array"))?
+            .iter()
+            .map(account_from_json)
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Some(PendingMultisig {
+            when: Timepoint {
+                height: u32_from_json(&value["when"]["height"])?,
+                index: u32_from_json(&value["when"]["index"])?,
+            },
+            deposit: u128_from_json(&value["deposit"])?,
+            depositor: account_from_json(&value["depositor"])?,
+            approvals,
+        }))
+    }
+}
+
+/// The hash of a call, used to identify a pending multisig operation.
+pub fn call_hash(call: &EncodedCall) -> [u8; 32] {
+    blake2_256(&call.0)
+}
+
+/// The arguments to the multisig calls, which we encode according to the metadata.
+struct MultisigArgs<'a> {
+    threshold: u16,
+    other_signatories: Vec<AccountId32>,
+    timepoint: Option<Timepoint>,
+    call: Option<&'a EncodedCall>,
+    call_hash: [u8; 32],
+    max_weight: Weight,
+}
+
+impl<'a> MultisigArgs<'a> {
+    /// The arguments taken by the multisig calls have changed a few times over different runtime
+    /// versions (the call used to be wrapped in `WrapperKeepOpaque`, there used to be a
+    /// `store_call` argument, and weights gained a second dimension), so rather than assuming any
+    /// one layout, we look at each argument that the metadata says the call takes and encode it.
+    fn encode_call(
+        &self,
+        metadata: &RuntimeMetadataV14,
+        call_name: &str,
+    ) -> anyhow::Result<EncodedCall> {
+        EncodedCall::from_fields(metadata, "Multisig", call_name, |field, bytes| {
+            let field_name = field.name().map(|n| n.as_str()).unwrap_or_default();
+            match field_name {
+                "threshold" => self.threshold.encode_to(bytes),
+                "other_signatories" => self.other_signatories.encode_to(bytes),
+                "maybe_timepoint" => self.timepoint.encode_to(bytes),
+                "call_hash" => self.call_hash.encode_to(bytes),
+                "store_call" => false.encode_to(bytes),
+                "max_weight" => bytes.extend(
+                    self.max_weight
+                        .encode_as(&metadata.types, field.ty().id())?,
+                ),
+                "call" => {
+                    let call = self
+                        .call
+                        .ok_or_else(|| anyhow::anyhow!("'{}' needs a call", call_name))?;
+                    let is_opaque = metadata
+                        .types
+                        .resolve(field.ty().id())
+                        .map(|ty| ty.path().ident().as_deref() == Some("WrapperKeepOpaque"))
+                        .unwrap_or(false);
+                    // Opaque calls are encoded like a Vec<u8>, so they're prefixed with their length:
+                    if is_opaque {
+                        Compact(call.0.len() as u32).encode_to(bytes);
+                    }
+                    call.encode_to(bytes);
+                }
+                other => anyhow::bail!("unexpected argument '{}' to Multisig.{}", other, call_name),
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format_address;
+    use sp_keyring::AccountKeyring;
+    use sp_runtime::MultiAddress;
+
+    #[test]
+    fn account_id_matches_the_multisig_pallet() {
+        // The 2 of 3 multisig of Alice, Bob and Charlie, as polkadot.js shows it (the order
+        // that the signatories are given in doesn't matter):
+        let multisig = Multisig::new(
+            vec![
+                AccountKeyring::Charlie.to_account_id(),
+                AccountKeyring::Alice.to_account_id(),
+                AccountKeyring::Bob.to_account_id(),
+            ],
+            2,
+        )
+        .unwrap();
+        assert_eq!(
+            format_address(&multisig.account_id(), 42),
+            "5DjYJStmdZ2rcqXbXGX7TW85JsrW6uG4y9MUcLq2BoPMpRA7"
+        );
+    }
+
+    #[test]
+    fn call_hash_is_blake2_256_of_the_call() {
+        // `Balances.transfer_keep_alive` of 1 DOT to Dave, on Polkadot:
+        let call = EncodedCall::new(
+            5,
+            3,
+            (
+                MultiAddress::Id::<_, u32>(AccountKeyring::Dave.to_account_id()),
+                Compact(1_000_000_000_000u128),
+            ),
+        );
+        assert_eq!(
+            hex::encode(&call.0),
+            "050300306721211d5404bd9da88e0204360a1a9ab8b87c66c1bc2fcdd37f3c2222cc20070010a5d4e8"
+        );
+        assert_eq!(
+            hex::encode(call_hash(&call)),
+            "9c2fb87bd6bdd26ddb4301e120c00bc18e32dcedd12e65e44a19707a022ff9dc"
+        );
+    }
+}
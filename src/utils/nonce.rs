@@ -0,0 +1,117 @@
+use sp_runtime::AccountId32;
+use std::collections::{BTreeSet, HashMap};
+use tokio::sync::Mutex;
+
+use crate::{rpc, RpcTransport};
+
+/// Hands out nonces for one or more accounts without asking the node each time.
+///
+/// Calling `system_accountNextIndex` before every transaction (as example 05 does) is
+/// fine for one-off transfers, but if we fire off several transactions in quick succession
+/// the node won't have seen the earlier ones yet and we'll be handed the same nonce again.
+/// Instead, we ask the node once per account and then count upwards locally.
+///
+/// The manager can be shared between tasks (wrap it in an `Arc`); nonces are handed
+/// out under a lock, so concurrent submitters never receive the same one.
+pub struct NonceManager<T = String> {
+    url: T,
+    accounts: Mutex<HashMap<AccountId32, AccountNonces>>,
+}
+
+#[derive(Debug, Default)]
+struct AccountNonces {
+    /// The next never-before-used nonce for the account.
+    next: u32,
+    /// Nonces that were handed out but whose transactions never made it into
+    /// the pool, and so can be handed out again (lowest first).
+    released: BTreeSet<u32>,
+}
+
+impl<T: RpcTransport> NonceManager<T> {
+    /// Create a nonce manager that seeds nonces from the given node (usually its HTTP URL;
+    /// see [`RpcTransport`]).
+    pub fn new(url: T) -> Self {
+        NonceManager {
+            url,
+            accounts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Hand out the next nonce to use for a transaction from this account. The first time
+    /// we see an account, we ask the node for its next index.
+    pub async fn next(&self, account: &AccountId32) -> anyhow::Result<u32> {
+        let mut accounts = self.accounts.lock().await;
+
+        if !accounts.contains_key(account) {
+            let next = node_next_index(&self.url, account).await?;
+            accounts.insert(
+                account.clone(),
+                AccountNonces {
+                    next,
+                    ..Default::default()
+                },
+            );
+        }
+
+        let nonces = accounts.get_mut(account).expect("inserted above; qed");
+
+        // Fill in any gaps left by dropped transactions before moving on:
+        if let Some(nonce) = nonces.released.iter().next().copied() {
+            nonces.released.remove(&nonce);
+            return Ok(nonce);
+        }
+
+        let nonce = nonces.next;
+        nonces.next += 1;
+        Ok(nonce)
+    }
+
+    /// Give a nonce back because the transaction using it was never accepted into the
+    /// pool, or was later dropped from it. The nonce will be handed out again before any
+    /// new ones, otherwise every transaction after it would be stuck waiting on the gap.
+    pub async fn release(&self, account: &AccountId32, nonce: u32) {
+        let mut accounts = self.accounts.lock().await;
+        if let Some(nonces) = accounts.get_mut(account) {
+            if nonce < nonces.next {
+                nonces.released.insert(nonce);
+            }
+        }
+    }
+
+    /// Re-sync our view of an account with the node. Call this when the node rejects a
+    /// transaction as having a stale nonce (for instance, because something else also
+    /// submitted transactions from the same account). This never moves backwards past
+    /// nonces that we've already handed out, and forgets any released nonces which the
+    /// node now considers used. Returns the next nonce that will be handed out.
+    pub async fn resync(&self, account: &AccountId32) -> anyhow::Result<u32> {
+        let mut accounts = self.accounts.lock().await;
+        let node_next = node_next_index(&self.url, account).await?;
+
+        let nonces = accounts.entry(account.clone()).or_default();
+        nonces.next = nonces.next.max(node_next);
+        nonces.released = nonces.released.split_off(&node_next);
+
+        Ok(nonces
+            .released
+            .iter()
+            .next()
+            .copied()
+            .unwrap_or(nonces.next))
+    }
+
+    /// Forget everything we know about an account; the next call to
+    /// [`NonceManager::next`] will ask the node again.
+    pub async fn reset(&self, account: &AccountId32) {
+        self.accounts.lock().await.remove(account);
+    }
+}
+
+/// How many transactions has this account already made? The node takes into account
+/// transactions sitting in its pool as well as those already in blocks.
+async fn node_next_index(
+    url: &(impl RpcTransport + ?Sized),
+    account: &AccountId32,
+) -> anyhow::Result<u32> {
+    let nonce_json = rpc(url, "system_accountNextIndex", (account,)).await?;
+    Ok(serde_json::from_value(nonce_json)?)
+}
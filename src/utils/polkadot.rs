@@ -0,0 +1,14 @@
+//! Types, calls, storage entries, events and constants for the Polkadot runtime, generated by
+//! `build.rs` from the metadata in `tests/fixtures/polkadot_metadata.scale` (see
+//! [`crate::generate_code`] for what's in here). These encode and decode in the same way as the
+//! runtime's own types, as of the runtime that the metadata was saved from.
+
+include!(concat!(env!("OUT_DIR"), "/polkadot.rs"));
+
+/// The generated code has its own `EncodedCall` (so that it can be used outside of `utils`),
+/// which encodes in the same way as ours.
+impl From<EncodedCall> for crate::EncodedCall {
+    fn from(call: EncodedCall) -> Self {
+        crate::EncodedCall(call.0)
+    }
+}
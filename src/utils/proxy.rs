@@ -0,0 +1,195 @@
+use frame_metadata::{RuntimeMetadataV14, StorageEntryType};
+use parity_scale_codec::Encode;
+use sp_core::blake2_256;
+use sp_runtime::AccountId32;
+
+use crate::{
+    account_from_json, decode_value, encode_account_arg, get_storage, pallet, storage_entry,
+    storage_map_key, storage_prefix, u128_from_json, u32_from_json, variant_by_name, EncodedCall,
+    RpcTransport,
+};
+
+/// One account which is allowed to make calls on behalf of another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyDefinition {
+    /// The account that can make calls on behalf of the proxied account.
+    pub delegate: AccountId32,
+    /// The name of the `ProxyType` variant, which restricts the calls that the delegate
+    /// can make (eg "Any", "NonTransfer", "Staking").
+    pub proxy_type: String,
+    /// The number of blocks that an announcement must be in place for before the
+    /// delegate can make a call. 0 means that no announcement is needed.
+    pub delay: u32,
+}
+
+/// The proxies registered for an account, as stored in `Proxy.Proxies`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Proxies {
+    pub definitions: Vec<ProxyDefinition>,
+    /// The amount held from the account to pay for storing the proxy definitions.
+    pub deposit: u128,
+}
+
+/// Wrap a call in `Proxy.proxy`, so that it's dispatched on behalf of `real` when signed and
+/// submitted by one of its delegates. If `force_proxy_type` is given, only a proxy definition of
+/// that type (eg "Any") will be used, otherwise the first one that permits the call is used.
+pub fn proxy_call(
+    metadata: &RuntimeMetadataV14,
+    real: &AccountId32,
+    force_proxy_type: Option<&str>,
+    call: &EncodedCall,
+) -> anyhow::Result<EncodedCall> {
+    EncodedCall::from_fields(metadata, "Proxy", "proxy", |field, bytes| {
+        let field_name = field.name().map(|n| n.as_str()).unwrap_or_default();
+        match field_name {
+            "real" => encode_account_arg(&metadata.types, field.ty().id(), real, bytes)?,
+            "force_proxy_type" => match force_proxy_type {
+                None => None::<u8>.encode_to(bytes),
+                Some(name) => Some(proxy_type_index(metadata, name)?).encode_to(bytes),
+            },
+            "call" => call.encode_to(bytes),
+            other => anyhow::bail!("unexpected argument '{}' to Proxy.proxy", other),
+        }
+        Ok(())
+    })
+}
+
+/// Build a `Proxy.add_proxy` call, allowing `delegate` to make calls of the given proxy type
+/// on behalf of whoever signs this call.
+pub fn add_proxy_call(
+    metadata: &RuntimeMetadataV14,
+    delegate: &AccountId32,
+    proxy_type: &str,
+    delay: u32,
+) -> anyhow::Result<EncodedCall> {
+    let proxy_type = proxy_type_index(metadata, proxy_type)?;
+    EncodedCall::from_fields(metadata, "Proxy", "add_proxy", |field, bytes| {
+        let field_name = field.name().map(|n| n.as_str()).unwrap_or_default();
+        match field_name {
+            "delegate" => encode_account_arg(&metadata.types, field.ty().id(), delegate, bytes)?,
+            "proxy_type" => proxy_type.encode_to(bytes),
+            "delay" => delay.encode_to(bytes),
+            other => anyhow::bail!("unexpected argument '{}' to Proxy.add_proxy", other),
+        }
+        Ok(())
+    })
+}
+
+/// Build a `Proxy.create_pure` call, which creates a new pure proxy account (see
+/// [`pure_proxy_account_id`]) controlled by whoever signs this call.
+pub fn create_pure_call(
+    metadata: &RuntimeMetadataV14,
+    proxy_type: &str,
+    delay: u32,
+    index: u16,
+) -> anyhow::Result<EncodedCall> {
+    let proxy_type = proxy_type_index(metadata, proxy_type)?;
+    EncodedCall::from_fields(metadata, "Proxy", "create_pure", |field, bytes| {
+        let field_name = field.name().map(|n| n.as_str()).unwrap_or_default();
+        match field_name {
+            "proxy_type" => proxy_type.encode_to(bytes),
+            "delay" => delay.encode_to(bytes),
+            "index" => index.encode_to(bytes),
+            other => anyhow::bail!("unexpected argument '{}' to Proxy.create_pure", other),
+        }
+        Ok(())
+    })
+}
+
+/// Find the index of a `ProxyType` variant by name. Proxy types are defined by each runtime,
+/// so we find the type that the `Proxy.add_proxy` call takes in the metadata and look there.
+pub fn proxy_type_index(metadata: &RuntimeMetadataV14, proxy_type: &str) -> anyhow::Result<u8> {
+    let calls_ty = pallet(metadata, "Proxy")?
+        .calls
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("the Proxy pallet has no calls"))?
+        .ty
+        .id();
+    let proxy_type_ty = variant_by_name(&metadata.types, calls_ty, "add_proxy")
+        .and_then(|call| {
+            call.fields()
+                .iter()
+                .find(|f| f.name().map(|n| n.as_str()) == Some("proxy_type"))
+        })
+        .ok_or_else(|| anyhow::anyhow!("Proxy.add_proxy has no proxy_type argument"))?
+        .ty()
+        .id();
+
+    variant_by_name(&metadata.types, proxy_type_ty, proxy_type)
+        .map(|v| v.index())
+        .ok_or_else(|| anyhow::anyhow!("'{}' is not a known proxy type", proxy_type))
+}
+
+/// Fetch the proxies that have been registered for some account.
+pub async fn get_proxies(
+    url: &(impl RpcTransport + ?Sized),
+    metadata: &RuntimeMetadataV14,
+    who: &AccountId32,
+) -> anyhow::Result<Proxies> {
+    let entry = storage_entry(metadata, "Proxy", "Proxies")?;
+    let (hasher, value_ty) = match &entry.ty {
+        StorageEntryType::Map { hashers, value, .. } if hashers.len() == 1 => (&hashers[0], value),
+        _ => anyhow::bail!("expected Proxy.Proxies to be a map"),
+    };
+
+    let storage_key = storage_map_key(
+        storage_prefix(metadata, "Proxy")?,
+        "Proxies",
+        &[(hasher, &who.encode())],
+    );
+    let bytes = match get_storage(url, &storage_key, None).await? {
+        Some(bytes) => bytes,
+        None => return Ok(Proxies::default()),
+    };
+
+    // The value is a tuple of (proxy definitions, deposit):
+    let value = decode_value(&metadata.types, value_ty.id(), &mut &*bytes)?;
+    let definitions = value[0]
+        .as_array()
+        .ok_or_else(|| anyhow::anyhow!("proxy definitions should be an array"))?
+        .iter()
+        .map(|def| {
+            Ok(ProxyDefinition {
+                delegate: account_from_json(&def["delegate"])?,
+                // Proxy types have no fields, so they're decoded to just their name:
+                proxy_type: def["proxy_type"]
+                    .as_str()
+                    .ok_or_else(|| anyhow::anyhow!("proxy type should be a variant name"))?
+                    .to_string(),
+                delay: u32_from_json(&def["delay"])?,
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    Ok(Proxies {
+        definitions,
+        deposit: u128_from_json(&value[1])?,
+    })
+}
+
+/// Derive the account ID of a pure (formerly "anonymous") proxy. Pure proxies are fresh accounts
+/// with no private key, created by `Proxy.create_pure`, and controlled only via the account that
+/// spawned them. This is `pure_account` from `substrate/frame/proxy/src/lib.rs`: a hash of the
+/// spawner, the block number and extrinsic index that the pure proxy was created at, the proxy
+/// type and the disambiguation index given to `create_pure`.
+pub fn pure_proxy_account_id(
+    metadata: &RuntimeMetadataV14,
+    spawner: &AccountId32,
+    proxy_type: &str,
+    index: u16,
+    height: u32,
+    ext_index: u32,
+) -> anyhow::Result<AccountId32> {
+    // Proxy types are plain enums, and so encode to just their variant index:
+    let proxy_type = proxy_type_index(metadata, proxy_type)?;
+    let entropy = (
+        b"modlpy/proxy____",
+        spawner,
+        height,
+        ext_index,
+        proxy_type,
+        index,
+    )
+        .encode();
+    Ok(AccountId32::from(blake2_256(&entropy)))
+}
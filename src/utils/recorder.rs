@@ -0,0 +1,281 @@
+use jsonrpsee::{
+    async_client::Client,
+    core::client::{TransportReceiverT, TransportSenderT},
+};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use crate::{normalize_params, Fixture, RpcTransport};
+
+/// Records every RPC call made through it (and every subscription notification that comes
+/// back) to a file, so that a session with some node can be looked at later, or replayed
+/// using [`crate::MockNode::replay`].
+///
+/// Use [`Recorder::wrap`] on a URL (or a WS client) and hand the result to any of the helpers
+/// in its place, and use [`Recorder::ws_client`] in place of [`crate::ws_client`] to record
+/// subscriptions. Calls are kept in memory, and written out by [`Recorder::finish`], or else
+/// once the recorder and everything made from it has been dropped.
+#[derive(Clone)]
+pub struct Recorder {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    path: PathBuf,
+    recording: Mutex<Recording>,
+}
+
+#[derive(Default)]
+struct Recording {
+    calls: Vec<Fixture>,
+    /// WS requests which we haven't seen a response to yet, by request ID.
+    pending: HashMap<String, Fixture>,
+    /// The index into `calls` of each subscription, by subscription ID.
+    subscriptions: HashMap<String, usize>,
+    /// Whether anything has been recorded since the calls were last written out.
+    unsaved: bool,
+}
+
+impl Recorder {
+    /// Record to the given file, replacing anything that's already there.
+    pub fn create(path: impl AsRef<Path>) -> anyhow::Result<Recorder> {
+        let path = path.as_ref().to_path_buf();
+        // Find out now, rather than at the end of the session, if we can't write there:
+        save(&path, &[])?;
+        Ok(Recorder {
+            shared: Arc::new(Shared {
+                path,
+                recording: Default::default(),
+            }),
+        })
+    }
+
+    /// The calls recorded so far.
+    pub fn calls(&self) -> Vec<Fixture> {
+        self.recording().calls.clone()
+    }
+
+    /// Write the calls recorded so far to the file. Anything recorded after this is written
+    /// out when the recorder is dropped (or by calling this again).
+    pub fn finish(&self) -> anyhow::Result<()> {
+        let calls = {
+            let mut recording = self.recording();
+            recording.unsaved = false;
+            recording.calls.clone()
+        };
+        save(&self.shared.path, &calls).map_err(|e| {
+            self.recording().unsaved = true;
+            e
+        })
+    }
+
+    /// Wrap something we make RPC requests through (like a URL), so that every request made
+    /// through it is recorded.
+    pub fn wrap<T: RpcTransport>(&self, transport: T) -> RecordingTransport<T> {
+        RecordingTransport {
+            inner: transport,
+            recorder: self.clone(),
+        }
+    }
+
+    /// Build a WS client which records everything sent to and received from the node.
+    pub async fn ws_client(&self, url: &str) -> anyhow::Result<Client> {
+        let url: jsonrpsee::client_transport::ws::Uri = url.parse()?;
+
+        let (sender, receiver) =
+            jsonrpsee::client_transport::ws::WsTransportClientBuilder::default()
+                .build(url)
+                .await?;
+        let sender = RecordingSender {
+            inner: sender,
+            recorder: self.clone(),
+        };
+        let receiver = RecordingReceiver {
+            inner: receiver,
+            recorder: self.clone(),
+        };
+
+        Ok(jsonrpsee::core::client::ClientBuilder::default()
+            .max_notifs_per_subscription(4096)
+            .build_with_tokio(sender, receiver))
+    }
+
+    /// Note down the requests in a message sent over WS, to match up with the responses.
+    fn sent(&self, message: &str) {
+        let message: Value = match serde_json::from_str(message) {
+            Ok(message) => message,
+            Err(_) => return,
+        };
+        let mut recording = self.recording();
+        for request in as_batch(&message) {
+            if let Some(method) = request["method"].as_str() {
+                let call = Fixture {
+                    method: method.to_string(),
+                    params: Some(normalize_params(request.get("params"))),
+                    result: Value::Null,
+                    error: None,
+                    notifications: Vec::new(),
+                    notification_method: None,
+                };
+                recording.pending.insert(request["id"].to_string(), call);
+            }
+        }
+    }
+
+    /// Record the responses and notifications in a message received over WS.
+    fn received(&self, message: &str) {
+        let message: Value = match serde_json::from_str(message) {
+            Ok(message) => message,
+            Err(_) => return,
+        };
+        let mut recording = self.recording();
+        for response in as_batch(&message) {
+            // Notifications look like:
+            // { "method": .., "params": { "subscription": .., "result": .. } }
+            if let Some(notification_method) = response["method"].as_str() {
+                let params = &response["params"];
+                let index = match recording
+                    .subscriptions
+                    .get(&id_key(&params["subscription"]))
+                {
+                    Some(&index) => index,
+                    None => continue,
+                };
+                let call = &mut recording.calls[index];
+                call.notification_method = Some(notification_method.to_string());
+                call.notifications.push(params["result"].clone());
+                recording.unsaved = true;
+                continue;
+            }
+
+            let call = match recording.pending.remove(&response["id"].to_string()) {
+                Some(call) => call,
+                None => continue,
+            };
+            let call = call_from_response(&call.method, &call.params.unwrap_or_default(), response);
+            // The result of subscribing is an ID that later notifications refer to:
+            let method = call.method.to_lowercase();
+            if method.contains("subscribe") || method.contains("watch") {
+                let index = recording.calls.len();
+                recording.subscriptions.insert(id_key(&call.result), index);
+            }
+            recording.calls.push(call);
+            recording.unsaved = true;
+        }
+    }
+
+    fn record(&self, call: Fixture) {
+        let mut recording = self.recording();
+        recording.calls.push(call);
+        recording.unsaved = true;
+    }
+
+    fn recording(&self) -> MutexGuard<'_, Recording> {
+        self.shared.recording.lock().unwrap()
+    }
+}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        let recording = match self.recording.get_mut() {
+            Ok(recording) => recording,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if recording.unsaved {
+            if let Err(e) = save(&self.path, &recording.calls) {
+                eprintln!("Failed to save recording: {}", e);
+            }
+        }
+    }
+}
+
+fn save(path: &Path, calls: &[Fixture]) -> anyhow::Result<()> {
+    let file = std::fs::File::create(path)
+        .map_err(|e| anyhow::anyhow!("can't write recording '{}': {}", path.display(), e))?;
+    serde_json::to_writer_pretty(std::io::BufWriter::new(file), calls)?;
+    Ok(())
+}
+
+/// Something we make RPC requests through, wrapped so that each request is recorded; see
+/// [`Recorder::wrap`].
+pub struct RecordingTransport<T> {
+    inner: T,
+    recorder: Recorder,
+}
+
+#[jsonrpsee::core::async_trait]
+impl<T: RpcTransport> RpcTransport for RecordingTransport<T> {
+    async fn request(&self, method: &str, params: Value) -> anyhow::Result<Value> {
+        let response = self.inner.request(method, params.clone()).await?;
+        self.recorder
+            .record(call_from_response(method, &params, &response));
+        Ok(response)
+    }
+}
+
+fn call_from_response(method: &str, params: &Value, response: &Value) -> Fixture {
+    Fixture {
+        method: method.to_string(),
+        params: Some(normalize_params(Some(params))),
+        result: response["result"].clone(),
+        error: response.get("error").cloned(),
+        notifications: Vec::new(),
+        notification_method: None,
+    }
+}
+
+/// Messages can hold a single request or response, or a batch of them.
+fn as_batch(message: &Value) -> &[Value] {
+    match message {
+        Value::Array(batch) => batch,
+        single => std::slice::from_ref(single),
+    }
+}
+
+/// Subscription IDs can be strings or numbers; either way, this gives a string to look them
+/// up by.
+fn id_key(id: &Value) -> String {
+    match id {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+struct RecordingSender<S> {
+    inner: S,
+    recorder: Recorder,
+}
+
+#[jsonrpsee::core::async_trait]
+impl<S: TransportSenderT + Send> TransportSenderT for RecordingSender<S> {
+    type Error = S::Error;
+
+    async fn send(&mut self, msg: String) -> Result<(), Self::Error> {
+        self.recorder.sent(&msg);
+        self.inner.send(msg).await
+    }
+
+    async fn close(&mut self) -> Result<(), Self::Error> {
+        self.inner.close().await
+    }
+}
+
+struct RecordingReceiver<R> {
+    inner: R,
+    recorder: Recorder,
+}
+
+#[jsonrpsee::core::async_trait]
+impl<R: TransportReceiverT + Send> TransportReceiverT for RecordingReceiver<R> {
+    type Error = R::Error;
+
+    async fn receive(&mut self) -> Result<String, Self::Error> {
+        let msg = self.inner.receive().await?;
+        self.recorder.received(&msg);
+        Ok(msg)
+    }
+}
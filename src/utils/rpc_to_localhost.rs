@@ -2,36 +2,96 @@ use serde_json::{json, Value};
 
 pub const LOCALHOST_RPC_URL: &str = "http://localhost:9933";
 
-/// Make an RPC request to the localhost node over HTTP.
+/// The HTTP URL of the node that the examples talk to. This is [`LOCALHOST_RPC_URL`], unless
+/// the `POLKADOT_RPC_URL` environment variable says otherwise (which is how CI points them at
+/// a [`crate::MockNode`]).
+pub fn rpc_url() -> String {
+    std::env::var("POLKADOT_RPC_URL").unwrap_or_else(|_| LOCALHOST_RPC_URL.to_string())
+}
+
+/// Make an RPC request to the localhost node (or whichever node [`rpc_url`] gives) over HTTP.
 pub async fn rpc_to_localhost<Params: serde::Serialize>(
     method: &str,
     params: Params,
 ) -> anyhow::Result<Value> {
-    rpc(LOCALHOST_RPC_URL, method, params).await
+    rpc(&rpc_url(), method, params).await
 }
 
-/// Make an RPC request to some URL.
+/// Something we can make JSON-RPC requests through. The helpers in this crate take one of
+/// these as their `url`; usually that's the URL of a node (as a `&str` or `String`), which we
+/// make requests to over HTTP. A WS client (see [`crate::ws_client`]) works too, as does a
+/// [`crate::Recorder`] wrapping either of those, which records every request made through it.
+#[jsonrpsee::core::async_trait]
+pub trait RpcTransport: Send + Sync {
+    /// Make a request, handing back the whole JSONRPC response rather than just the "result".
+    async fn request(&self, method: &str, params: Value) -> anyhow::Result<Value>;
+}
+
+#[jsonrpsee::core::async_trait]
+impl RpcTransport for str {
+    async fn request(&self, method: &str, params: Value) -> anyhow::Result<Value> {
+        let client = reqwest::Client::new();
+        let body: Value = client
+            .post(self)
+            .json(&json! {{
+                // Used to correlate request with response over socket connections.
+                // not needed here over our simple HTTP connection, so just set it
+                // to 1 always:
+                "id": 1,
+                "jsonrpc": "2.0",
+                "method": method,
+                "params": params
+            }})
+            .send()
+            .await?
+            .json()
+            .await?;
+        Ok(body)
+    }
+}
+
+#[jsonrpsee::core::async_trait]
+impl RpcTransport for String {
+    async fn request(&self, method: &str, params: Value) -> anyhow::Result<Value> {
+        self.as_str().request(method, params).await
+    }
+}
+
+#[jsonrpsee::core::async_trait]
+impl<T: RpcTransport + ?Sized> RpcTransport for &T {
+    async fn request(&self, method: &str, params: Value) -> anyhow::Result<Value> {
+        (**self).request(method, params).await
+    }
+}
+
+/// Make an RPC request to some URL (or anything else we can make requests through; see
+/// [`RpcTransport`]).
 pub async fn rpc<Params: serde::Serialize>(
-    url: &str,
+    url: &(impl RpcTransport + ?Sized),
+    method: &str,
+    params: Params,
+) -> anyhow::Result<Value> {
+    let body = rpc_response(url, method, params).await?;
+    result_from_response(method, body)
+}
+
+/// Make an RPC request to some URL, handing back the whole JSONRPC response rather
+/// than just the "result".
+pub async fn rpc_response<Params: serde::Serialize>(
+    url: &(impl RpcTransport + ?Sized),
     method: &str,
     params: Params,
 ) -> anyhow::Result<Value> {
-    let client = reqwest::Client::new();
-    let mut body: Value = client
-        .post(url)
-        .json(&json! {{
-            // Used to correlate request with response over socket connections.
-            // not needed here over our simple HTTP connection, so just set it
-            // to 1 always:
-            "id": 1,
-            "jsonrpc": "2.0",
-            "method": method,
-            "params": params
-        }})
-        .send()
-        .await?
-        .json()
-        .await?;
+    url.request(method, serde_json::to_value(params)?).await
+}
+
+/// Take the "result" out of a JSONRPC response.
+pub(crate) fn result_from_response(method: &str, mut body: Value) -> anyhow::Result<Value> {
+    // If the node responded with an error, surface it rather than handing back
+    // a null "result":
+    if let Some(error) = body.get("error") {
+        anyhow::bail!("RPC call '{}' failed: {}", method, error);
+    }
 
     // take the "result" out of the JSONRPC response:
     Ok(body["result"].take())
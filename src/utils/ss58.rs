@@ -0,0 +1,87 @@
+use frame_metadata::RuntimeMetadataV14;
+use sp_core::crypto::{Ss58AddressFormat, Ss58Codec};
+use sp_runtime::AccountId32;
+use std::convert::TryInto;
+
+use crate::{constant_value, get_token_info, RpcTransport};
+
+/// An account ID parsed from some user input, along with the SS58 prefix it was encoded
+/// with (if it was given as an SS58 address rather than as hex).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedAddress {
+    pub account: AccountId32,
+    pub prefix: Option<u16>,
+}
+
+impl ParsedAddress {
+    /// Hand back the account ID if the address was meant for a chain using the given prefix
+    /// (or was given as hex, which doesn't say), and complain if it wasn't. An address with the
+    /// wrong prefix still refers to the same account, but is usually a sign of a mix up.
+    pub fn for_prefix(self, expected_prefix: u16) -> anyhow::Result<AccountId32> {
+        match self.prefix {
+            Some(prefix) if prefix != expected_prefix => anyhow::bail!(
+                "address has SS58 prefix {}, but this chain uses prefix {} (the same account is {} here)",
+                prefix,
+                expected_prefix,
+                format_address(&self.account, expected_prefix)
+            ),
+            _ => Ok(self.account),
+        }
+    }
+}
+
+/// Parse an account from an SS58 address in any format (like "15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5"
+/// for Polkadot, or "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty" for a dev chain), or
+/// from the `0x` prefixed hex encoding of the account ID's 32 bytes.
+pub fn parse_address(address: &str) -> anyhow::Result<ParsedAddress> {
+    let address = address.trim();
+
+    if let Some(hex_str) = address.strip_prefix("0x") {
+        let bytes: [u8; 32] = hex::decode(hex_str)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("hex account IDs should be 32 bytes"))?;
+        return Ok(ParsedAddress {
+            account: bytes.into(),
+            prefix: None,
+        });
+    }
+
+    let (account, format) = AccountId32::from_ss58check_with_version(address)
+        .map_err(|e| anyhow::anyhow!("'{}' is not a valid SS58 address: {:?}", address, e))?;
+    Ok(ParsedAddress {
+        account,
+        prefix: Some(format.into()),
+    })
+}
+
+/// Encode an account ID as an SS58 address with the given prefix.
+pub fn format_address(account: &AccountId32, prefix: u16) -> String {
+    account.to_ss58check_with_version(Ss58AddressFormat::custom(prefix))
+}
+
+/// The SS58 prefix that the runtime says addresses should use, from the `System.SS58Prefix`
+/// constant. Older runtimes don't have this.
+pub fn ss58_prefix(metadata: &RuntimeMetadataV14) -> anyhow::Result<Option<u16>> {
+    let value = match constant_value(metadata, "System", "SS58Prefix")? {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    let prefix = value
+        .as_u64()
+        .and_then(|p| p.try_into().ok())
+        .ok_or_else(|| anyhow::anyhow!("invalid SS58Prefix {}", value))?;
+    Ok(Some(prefix))
+}
+
+/// Work out which SS58 prefix the connected chain uses; from the `System.SS58Prefix`
+/// constant if the runtime has one, and otherwise from the "ss58Format" that the
+/// "system_properties" RPC method hands back (see [`get_token_info`]).
+pub async fn get_ss58_prefix(
+    url: &(impl RpcTransport + ?Sized),
+    metadata: &RuntimeMetadataV14,
+) -> anyhow::Result<u16> {
+    match ss58_prefix(metadata)? {
+        Some(prefix) => Ok(prefix),
+        None => Ok(get_token_info(url).await?.ss58_format),
+    }
+}
@@ -0,0 +1,318 @@
+use frame_metadata::{
+    RuntimeMetadataV14, StorageEntryMetadata, StorageEntryModifier, StorageEntryType, StorageHasher,
+};
+use scale_info::{form::PortableForm, TypeDef};
+use serde_json::Value;
+use sp_core::hashing;
+
+use crate::{decode_value, encode_value, pallet, rpc, storage_entry, RpcTransport};
+
+/// A value fetched by [`iter_storage_values`], along with the storage key it was found at.
+#[derive(Debug, Clone)]
+pub struct StorageItem {
+    /// The full storage key that the value is stored at.
+    pub key: Vec<u8>,
+    /// The map keys, decoded from the storage key (see [`decode_storage_keys`]).
+    pub keys: Vec<Value>,
+    /// The decoded value.
+    pub value: Value,
+}
+
+/// The storage key for a plain (non-map) storage item; `twox_128(prefix) ++ twox_128(name)`.
+/// Map keys start with this too, and then have their hashed keys appended (see example 04).
+pub fn storage_prefix_key(storage_prefix: &str, storage_name: &str) -> Vec<u8> {
+    let mut storage_key = Vec::new();
+    storage_key.extend_from_slice(&hashing::twox_128(storage_prefix.as_bytes()));
+    storage_key.extend_from_slice(&hashing::twox_128(storage_name.as_bytes()));
+    storage_key
+}
+
+/// Fetch the SCALE encoded bytes stored at some key, optionally at a given block
+/// hash (the latest block is used otherwise). `None` means nothing is stored there.
+pub async fn get_storage(
+    url: &(impl RpcTransport + ?Sized),
+    storage_key: &[u8],
+    at: Option<&str>,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    let storage_key_hex = format!("0x{}", hex::encode(storage_key));
+    let result = rpc(url, "state_getStorage", (storage_key_hex, at)).await?;
+
+    match result.as_str() {
+        Some(hex) => Ok(Some(hex::decode(hex.trim_start_matches("0x"))?)),
+        None => Ok(None),
+    }
+}
+
+/// Hash a SCALE encoded storage map key using the given hasher. The "Concat" hashers append
+/// the key itself to the hash, which is what lets us read keys back out of storage keys (as in
+/// examples 04 and 06).
+pub fn hash_key(hasher: &StorageHasher, key: &[u8]) -> Vec<u8> {
+    match hasher {
+        StorageHasher::Blake2_128 => hashing::blake2_128(key).to_vec(),
+        StorageHasher::Blake2_256 => hashing::blake2_256(key).to_vec(),
+        StorageHasher::Blake2_128Concat => [&hashing::blake2_128(key)[..], key].concat(),
+        StorageHasher::Twox128 => hashing::twox_128(key).to_vec(),
+        StorageHasher::Twox256 => hashing::twox_256(key).to_vec(),
+        StorageHasher::Twox64Concat => [&hashing::twox_64(key)[..], key].concat(),
+        StorageHasher::Identity => key.to_vec(),
+    }
+}
+
+/// The storage key for an entry in a storage map, given each of the SCALE encoded keys
+/// and the hashers that the metadata says are used for them.
+pub fn storage_map_key(
+    storage_prefix: &str,
+    storage_name: &str,
+    keys: &[(&StorageHasher, &[u8])],
+) -> Vec<u8> {
+    let mut storage_key = storage_prefix_key(storage_prefix, storage_name);
+    for (hasher, key) in keys {
+        storage_key.extend(hash_key(hasher, key));
+    }
+    storage_key
+}
+
+/// Fetch and decode (see [`decode_value`]) a storage entry, looking up its type, hashers and
+/// default value in the metadata. `keys` holds the SCALE encoded key for each hasher of a storage
+/// map, and is empty for a plain storage value. Entries with a default value (like `System.Account`)
+/// hand back that default when nothing is stored, and other entries hand back `None`.
+pub async fn get_storage_value(
+    url: &(impl RpcTransport + ?Sized),
+    metadata: &RuntimeMetadataV14,
+    pallet_name: &str,
+    entry_name: &str,
+    keys: &[Vec<u8>],
+    at: Option<&str>,
+) -> anyhow::Result<Option<Value>> {
+    let entry = storage_entry(metadata, pallet_name, entry_name)?;
+    let storage_prefix = storage_prefix(metadata, pallet_name)?;
+
+    let (storage_key, value_ty) = match &entry.ty {
+        StorageEntryType::Plain(value) if keys.is_empty() => {
+            (storage_prefix_key(storage_prefix, entry_name), value)
+        }
+        StorageEntryType::Map { hashers, value, .. } if hashers.len() == keys.len() => {
+            let keys: Vec<_> = hashers
+                .iter()
+                .zip(keys)
+                .map(|(hasher, key)| (hasher, &key[..]))
+                .collect();
+            (storage_map_key(storage_prefix, entry_name, &keys), value)
+        }
+        _ => anyhow::bail!(
+            "storage entry '{}.{}' does not take {} key(s)",
+            pallet_name,
+            entry_name,
+            keys.len()
+        ),
+    };
+
+    let bytes = match get_storage(url, &storage_key, at).await? {
+        Some(bytes) => bytes,
+        None if entry.modifier == StorageEntryModifier::Default => entry.default.clone(),
+        None => return Ok(None),
+    };
+    Ok(Some(decode_value(
+        &metadata.types,
+        value_ty.id(),
+        &mut &*bytes,
+    )?))
+}
+
+/// The type ID of each key of a storage map, one for each of its hashers. The metadata
+/// describes the keys of a map with more than one hasher (like `Staking.ErasStakers`) as
+/// a single tuple type, which we split up here. Plain storage values have no keys.
+pub fn storage_key_types(
+    metadata: &RuntimeMetadataV14,
+    entry: &StorageEntryMetadata<PortableForm>,
+) -> anyhow::Result<Vec<u32>> {
+    let (hashers, key) = match &entry.ty {
+        StorageEntryType::Plain(_) => return Ok(Vec::new()),
+        StorageEntryType::Map { hashers, key, .. } => (hashers, key),
+    };
+    if hashers.len() == 1 {
+        return Ok(vec![key.id()]);
+    }
+
+    let key_ty = metadata
+        .types
+        .resolve(key.id())
+        .ok_or_else(|| anyhow::anyhow!("type {} not found in the metadata", key.id()))?;
+    match key_ty.type_def() {
+        TypeDef::Tuple(tuple) if tuple.fields().len() == hashers.len() => {
+            Ok(tuple.fields().iter().map(|field| field.id()).collect())
+        }
+        _ => anyhow::bail!(
+            "storage entry '{}' has {} hashers, but its key is not a tuple of that many types",
+            entry.name,
+            hashers.len()
+        ),
+    }
+}
+
+/// SCALE encode the keys of a storage map (see [`encode_value`]), ready to hand to
+/// [`get_storage_value`] or [`iter_storage_values`]. Fewer keys than the map has hashers
+/// can be given, which is useful for iterating over part of a map.
+pub fn encode_storage_keys(
+    metadata: &RuntimeMetadataV14,
+    pallet_name: &str,
+    entry_name: &str,
+    keys: &[Value],
+) -> anyhow::Result<Vec<Vec<u8>>> {
+    let entry = storage_entry(metadata, pallet_name, entry_name)?;
+    let key_types = storage_key_types(metadata, entry)?;
+    if keys.len() > key_types.len() {
+        anyhow::bail!(
+            "storage entry '{}.{}' takes at most {} key(s), but got {}",
+            pallet_name,
+            entry_name,
+            key_types.len(),
+            keys.len()
+        );
+    }
+
+    keys.iter()
+        .zip(key_types)
+        .enumerate()
+        .map(|(index, (key, key_ty))| {
+            let mut out = Vec::new();
+            encode_value(&metadata.types, key_ty, key, &mut out)
+                .map_err(|e| anyhow::anyhow!("key {} ({}) is not valid: {}", index + 1, key, e))?;
+            Ok(out)
+        })
+        .collect()
+}
+
+/// Decode the map keys back out of a full storage key (which starts with the 32 byte
+/// [`storage_prefix_key`]). Only keys hashed with a "Concat" hasher or `Identity` can be read
+/// back; the others are handed back as `null`.
+pub fn decode_storage_keys(
+    metadata: &RuntimeMetadataV14,
+    entry: &StorageEntryMetadata<PortableForm>,
+    storage_key: &[u8],
+) -> anyhow::Result<Vec<Value>> {
+    let hashers = match &entry.ty {
+        StorageEntryType::Plain(_) => return Ok(Vec::new()),
+        StorageEntryType::Map { hashers, .. } => hashers,
+    };
+    let key_types = storage_key_types(metadata, entry)?;
+
+    let mut data = storage_key
+        .get(32..)
+        .ok_or_else(|| anyhow::anyhow!("storage key is shorter than its prefix"))?;
+    let mut keys = Vec::new();
+    for (hasher, key_ty) in hashers.iter().zip(key_types) {
+        let (hash_len, is_concat) = match hasher {
+            StorageHasher::Blake2_128 | StorageHasher::Twox128 => (16, false),
+            StorageHasher::Blake2_256 | StorageHasher::Twox256 => (32, false),
+            StorageHasher::Blake2_128Concat => (16, true),
+            StorageHasher::Twox64Concat => (8, true),
+            StorageHasher::Identity => (0, true),
+        };
+        if data.len() < hash_len {
+            anyhow::bail!("storage key is too short for its hashers");
+        }
+        data = &data[hash_len..];
+        if is_concat {
+            keys.push(decode_value(&metadata.types, key_ty, &mut data)?);
+        } else {
+            keys.push(Value::Null);
+        }
+    }
+    Ok(keys)
+}
+
+/// Fetch and decode (see [`decode_value`]) every value stored under some storage entry,
+/// along with the storage key of each and the map keys that we can decode from it. This is
+/// how we'd look through all of the entries in a storage map, as example 06 does by hand.
+/// `keys` holds SCALE encoded keys for the first few hashers of a map, to only fetch the
+/// entries under them (for instance every `Staking.ErasStakers` entry for one era).
+///
+/// Keys are fetched a page at a time using "state_getKeysPaged", and then the values for each
+/// page of keys are fetched in one go using "state_queryStorageAt".
+pub async fn iter_storage_values(
+    url: &(impl RpcTransport + ?Sized),
+    metadata: &RuntimeMetadataV14,
+    pallet_name: &str,
+    entry_name: &str,
+    keys: &[Vec<u8>],
+    at: Option<&str>,
+) -> anyhow::Result<Vec<StorageItem>> {
+    const PAGE_SIZE: usize = 1000;
+
+    let entry = storage_entry(metadata, pallet_name, entry_name)?;
+    let storage_prefix = storage_prefix(metadata, pallet_name)?;
+    let (prefix, value_ty) = match &entry.ty {
+        StorageEntryType::Plain(value) if keys.is_empty() => {
+            (storage_prefix_key(storage_prefix, entry_name), value)
+        }
+        StorageEntryType::Map { hashers, value, .. } if keys.len() <= hashers.len() => {
+            let keys: Vec<_> = hashers
+                .iter()
+                .zip(keys)
+                .map(|(hasher, key)| (hasher, &key[..]))
+                .collect();
+            (storage_map_key(storage_prefix, entry_name, &keys), value)
+        }
+        _ => anyhow::bail!(
+            "storage entry '{}.{}' does not take {} key(s)",
+            pallet_name,
+            entry_name,
+            keys.len()
+        ),
+    };
+    let prefix_hex = format!("0x{}", hex::encode(prefix));
+
+    let mut entries = Vec::new();
+    let mut start_key: Option<String> = None;
+    loop {
+        let keys: Vec<String> = serde_json::from_value(
+            rpc(
+                url,
+                "state_getKeysPaged",
+                (&prefix_hex, PAGE_SIZE, &start_key, at),
+            )
+            .await?,
+        )?;
+        if keys.is_empty() {
+            break;
+        }
+
+        let change_sets = rpc(url, "state_queryStorageAt", (&keys, at)).await?;
+        for change_set in change_sets.as_array().into_iter().flatten() {
+            for change in change_set["changes"].as_array().into_iter().flatten() {
+                let (key_hex, value_hex) = match (change[0].as_str(), change[1].as_str()) {
+                    (Some(key), Some(value)) => (key, value),
+                    // Keys can be removed between fetching them and fetching their values:
+                    _ => continue,
+                };
+                let key = hex::decode(key_hex.trim_start_matches("0x"))?;
+                let bytes = hex::decode(value_hex.trim_start_matches("0x"))?;
+                entries.push(StorageItem {
+                    keys: decode_storage_keys(metadata, entry, &key)?,
+                    value: decode_value(&metadata.types, value_ty.id(), &mut &*bytes)?,
+                    key,
+                });
+            }
+        }
+
+        if keys.len() < PAGE_SIZE {
+            break;
+        }
+        start_key = keys.last().cloned();
+    }
+    Ok(entries)
+}
+
+/// The prefix used in the storage keys of a pallet's entries. This is usually, but not
+/// always, the pallet's name.
+pub fn storage_prefix<'a>(
+    metadata: &'a RuntimeMetadataV14,
+    pallet_name: &'a str,
+) -> anyhow::Result<&'a str> {
+    Ok(pallet(metadata, pallet_name)?
+        .storage
+        .as_ref()
+        .map(|storage| storage.prefix.as_str())
+        .unwrap_or(pallet_name))
+}
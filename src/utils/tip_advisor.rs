@@ -0,0 +1,207 @@
+use frame_metadata::{RuntimeMetadataV14, StorageEntryType};
+use serde_json::Value;
+use std::convert::TryFrom;
+
+use crate::{
+    constant, decode_signed_details, decode_value, get_storage, number_from_json, rpc,
+    storage_entry, storage_prefix_key, ExtrinsicSigner, RpcTransport, Weight,
+};
+
+/// How busy a recent block was.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockCongestion {
+    pub number: u64,
+    /// The weight used by normal extrinsics in the block, from `System.BlockWeight`.
+    pub normal_weight: Weight,
+    /// How full the block was with normal extrinsics, from 0 to 1. With two dimensional
+    /// weights, this is whichever dimension is closer to its limit.
+    pub fullness: f64,
+    /// The number of signed extrinsics in the block.
+    pub signed_extrinsics: usize,
+    /// The tips paid by those extrinsics (leaving out any signed in a format we can't decode).
+    pub tips: Vec<u128>,
+}
+
+/// A suggested tip, and the figures it was based on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TipAdvice {
+    /// The number of blocks we'd like the transaction to be included within.
+    pub target_blocks: u32,
+    /// The recent blocks that we looked at.
+    pub blocks: Vec<BlockCongestion>,
+    /// The number of extrinsics waiting in the node's transaction pool.
+    pub pending_extrinsics: usize,
+    /// The average fullness of the recent blocks (see [`BlockCongestion::fullness`]).
+    pub average_fullness: f64,
+    /// Roughly how many blocks it would take to include everything that's pending, going
+    /// by how many signed extrinsics recent blocks have included.
+    pub blocks_to_clear_pool: f64,
+    /// The tip we suggest paying.
+    pub suggested_tip: u128,
+}
+
+/// Blocks fuller than this (with normal extrinsics) are considered congested.
+const CONGESTED_FULLNESS: f64 = 0.75;
+
+/// Suggest a tip for a transaction that we'd like included within `target_blocks` blocks,
+/// based on the last `recent_blocks` blocks.
+///
+/// Transactions in the pool are ordered by priority, and a higher tip gives a higher priority.
+/// Tips only matter when there are more transactions waiting than fit in the next few blocks,
+/// so if recent blocks have had room to spare and the pool isn't backed up, we suggest no tip.
+/// Otherwise, we suggest paying at least as much as most recent transactions did; the sooner we
+/// want to be included, the higher the percentile of recent tips that we aim for.
+///
+/// This is a heuristic, and only a rough guide; priority also depends on the fee and weight of
+/// the transaction, and nothing stops others from outbidding us.
+pub async fn suggest_tip(
+    url: &(impl RpcTransport + ?Sized),
+    metadata: &RuntimeMetadataV14,
+    recent_blocks: u32,
+    target_blocks: u32,
+) -> anyhow::Result<TipAdvice> {
+    let max_normal_weight = max_normal_weight(metadata)?;
+
+    let header = rpc(url, "chain_getHeader", ()).await?;
+    let latest = number_from_json(&header["number"], "number")?;
+    let latest = u64::try_from(latest)
+        .map_err(|_| anyhow::anyhow!("block number {} does not fit in a u64", latest))?;
+
+    let mut blocks = Vec::new();
+    for number in latest.saturating_sub(recent_blocks.saturating_sub(1) as u64)..=latest {
+        blocks.push(block_congestion(url, metadata, number, max_normal_weight).await?);
+    }
+
+    let pending = rpc(url, "author_pendingExtrinsics", ()).await?;
+    let pending_extrinsics = pending.as_array().map(|p| p.len()).unwrap_or(0);
+
+    let num_blocks = blocks.len().max(1) as f64;
+    let average_fullness = blocks.iter().map(|b| b.fullness).sum::<f64>() / num_blocks;
+    let average_extrinsics =
+        blocks.iter().map(|b| b.signed_extrinsics).sum::<usize>() as f64 / num_blocks;
+    let blocks_to_clear_pool = if pending_extrinsics == 0 {
+        0.0
+    } else {
+        pending_extrinsics as f64 / average_extrinsics.max(1.0)
+    };
+
+    let is_congested = average_fullness >= CONGESTED_FULLNESS
+        || blocks_to_clear_pool > target_blocks.max(1) as f64;
+
+    let suggested_tip = if is_congested {
+        let mut tips: Vec<u128> = blocks.iter().flat_map(|b| b.tips.iter().copied()).collect();
+        tips.sort_unstable();
+        let percentile = match target_blocks {
+            0 | 1 => 0.9,
+            2 | 3 => 0.75,
+            _ => 0.5,
+        };
+        match tips.len() {
+            0 => 0,
+            n => tips[((n - 1) as f64 * percentile).round() as usize],
+        }
+    } else {
+        0
+    };
+
+    Ok(TipAdvice {
+        target_blocks,
+        blocks,
+        pending_extrinsics,
+        average_fullness,
+        blocks_to_clear_pool,
+        suggested_tip,
+    })
+}
+
+/// Find out how full some block was, and what tips were paid in it.
+pub async fn block_congestion(
+    url: &(impl RpcTransport + ?Sized),
+    metadata: &RuntimeMetadataV14,
+    number: u64,
+    max_normal_weight: Weight,
+) -> anyhow::Result<BlockCongestion> {
+    let hash = rpc(url, "chain_getBlockHash", [number])
+        .await?
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("no block hash found for block {}", number))?
+        .to_string();
+
+    // `System.BlockWeight` holds the weight used so far in the block, per dispatch class:
+    let entry = storage_entry(metadata, "System", "BlockWeight")?;
+    let block_weight_ty = match &entry.ty {
+        StorageEntryType::Plain(ty) => ty.id(),
+        _ => anyhow::bail!("expected System.BlockWeight to be a plain storage value"),
+    };
+    let normal_weight = match get_storage(
+        url,
+        &storage_prefix_key("System", "BlockWeight"),
+        Some(&hash),
+    )
+    .await?
+    {
+        Some(bytes) => {
+            let block_weight = decode_value(&metadata.types, block_weight_ty, &mut &*bytes)?;
+            Weight::from_json(&block_weight["normal"])?
+        }
+        None => Weight::default(),
+    };
+
+    let fullness = |used: u64, max: u64| {
+        if max == 0 {
+            0.0
+        } else {
+            used as f64 / max as f64
+        }
+    };
+    let fullness = fullness(normal_weight.ref_time, max_normal_weight.ref_time).max(fullness(
+        normal_weight.proof_size,
+        max_normal_weight.proof_size,
+    ));
+
+    let block = rpc(url, "chain_getBlock", [&hash]).await?;
+    let mut tips = Vec::new();
+    let mut signed_extrinsics = 0;
+    for extrinsic_hex in block["block"]["extrinsics"]
+        .as_array()
+        .into_iter()
+        .flatten()
+    {
+        let extrinsic = hex::decode(
+            extrinsic_hex
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("extrinsic should be a hex string"))?
+                .trim_start_matches("0x"),
+        )?;
+        // Extrinsics signed in a format we can't decode still took up room in the block, but
+        // we don't know what tip they paid, so we skip them:
+        match decode_signed_details(&extrinsic) {
+            ExtrinsicSigner::Signed(details) => {
+                signed_extrinsics += 1;
+                tips.push(details.tip);
+            }
+            ExtrinsicSigner::Undecodable(_) => signed_extrinsics += 1,
+            ExtrinsicSigner::Unsigned => {}
+        }
+    }
+
+    Ok(BlockCongestion {
+        number,
+        normal_weight,
+        fullness,
+        signed_extrinsics,
+        tips,
+    })
+}
+
+/// The most weight that normal extrinsics can use in a block. This comes from the
+/// `System.BlockWeights` constant; the normal class has its own limit (`max_total`),
+/// and if it doesn't, the limit for the whole block applies.
+pub fn max_normal_weight(metadata: &RuntimeMetadataV14) -> anyhow::Result<Weight> {
+    let block_weights = constant(metadata, "System", "BlockWeights")?;
+
+    match &block_weights["per_class"]["normal"]["max_total"] {
+        Value::Object(option) if option.contains_key("Some") => Weight::from_json(&option["Some"]),
+        _ => Weight::from_json(&block_weights["max_block"]),
+    }
+}
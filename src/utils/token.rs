@@ -0,0 +1,183 @@
+use serde_json::Value;
+
+use crate::{rpc, RpcTransport};
+
+/// Details about a chain's native token, from the "system_properties" RPC method.
+///
+/// Balances are stored as whole numbers of the smallest unit (on Polkadot, "plancks"); a
+/// chain with 10 token decimals, like Polkadot, has 10^10 plancks in 1 DOT.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenInfo {
+    pub decimals: u8,
+    pub symbol: String,
+    /// The address format used by this chain (see example 04).
+    pub ss58_format: u16,
+}
+
+/// Fetch the token details from the node. Chains with several tokens give back a list of
+/// decimals and symbols; the first is the native token. If the chain doesn't say, we fall
+/// back to no decimals, a symbol of "UNIT" and the generic substrate address format (42).
+pub async fn get_token_info(url: &(impl RpcTransport + ?Sized)) -> anyhow::Result<TokenInfo> {
+    let properties = rpc(url, "system_properties", ()).await?;
+
+    let first = |value: &Value| match value {
+        Value::Array(values) => values.first().cloned(),
+        Value::Null => None,
+        other => Some(other.clone()),
+    };
+
+    let decimals = match first(&properties["tokenDecimals"]) {
+        Some(value) => value
+            .as_u64()
+            .filter(|&d| d <= 38)
+            .ok_or_else(|| anyhow::anyhow!("invalid tokenDecimals {}", value))?
+            as u8,
+        None => 0,
+    };
+    let symbol = match first(&properties["tokenSymbol"]) {
+        Some(Value::String(symbol)) => symbol,
+        Some(value) => anyhow::bail!("invalid tokenSymbol {}", value),
+        None => "UNIT".to_string(),
+    };
+    let ss58_format = match &properties["ss58Format"] {
+        Value::Null => 42,
+        value => value
+            .as_u64()
+            .filter(|&f| f < 16384)
+            .ok_or_else(|| anyhow::anyhow!("invalid ss58Format {}", value))?
+            as u16,
+    };
+
+    Ok(TokenInfo {
+        decimals,
+        symbol,
+        ss58_format,
+    })
+}
+
+impl TokenInfo {
+    /// Format an amount given in the smallest unit (plancks) as something like "1.5 DOT".
+    pub fn format_amount(&self, plancks: u128) -> String {
+        let unit = 10u128.pow(self.decimals as u32);
+        let whole = plancks / unit;
+        let fraction = plancks % unit;
+
+        if fraction == 0 {
+            format!("{} {}", whole, self.symbol)
+        } else {
+            let fraction = format!("{:0width$}", fraction, width = self.decimals as usize);
+            format!(
+                "{}.{} {}",
+                whole,
+                fraction.trim_end_matches('0'),
+                self.symbol
+            )
+        }
+    }
+
+    /// Parse an amount like "1.5 DOT", "1.5DOT" or "1.5" into the smallest unit (plancks). If a
+    /// symbol is given, it must match this token's symbol.
+    pub fn parse_amount(&self, amount: &str) -> anyhow::Result<u128> {
+        let amount = amount.trim();
+        let (number, symbol) = amount.split_at(
+            amount
+                .find(|c: char| !c.is_ascii_digit() && c != '.')
+                .unwrap_or(amount.len()),
+        );
+        let symbol = symbol.trim_start();
+        if symbol.contains(char::is_whitespace) {
+            anyhow::bail!(
+                "expected an amount like '1.5 {}', got '{}'",
+                self.symbol,
+                amount
+            );
+        }
+        if !symbol.is_empty() && !symbol.eq_ignore_ascii_case(&self.symbol) {
+            anyhow::bail!(
+                "amount '{}' is in {}, but this chain's token is {}",
+                amount,
+                symbol,
+                self.symbol
+            );
+        }
+
+        let (whole, fraction) = match number.split_once('.') {
+            Some((whole, fraction)) => (whole, fraction),
+            None => (number, ""),
+        };
+        if fraction.len() > self.decimals as usize {
+            anyhow::bail!(
+                "amount '{}' has more than {} decimal places",
+                amount,
+                self.decimals
+            );
+        }
+        let digits_ok = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+        if (whole.is_empty() && fraction.is_empty()) || !digits_ok(whole) || !digits_ok(fraction) {
+            anyhow::bail!("'{}' is not a valid amount", amount);
+        }
+
+        let too_big = || anyhow::anyhow!("amount '{}' is too large", amount);
+        let unit = 10u128.pow(self.decimals as u32);
+        let whole: u128 = if whole.is_empty() {
+            0
+        } else {
+            whole.parse().map_err(|_| too_big())?
+        };
+        let fraction: u128 = if fraction.is_empty() {
+            0
+        } else {
+            fraction.parse::<u128>().map_err(|_| too_big())?
+                * 10u128.pow((self.decimals as usize - fraction.len()) as u32)
+        };
+
+        whole
+            .checked_mul(unit)
+            .and_then(|n| n.checked_add(fraction))
+            .ok_or_else(too_big)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dot() -> TokenInfo {
+        TokenInfo {
+            decimals: 10,
+            symbol: "DOT".to_string(),
+            ss58_format: 0,
+        }
+    }
+
+    #[test]
+    fn parses_amounts_with_or_without_a_symbol() {
+        let token = dot();
+        for amount in ["1.2", "1.2 DOT", "1.2DOT", "1.2dot", " 1.2 DOT "] {
+            assert_eq!(
+                token.parse_amount(amount).unwrap(),
+                12_000_000_000,
+                "{}",
+                amount
+            );
+        }
+        assert_eq!(token.parse_amount(".5").unwrap(), 5_000_000_000);
+        assert_eq!(token.parse_amount("3").unwrap(), 30_000_000_000);
+    }
+
+    #[test]
+    fn rejects_invalid_amounts() {
+        let token = dot();
+        for amount in [
+            "",
+            "DOT",
+            "1.2 KSM",
+            "1.2KSM",
+            "1.2 DOT DOT",
+            "1.2.3",
+            "0.00000000001",
+        ] {
+            assert!(token.parse_amount(amount).is_err(), "{}", amount);
+        }
+    }
+}
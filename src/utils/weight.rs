@@ -0,0 +1,98 @@
+use parity_scale_codec::{Compact, Encode};
+use scale_info::{PortableRegistry, TypeDef, TypeDefPrimitive};
+use serde_json::Value;
+use std::convert::TryInto;
+
+use crate::decode::u128_from_json;
+
+/// A weight, describing how much time (and, on newer runtimes, how much proof
+/// size) something is expected to take to execute.
+///
+/// Older runtimes represent weight as a single `u64` (ref time only), while newer ones
+/// use a struct of `{ ref_time, proof_size }`. Calls which take a weight as an argument
+/// (like `Multisig.as_multi`) need it encoded in whichever shape the runtime expects,
+/// which we can work out from the metadata.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Weight {
+    pub ref_time: u64,
+    pub proof_size: u64,
+}
+
+impl Weight {
+    pub fn new(ref_time: u64, proof_size: u64) -> Self {
+        Weight {
+            ref_time,
+            proof_size,
+        }
+    }
+
+    /// SCALE encode the weight according to the weight type with the given ID
+    /// in the metadata.
+    pub fn encode_as(&self, types: &PortableRegistry, type_id: u32) -> anyhow::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        self.encode_to_type(types, type_id, None, &mut out)?;
+        Ok(out)
+    }
+
+    fn encode_to_type(
+        &self,
+        types: &PortableRegistry,
+        type_id: u32,
+        field_name: Option<&str>,
+        out: &mut Vec<u8>,
+    ) -> anyhow::Result<()> {
+        let ty = types
+            .resolve(type_id)
+            .ok_or_else(|| anyhow::anyhow!("type {} not found in the metadata", type_id))?;
+
+        // Which number are we encoding? A bare u64 (legacy weights) is the ref time.
+        let value = match field_name {
+            Some("proof_size") => self.proof_size,
+            _ => self.ref_time,
+        };
+
+        match ty.type_def() {
+            TypeDef::Primitive(TypeDefPrimitive::U64) => value.encode_to(out),
+            TypeDef::Compact(_) => Compact(value).encode_to(out),
+            TypeDef::Composite(composite) => {
+                for field in composite.fields() {
+                    let name = field.name().map(|n| n.as_str()).or(field_name);
+                    self.encode_to_type(types, field.ty().id(), name, out)?;
+                }
+            }
+            _ => anyhow::bail!("type {} does not look like a weight", type_id),
+        }
+        Ok(())
+    }
+}
+
+impl Weight {
+    /// Read a weight from JSON. Legacy weights are a single number, and newer ones are an
+    /// object with `ref_time` and `proof_size` fields. These are snake case when we've decoded
+    /// them ourselves (for instance, from the `dispatch_info` of a `System.ExtrinsicSuccess`
+    /// event), and camel case ("refTime" and "proofSize") when the node hands them back from
+    /// RPC methods like "payment_queryInfo".
+    pub fn from_json(value: &Value) -> anyhow::Result<Weight> {
+        let to_u64 = |v: &Value| -> anyhow::Result<u64> {
+            u128_from_json(v)?
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("weight {} does not fit in a u64", v))
+        };
+        match value {
+            Value::Object(fields) => {
+                let field =
+                    |snake: &str, camel: &str| fields.get(snake).or_else(|| fields.get(camel));
+                let ref_time = field("ref_time", "refTime")
+                    .ok_or_else(|| anyhow::anyhow!("weight {} has no ref time", value))?;
+                Ok(Weight::new(
+                    to_u64(ref_time)?,
+                    field("proof_size", "proofSize")
+                        .map(to_u64)
+                        .transpose()?
+                        .unwrap_or(0),
+                ))
+            }
+            other => Ok(Weight::new(to_u64(other)?, 0)),
+        }
+    }
+}
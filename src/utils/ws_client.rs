@@ -1,4 +1,20 @@
-use jsonrpsee::async_client::Client;
+use jsonrpsee::{
+    async_client::Client,
+    core::client::{ClientT, Subscription, SubscriptionClientT},
+    rpc_params,
+    types::{error::CallError, ParamsSer},
+};
+use serde_json::{json, Value};
+
+use crate::RpcTransport;
+
+pub const LOCALHOST_WS_URL: &str = "ws://localhost:9944";
+
+/// The WS URL of the node that the examples talk to. This is [`LOCALHOST_WS_URL`], unless the
+/// `POLKADOT_WS_URL` environment variable says otherwise.
+pub fn ws_url() -> String {
+    std::env::var("POLKADOT_WS_URL").unwrap_or_else(|_| LOCALHOST_WS_URL.to_string())
+}
 
 /// Build an WebServer client for interacting with the node's RPC.
 pub async fn ws_client(url: &str) -> anyhow::Result<Client> {
@@ -12,3 +28,60 @@ pub async fn ws_client(url: &str) -> anyhow::Result<Client> {
         .max_notifs_per_subscription(4096)
         .build_with_tokio(sender, receiver))
 }
+
+/// Requests can be made over WS too, so a WS client can be handed to any of the helpers in
+/// place of a URL.
+#[jsonrpsee::core::async_trait]
+impl RpcTransport for Client {
+    async fn request(&self, method: &str, params: Value) -> anyhow::Result<Value> {
+        let params = match params {
+            Value::Null => None,
+            Value::Array(params) => Some(ParamsSer::Array(params)),
+            other => anyhow::bail!("params should be given as an array, not {}", other),
+        };
+        // The client hands back the "result", or an error if the node responded with one, so
+        // we put the response back together:
+        match ClientT::request::<Value>(self, method, params).await {
+            Ok(result) => Ok(json!({ "jsonrpc": "2.0", "id": 1, "result": result })),
+            Err(jsonrpsee::core::Error::Call(CallError::Custom(error))) => {
+                Ok(json!({ "jsonrpc": "2.0", "id": 1, "error": error }))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Submit an extrinsic using "author_submitAndWatchExtrinsic", and wait until it's been
+/// included in a block, handing back the hash of that block. Unlike "author_submitExtrinsic",
+/// this subscription tells us how our transaction is getting on, so it has to go over WS.
+pub async fn submit_and_watch(client: &Client, extrinsic_hex: &str) -> anyhow::Result<String> {
+    let mut sub: Subscription<Value> = client
+        .subscribe(
+            "author_submitAndWatchExtrinsic",
+            rpc_params![extrinsic_hex],
+            "author_unwatchExtrinsic",
+        )
+        .await?;
+
+    // Statuses look like "ready", or { "inBlock": "0x1234.." }:
+    while let Some(status) = sub.next().await {
+        let status = status?;
+        if let Some(block_hash) = status["inBlock"].as_str() {
+            return Ok(block_hash.to_string());
+        }
+        if let Some(block_hash) = status["finalized"].as_str() {
+            return Ok(block_hash.to_string());
+        }
+        match status.as_str() {
+            Some("dropped") | Some("invalid") => {
+                anyhow::bail!("transaction was not included: {}", status)
+            }
+            _ if status.get("usurped").is_some() => {
+                anyhow::bail!("transaction was usurped: {}", status)
+            }
+            _ => {}
+        }
+    }
+
+    anyhow::bail!("subscription ended before the transaction was included in a block")
+}
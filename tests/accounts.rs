@@ -0,0 +1,50 @@
+//! Run the account helpers against a mock node.
+
+mod common;
+
+use parity_scale_codec::Encode;
+use sp_keyring::AccountKeyring;
+use utils::{get_account_balances, get_metadata, get_storage_value};
+
+#[tokio::test]
+async fn fetches_account_balances() {
+    let node = common::start_node().await;
+    let metadata = get_metadata(&node.http_url).await.unwrap();
+    let alice = AccountKeyring::Alice.to_account_id();
+
+    let balances = get_account_balances(&node.http_url, &metadata, &alice, None)
+        .await
+        .unwrap();
+    assert_eq!(balances.free, 10_000_000_000_000);
+    assert_eq!(balances.reserved, 2_000_000_000_000);
+    assert_eq!(balances.frozen, 5_000_000_000_000);
+    assert!(balances.frozen_includes_reserved);
+    assert!(balances.locks.is_empty());
+    assert!(balances.holds.is_empty());
+
+    // Reserved funds count towards the frozen amount, so only 3 of the 5 frozen DOT
+    // come out of the free balance:
+    assert_eq!(balances.spendable(), 7_000_000_000_000);
+}
+
+#[tokio::test]
+async fn falls_back_to_the_default_value() {
+    let node = common::start_node().await;
+    let metadata = get_metadata(&node.http_url).await.unwrap();
+    let bob = AccountKeyring::Bob.to_account_id();
+
+    // Nothing is stored for Bob, so we get the default (empty) account back:
+    let account = get_storage_value(
+        &node.http_url,
+        &metadata,
+        "System",
+        "Account",
+        &[bob.encode()],
+        None,
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(account["nonce"], 0);
+    assert_eq!(account["data"]["free"], "0");
+}
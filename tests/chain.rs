@@ -0,0 +1,66 @@
+//! Run the helpers for looking up chain details against a mock node.
+
+mod common;
+
+use sp_keyring::AccountKeyring;
+use utils::{
+    call_index, get_block_hash, get_genesis_hash, get_metadata, get_runtime_version,
+    get_ss58_prefix, get_token_info, NonceManager,
+};
+
+#[tokio::test]
+async fn fetches_chain_details() {
+    let node = common::start_node().await;
+
+    let runtime_version = get_runtime_version(&node.http_url).await.unwrap();
+    assert_eq!(runtime_version.spec_name.to_string(), "polkadot");
+    assert_eq!(runtime_version.spec_version, 1001002);
+    assert_eq!(runtime_version.transaction_version, 25);
+
+    let genesis_hash = get_genesis_hash(&node.http_url).await.unwrap();
+    assert_eq!(
+        format!("{:?}", genesis_hash),
+        "0x91b171bb158e2d3848fa23a9f1c25182fb8e20313b2c1eb49219da7a70ce90c3"
+    );
+
+    let token = get_token_info(&node.http_url).await.unwrap();
+    assert_eq!(token.symbol, "DOT");
+    assert_eq!(token.decimals, 10);
+    assert_eq!(token.format_amount(15_000_000_000), "1.5 DOT");
+
+    assert_eq!(
+        get_block_hash(&node.http_url, "100").await.unwrap(),
+        "0x6e43d41109ab07611c050b08c468f11f865e6b0679350ff6e78c11b5ba413b35"
+    );
+}
+
+#[tokio::test]
+async fn looks_things_up_in_the_metadata() {
+    let node = common::start_node().await;
+    let metadata = get_metadata(&node.http_url).await.unwrap();
+
+    // There's no `Balances.transfer` any more; `transfer_allow_death` took its place:
+    assert_eq!(
+        call_index(&metadata, "Balances", "transfer_allow_death").unwrap(),
+        (5, 0)
+    );
+    assert_eq!(
+        call_index(&metadata, "Balances", "transfer_keep_alive").unwrap(),
+        (5, 3)
+    );
+    assert!(call_index(&metadata, "Balances", "transfer").is_err());
+
+    assert_eq!(get_ss58_prefix(&node.http_url, &metadata).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn hands_out_nonces_from_the_node() {
+    let node = common::start_node().await;
+    let nonces = NonceManager::new(&node.http_url);
+    let alice = AccountKeyring::Alice.to_account_id();
+
+    assert_eq!(nonces.next(&alice).await.unwrap(), 7);
+    assert_eq!(nonces.next(&alice).await.unwrap(), 8);
+    nonces.release(&alice, 7).await;
+    assert_eq!(nonces.next(&alice).await.unwrap(), 7);
+}
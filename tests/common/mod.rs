@@ -0,0 +1,23 @@
+// Each test file uses a different part of this:
+#![allow(dead_code)]
+
+use utils::{load_fixtures, metadata_fixture, MockNode};
+
+/// Responses from a Polkadot node, for the calls that the utils make. See the fixtures
+/// README for where they came from.
+pub const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/polkadot.json");
+
+/// V14 metadata from the same Polkadot runtime.
+pub const METADATA: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/polkadot_metadata.scale"
+);
+
+/// Start a mock node serving the Polkadot fixtures on any free ports.
+pub async fn start_node() -> MockNode {
+    let mut fixtures = load_fixtures(FIXTURES).expect("fixtures should load");
+    fixtures.push(metadata_fixture(METADATA).expect("metadata should load"));
+    MockNode::start(fixtures)
+        .await
+        .expect("mock node should start")
+}
@@ -0,0 +1,71 @@
+//! Check the constant helpers against the fixture metadata.
+
+mod common;
+
+use serde_json::json;
+use utils::{
+    block_hash_count, constant, constant_value, constants, existential_deposit, load_metadata,
+    max_exposure_page_size, max_nominators_rewarded,
+};
+
+#[test]
+fn decodes_every_constant() {
+    let metadata = load_metadata(common::METADATA).unwrap();
+    let constants = constants(&metadata).unwrap();
+
+    // System comes first in the metadata, and so its constants come first too:
+    assert_eq!(constants[0].pallet, "System");
+
+    let ed = constants
+        .iter()
+        .find(|c| c.pallet == "Balances" && c.name == "ExistentialDeposit")
+        .expect("Balances has an existential deposit");
+    // u128s are decoded to strings so as not to lose precision:
+    assert_eq!(ed.value, json!("10000000000"));
+    assert_eq!(
+        ed.docs[0].trim(),
+        "The minimum amount required to keep an account open. MUST BE GREATER THAN ZERO!"
+    );
+
+    let sessions = constants
+        .iter()
+        .find(|c| c.pallet == "Staking" && c.name == "SessionsPerEra")
+        .expect("Staking has sessions per era");
+    assert_eq!(sessions.value, json!(6));
+}
+
+#[test]
+fn looks_up_single_constants() {
+    let metadata = load_metadata(common::METADATA).unwrap();
+
+    assert_eq!(
+        constant_value(&metadata, "System", "BlockHashCount").unwrap(),
+        Some(json!(4096))
+    );
+    // A missing constant isn't an error, but a missing pallet is:
+    assert_eq!(
+        constant_value(&metadata, "Staking", "MaxExposurePageSize").unwrap(),
+        None
+    );
+    assert!(constant_value(&metadata, "NoSuchPallet", "BlockHashCount").is_err());
+
+    // ..unless we expect the constant to be there:
+    assert_eq!(
+        constant(&metadata, "Staking", "MaxExposurePageSize")
+            .unwrap_err()
+            .to_string(),
+        "constant 'Staking.MaxExposurePageSize' not found"
+    );
+}
+
+#[test]
+fn typed_getters() {
+    let metadata = load_metadata(common::METADATA).unwrap();
+
+    assert_eq!(existential_deposit(&metadata).unwrap(), 10_000_000_000);
+    assert_eq!(block_hash_count(&metadata).unwrap(), 4096);
+
+    // The fixture runtime predates paged staking rewards:
+    assert_eq!(max_nominators_rewarded(&metadata).unwrap(), Some(512));
+    assert_eq!(max_exposure_page_size(&metadata).unwrap(), None);
+}
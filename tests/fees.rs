@@ -0,0 +1,104 @@
+//! Run the fee and dry run helpers against a mock node.
+
+mod common;
+
+use parity_scale_codec::Compact;
+use sp_keyring::AccountKeyring;
+use sp_runtime::MultiAddress;
+use utils::{
+    block_fees, dry_run, estimate_fees, get_metadata, weight_fees, ApplyExtrinsicResult,
+    InclusionFee, TransactionValidityError, Weight, FEE_MULTIPLIER_ONE,
+};
+
+#[tokio::test]
+async fn estimates_fees() {
+    let node = common::start_node().await;
+
+    let call = (
+        5u8,
+        3u8,
+        MultiAddress::Id::<_, u32>(AccountKeyring::Bob.to_account_id()),
+        Compact(125_000_000_000u128),
+    );
+    let alice = AccountKeyring::Alice.to_account_id();
+    let estimate = estimate_fees(&node.http_url, &alice, &call, 0)
+        .await
+        .unwrap();
+
+    assert_eq!(estimate.weight, Weight::new(145_879_000, 3593));
+    assert_eq!(estimate.class, "Normal");
+    assert_eq!(estimate.partial_fee, 156_230_721);
+    assert_eq!(
+        estimate.inclusion_fee,
+        Some(InclusionFee {
+            base_fee: 100_000_000,
+            len_fee: 1_430_000,
+            adjusted_weight_fee: 54_800_721,
+        })
+    );
+
+    let fees = weight_fees(&node.http_url, estimate.weight, None)
+        .await
+        .unwrap();
+    assert_eq!(fees.total, 54_800_721);
+}
+
+#[tokio::test]
+async fn finds_the_fees_paid_in_a_block() {
+    let node = common::start_node().await;
+    let fees = block_fees(&node.http_url, 100).await.unwrap();
+
+    // The block has a timestamp inherent, a transfer signed by Alice, and a transfer
+    // whose signer is given by account index, which we can't decode:
+    assert_eq!(fees.extrinsics.len(), 1);
+    assert_eq!(fees.extrinsics[0].index, 1);
+    assert_eq!(fees.extrinsics[0].tip, 1_000_000);
+    assert_eq!(fees.extrinsics[0].partial_fee(), 156_230_721);
+    assert_eq!(fees.skipped_extrinsics, 1);
+    assert_eq!(fees.next_fee_multiplier, FEE_MULTIPLIER_ONE / 10 * 12);
+}
+
+#[tokio::test]
+async fn dry_runs_extrinsics() {
+    let node = common::start_node().await;
+    let metadata = get_metadata(&node.http_url).await.unwrap();
+
+    let result = dry_run(&node.http_url, &metadata, &[0x04, 0x00], None)
+        .await
+        .unwrap();
+    assert_eq!(result, ApplyExtrinsicResult::Success);
+}
+
+#[tokio::test]
+async fn decodes_failed_dry_runs() {
+    let node = common::start_node().await;
+    let metadata = get_metadata(&node.http_url).await.unwrap();
+
+    // The transaction pool would reject this one:
+    let result = dry_run(&node.http_url, &metadata, &[0x04, 0x01], None)
+        .await
+        .unwrap();
+    assert_eq!(
+        result,
+        ApplyExtrinsicResult::Invalid(TransactionValidityError::Invalid("Stale".to_string()))
+    );
+
+    // This one would be included in a block, but the call fails with a pallet error, which we
+    // find the name and docs of in the metadata:
+    let result = dry_run(&node.http_url, &metadata, &[0x04, 0x02], None)
+        .await
+        .unwrap();
+    let error = match result {
+        ApplyExtrinsicResult::DispatchFailed(error) => error,
+        other => panic!("expected a dispatch error, but got {:?}", other),
+    };
+    assert_eq!(error.name, "Balances.InsufficientBalance");
+    assert_eq!(
+        error.docs,
+        vec!["Balance too low to send value.".to_string()]
+    );
+    assert_eq!(
+        error.to_string(),
+        "Balances.InsufficientBalance (Balance too low to send value.)"
+    );
+}
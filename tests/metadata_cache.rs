@@ -0,0 +1,116 @@
+//! Check that `MetadataCache` keeps metadata on disk by genesis hash and `spec_version`, and
+//! follows runtime upgrades that it's told about.
+
+mod common;
+
+use frame_metadata::RuntimeMetadataPrefixed;
+use parity_scale_codec::Encode;
+use serde_json::json;
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+use utils::{
+    load_fixtures, load_metadata, metadata_fixture, ws_client, Fixture, MetadataCache, MockNode,
+};
+
+const GENESIS_HASH: &str = "91b171bb158e2d3848fa23a9f1c25182fb8e20313b2c1eb49219da7a70ce90c3";
+const SPEC_VERSION: u32 = 1001002;
+
+/// An empty directory for a test to keep metadata in.
+fn cache_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("metadata-cache-{}-{}", test, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+/// A mock node which answers everything but "state_getMetadata", so that metadata can only
+/// come from the cache.
+async fn node_without_metadata() -> MockNode {
+    MockNode::start(load_fixtures(common::FIXTURES).unwrap())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn keeps_metadata_on_disk() {
+    let dir = cache_dir("on-disk");
+    let path = dir.join(format!("{}-{}.scale", GENESIS_HASH, SPEC_VERSION));
+
+    // Nothing is cached yet, so without "state_getMetadata" there's no metadata to be had:
+    let node = node_without_metadata().await;
+    assert!(MetadataCache::with_dir(&node.http_url, &dir)
+        .metadata()
+        .await
+        .is_err());
+    assert!(!path.exists());
+
+    // Fetching it from a node that has it saves it, under the chain and runtime it's from:
+    let node_with_metadata = common::start_node().await;
+    let fetched = MetadataCache::with_dir(&node_with_metadata.http_url, &dir)
+        .metadata()
+        .await
+        .unwrap();
+    assert!(path.exists());
+
+    // And so now a fresh cache can load it without asking the node for it:
+    let loaded = MetadataCache::with_dir(&node.http_url, &dir)
+        .metadata()
+        .await
+        .unwrap();
+    assert_eq!(loaded, fetched);
+    assert_eq!(*loaded, load_metadata(common::METADATA).unwrap());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn follows_runtime_upgrades() {
+    let dir = cache_dir("upgrades");
+
+    // Pretend that the upgraded runtime has one pallet fewer, and that its metadata is in the
+    // cache already (the node only has the metadata from before the upgrade):
+    let mut upgraded = load_metadata(common::METADATA).unwrap();
+    upgraded.pallets.pop();
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join(format!("{}-{}.scale", GENESIS_HASH, SPEC_VERSION + 1)),
+        RuntimeMetadataPrefixed::from(upgraded.clone()).encode(),
+    )
+    .unwrap();
+
+    let upgrade: Fixture = serde_json::from_value(json!({
+        "method": "state_subscribeRuntimeVersion",
+        "notifications": [
+            { "specName": "polkadot", "specVersion": SPEC_VERSION },
+            { "specName": "polkadot", "specVersion": SPEC_VERSION + 1 },
+        ],
+    }))
+    .unwrap();
+    let mut fixtures = load_fixtures(common::FIXTURES).unwrap();
+    fixtures.push(upgrade);
+    fixtures.push(metadata_fixture(common::METADATA).unwrap());
+    let node = MockNode::start(fixtures).await.unwrap();
+
+    // Before we're watching, the node says which runtime it's on (which is the old one):
+    let cache = MetadataCache::with_dir(&node.http_url, &dir);
+    let before = cache.metadata().await.unwrap();
+    assert_eq!(*before, load_metadata(common::METADATA).unwrap());
+
+    // Once the upgrade is announced, we should switch over to the new runtime's metadata:
+    let client = ws_client(&node.ws_url).await.unwrap();
+    cache.watch_upgrades(&client).await.unwrap();
+    let started = Instant::now();
+    loop {
+        if *cache.metadata().await.unwrap() == upgraded {
+            break;
+        }
+        assert!(
+            started.elapsed() < Duration::from_secs(5),
+            "the upgrade was not seen"
+        );
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
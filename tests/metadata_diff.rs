@@ -0,0 +1,124 @@
+//! Check `diff_metadata` against the fixture metadata, and against copies of it with the sorts
+//! of changes that a runtime upgrade can make.
+
+mod common;
+
+use frame_metadata::{RuntimeMetadataV14, StorageEntryType, StorageHasher};
+use serde_json::Value;
+use utils::{diff_metadata, load_metadata, pallet, MetadataChange};
+
+/// Edit the variants (the calls) of a pallet's `Call` enum. The type registry can't be changed
+/// in place, so this goes via its JSON form.
+fn edit_calls(
+    metadata: &mut RuntimeMetadataV14,
+    pallet_name: &str,
+    edit: impl FnOnce(&mut Vec<Value>),
+) {
+    let calls_ty = pallet(metadata, pallet_name)
+        .unwrap()
+        .calls
+        .as_ref()
+        .unwrap()
+        .ty
+        .id();
+    let mut types = serde_json::to_value(&metadata.types).unwrap();
+    let calls = types["types"]
+        .as_array_mut()
+        .unwrap()
+        .iter_mut()
+        .find(|ty| ty["id"] == calls_ty)
+        .unwrap();
+    edit(
+        calls["type"]["def"]["variant"]["variants"]
+            .as_array_mut()
+            .unwrap(),
+    );
+    metadata.types = serde_json::from_value(types).unwrap();
+}
+
+fn call<'a>(calls: &'a mut [Value], name: &str) -> &'a mut Value {
+    calls.iter_mut().find(|call| call["name"] == name).unwrap()
+}
+
+fn find_change<'a>(
+    changes: &'a [MetadataChange],
+    pallet: &str,
+    prefix: &str,
+) -> &'a MetadataChange {
+    changes
+        .iter()
+        .find(|c| c.pallet.as_deref() == Some(pallet) && c.description.starts_with(prefix))
+        .unwrap_or_else(|| panic!("no '{}' change to {} in {:#?}", prefix, pallet, changes))
+}
+
+#[test]
+fn the_same_metadata_has_no_changes() {
+    let metadata = load_metadata(common::METADATA).unwrap();
+    assert_eq!(diff_metadata(&metadata, &metadata), vec![]);
+
+    // The type IDs don't matter, only the shape of the types; a fresh copy is the same too:
+    let copy = load_metadata(common::METADATA).unwrap();
+    assert_eq!(diff_metadata(&metadata, &copy), vec![]);
+}
+
+#[test]
+fn breaking_changes_are_flagged() {
+    let old = load_metadata(common::METADATA).unwrap();
+    let mut new = old.clone();
+
+    edit_calls(&mut new, "Balances", |calls| {
+        // Move transfer_all from index 4:
+        call(calls, "transfer_all")["index"] = 40.into();
+        // Give transfer_keep_alive's value the type of transfer_all's keep_alive (a bool):
+        let bool_ty = call(calls, "transfer_all")["fields"][1]["type"].clone();
+        call(calls, "transfer_keep_alive")["fields"][1]["type"] = bool_ty;
+    });
+
+    // Hash the keys of System.Account differently:
+    let system = new.pallets.iter_mut().find(|p| p.name == "System").unwrap();
+    let account = system
+        .storage
+        .as_mut()
+        .unwrap()
+        .entries
+        .iter_mut()
+        .find(|e| e.name == "Account")
+        .unwrap();
+    match &mut account.ty {
+        StorageEntryType::Map { hashers, .. } => hashers[0] = StorageHasher::Twox64Concat,
+        StorageEntryType::Plain(_) => panic!("System.Account should be a map"),
+    }
+
+    let changes = diff_metadata(&old, &new);
+
+    let moved = find_change(&changes, "Balances", "call transfer_all index");
+    assert_eq!(
+        moved.description,
+        "call transfer_all index changed from 4 to 40"
+    );
+    assert!(moved.breaking);
+
+    let retyped = find_change(&changes, "Balances", "call transfer_keep_alive arguments");
+    assert!(
+        retyped.description.ends_with(", value: bool)"),
+        "{}",
+        retyped.description
+    );
+    assert!(retyped.breaking);
+
+    let rehashed = find_change(&changes, "System", "storage Account changed");
+    assert!(
+        rehashed
+            .description
+            .contains("hashed with Blake2_128Concat to map")
+            && rehashed.description.ends_with("hashed with Twox64Concat"),
+        "{}",
+        rehashed.description
+    );
+    assert!(rehashed.breaking);
+
+    // The calls that weren't touched haven't changed:
+    assert!(!changes
+        .iter()
+        .any(|c| c.description.contains("transfer_allow_death")));
+}
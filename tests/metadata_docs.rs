@@ -0,0 +1,61 @@
+//! Render the fixture metadata's docs, and check what ends up in them.
+
+mod common;
+
+use utils::{load_metadata, metadata_docs, DocsFormat};
+
+#[test]
+fn renders_markdown() {
+    let metadata = load_metadata(common::METADATA).unwrap();
+    let docs = metadata_docs(&metadata, DocsFormat::Markdown);
+
+    assert!(docs.starts_with("# Runtime metadata\n\n"));
+    assert!(docs.contains("- [Balances (index 5)](#balances)\n"));
+    assert!(docs.contains(
+        "#### transfer_keep_alive\n\n\
+         ```text\n\
+         transfer_keep_alive(dest: MultiAddress<AccountId32, ()>, value: Compact<u128>)\n\
+         ```\n\n\
+         Call index `3` (encoded starting with `0x0503`).\n\n"
+    ));
+}
+
+#[test]
+fn renders_html() {
+    let metadata = load_metadata(common::METADATA).unwrap();
+    let docs = metadata_docs(&metadata, DocsFormat::Html);
+
+    assert!(docs.starts_with("<!DOCTYPE html>\n"));
+    assert!(docs.ends_with("</body>\n</html>\n"));
+    assert!(docs.contains("<li><a href=\"#balances\">Balances (index 5)</a></li>\n"));
+    assert!(docs.contains(
+        "<h4>transfer_keep_alive</h4>\n\
+         <pre><code>transfer_keep_alive(dest: MultiAddress&lt;AccountId32, ()&gt;, \
+         value: Compact&lt;u128&gt;)</code></pre>\n\
+         <p>Call index <code>3</code> (encoded starting with <code>0x0503</code>).</p>\n"
+    ));
+}
+
+#[test]
+fn escapes_angle_brackets_in_doc_comments() {
+    let mut metadata = load_metadata(common::METADATA).unwrap();
+    let balances = metadata
+        .pallets
+        .iter_mut()
+        .find(|p| p.name == "Balances")
+        .unwrap();
+    let ed = balances
+        .constants
+        .iter_mut()
+        .find(|c| c.name == "ExistentialDeposit")
+        .unwrap();
+    ed.docs = vec![" Must be < 2^128, like any `Vec<u8>` length.".to_string()];
+
+    // Markdown would take "<" for the start of a tag, except in code:
+    let markdown = metadata_docs(&metadata, DocsFormat::Markdown);
+    assert!(markdown.contains("\nMust be &lt; 2^128, like any `Vec<u8>` length.\n"));
+
+    let html = metadata_docs(&metadata, DocsFormat::Html);
+    let expected = "<p>Must be &lt; 2^128, like any <code>Vec&lt;u8&gt;</code> length.</p>";
+    assert!(html.contains(expected));
+}
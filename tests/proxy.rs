@@ -0,0 +1,40 @@
+//! Check the proxy helpers against the fixture metadata.
+
+mod common;
+
+use sp_keyring::AccountKeyring;
+use utils::{format_address, load_metadata, proxy_type_index, pure_proxy_account_id};
+
+#[test]
+fn derives_pure_proxy_account_ids() {
+    let metadata = load_metadata(common::METADATA).unwrap();
+    assert_eq!(proxy_type_index(&metadata, "Any").unwrap(), 0);
+
+    // The pure proxy that Alice would create with `create_pure(Any, 0, 0)` as the extrinsic
+    // at index 1 of block 100 (the `Proxy.PureCreated` event would name this account):
+    let pure = pure_proxy_account_id(
+        &metadata,
+        &AccountKeyring::Alice.to_account_id(),
+        "Any",
+        0,
+        100,
+        1,
+    )
+    .unwrap();
+    assert_eq!(
+        format_address(&pure, 42),
+        "5CXe15GjPv6EVMyXnHHRUL1nF5Fj2vCYX746y8SsgXnALh7Z"
+    );
+
+    // A different disambiguation index gives a different account:
+    let other = pure_proxy_account_id(
+        &metadata,
+        &AccountKeyring::Alice.to_account_id(),
+        "Any",
+        1,
+        100,
+        1,
+    )
+    .unwrap();
+    assert_ne!(pure, other);
+}
@@ -0,0 +1,33 @@
+//! Record a session with a mock node, and replay it.
+
+mod common;
+
+use utils::{get_runtime_version, get_token_info, load_fixtures, MockNode, Recorder};
+
+#[tokio::test]
+async fn records_calls_made_by_the_helpers() {
+    let node = common::start_node().await;
+    let path = std::env::temp_dir().join(format!("recording-{}.json", std::process::id()));
+
+    let recorder = Recorder::create(&path).unwrap();
+    let wrapped = recorder.wrap(node.http_url.as_str());
+    let token = get_token_info(&wrapped).await.unwrap();
+    let runtime_version = get_runtime_version(&wrapped).await.unwrap();
+
+    // Nothing is written until we're finished:
+    assert!(load_fixtures(&path).unwrap().is_empty());
+    recorder.finish().unwrap();
+
+    let recording = load_fixtures(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let methods: Vec<&str> = recording.iter().map(|call| &*call.method).collect();
+    assert_eq!(methods, ["system_properties", "state_getRuntimeVersion"]);
+
+    // Replaying the recording gives the same answers as the node did:
+    let replay = MockNode::replay(recording).await.unwrap();
+    assert_eq!(get_token_info(&replay.http_url).await.unwrap(), token);
+    assert_eq!(
+        get_runtime_version(&replay.http_url).await.unwrap(),
+        runtime_version
+    );
+}
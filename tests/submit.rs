@@ -0,0 +1,69 @@
+//! Submit extrinsics to a mock node over WS.
+
+mod common;
+
+use serde_json::json;
+use sp_keyring::AccountKeyring;
+use utils::{
+    find_extrinsic_index, load_fixtures, metadata_fixture, sign_and_watch, submit_and_watch,
+    ws_client, EncodedCall, MockNode, NonceManager,
+};
+
+#[tokio::test]
+async fn waits_for_the_extrinsic_to_be_included() {
+    let node = common::start_node().await;
+    let client = ws_client(&node.ws_url).await.unwrap();
+
+    // The node reports the transaction as ready and broadcast before it's in a block:
+    let block_hash = submit_and_watch(&client, "0x1234").await.unwrap();
+    assert_eq!(
+        block_hash,
+        "0x6e43d41109ab07611c050b08c468f11f865e6b0679350ff6e78c11b5ba413b35"
+    );
+}
+
+#[tokio::test]
+async fn finds_extrinsics_in_a_block() {
+    let node = common::start_node().await;
+    let block_hash = "0x6e43d41109ab07611c050b08c468f11f865e6b0679350ff6e78c11b5ba413b35";
+
+    let timestamp = "0x280403000b0068e5cf8b01";
+    assert_eq!(
+        find_extrinsic_index(&node.http_url, block_hash, timestamp)
+            .await
+            .unwrap(),
+        Some(0)
+    );
+    assert_eq!(
+        find_extrinsic_index(&node.http_url, block_hash, "0x1234")
+            .await
+            .unwrap(),
+        None
+    );
+}
+
+#[tokio::test]
+async fn gives_the_nonce_back_when_the_extrinsic_is_not_included() {
+    // The same node, except that the transaction pool rejects whatever we submit:
+    let rejected = json!({
+        "method": "author_submitAndWatchExtrinsic",
+        "notification_method": "author_extrinsicUpdate",
+        "notifications": ["ready", "invalid"]
+    });
+    let mut fixtures = vec![serde_json::from_value(rejected).unwrap()];
+    fixtures.extend(load_fixtures(common::FIXTURES).unwrap());
+    fixtures.push(metadata_fixture(common::METADATA).unwrap());
+    let node = MockNode::start(fixtures).await.unwrap();
+
+    let client = ws_client(&node.ws_url).await.unwrap();
+    let nonces = NonceManager::new(node.http_url.as_str());
+    let alice = AccountKeyring::Alice;
+    let call = EncodedCall(vec![0, 0]);
+
+    let result = sign_and_watch(&client, &node.http_url, &nonces, &alice.pair(), &call).await;
+    assert!(result.is_err());
+
+    // The node says that Alice's next nonce is 7, and since that transaction went nowhere,
+    // 7 is what the next one should use too:
+    assert_eq!(nonces.next(&alice.to_account_id()).await.unwrap(), 7);
+}